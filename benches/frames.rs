@@ -0,0 +1,162 @@
+// Frame times with lists far bigger than anyone should keep in one file. Handling a key and drawing
+// the frame after it has to take under 16ms on average, one frame at 60 FPS, and no single frame
+// may take longer than two of them. Run with
+//
+//     cargo bench
+//
+// which builds with the release profile. Every case prints its average and slowest frame, and the
+// run fails when one of them is over.
+
+use std::process;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use cli_todo::app::App;
+use cli_todo::backend::test_backend::{keys, TestBackend};
+use cli_todo::config::Config;
+use cli_todo::input::KeyEvent;
+use cli_todo::ui::Ui;
+use cli_todo::vec2::Vec2;
+use todo_core::activity::{self, Entry, Event, DAY};
+use todo_core::history::History;
+use todo_core::item::Item;
+
+const AVERAGE: Duration = Duration::from_millis(16);
+const SLOWEST: Duration = Duration::from_millis(33);
+
+const ITEMS: usize = 50_000;
+// How many times the keys of a case are gone through.
+const ROUNDS: usize = 20;
+
+fn titles(prefix: &str) -> Vec<String> {
+    (0..ITEMS)
+        .map(|x| {
+            format!(
+                "{} number {} with a reasonably long title #tag{}",
+                prefix,
+                x,
+                x % 30
+            )
+        })
+        .collect()
+}
+
+fn items(prefix: &str) -> Vec<Item> {
+    titles(prefix).into_iter().map(Item::from).collect()
+}
+
+fn app() -> App {
+    App::new(
+        items("item"),
+        items("done"),
+        Config::default(),
+        History::default(),
+    )
+}
+
+// Half a year of a busy list: every item added at some point, most of them done since, and a
+// habit checked off every day.
+fn activity() -> activity::Log {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let start = now.as_secs() as i64 - 180 * DAY;
+    let mut entries = Vec::new();
+    for (x, title) in titles("item").into_iter().enumerate() {
+        let time = start + (x as i64 * 180 * DAY) / ITEMS as i64;
+        entries.push(Entry {
+            time,
+            event: Event::Added,
+            title,
+        });
+    }
+    for (x, title) in titles("done").into_iter().enumerate() {
+        let time = start + (x as i64 * 180 * DAY) / ITEMS as i64;
+        for (event, after) in [(Event::Added, 0), (Event::Done, 3 * DAY)] {
+            entries.push(Entry {
+                time: time + after,
+                event,
+                title: title.clone(),
+            });
+        }
+    }
+    for day in 0..180 {
+        entries.push(Entry {
+            time: start + day * DAY,
+            event: Event::Checked,
+            title: "stretch every:day".to_string(),
+        });
+    }
+    entries.sort_by_key(|entry| entry.time);
+    activity::Log::new(entries)
+}
+
+// Runs `app` through `script` ROUNDS times, timing each key from handling it to the frame after it
+// being drawn. Returns whether it stayed within the targets.
+fn case(name: &str, mut app: App, setup: &str, script: &[KeyEvent]) -> bool {
+    let (backend, _screen) = TestBackend::new(Vec2::new(200, 60), Vec::new());
+    let mut ui = Ui::new(Box::new(backend));
+    app.size = ui.backend.size();
+    app.settle();
+    let frame = |app: &mut App, ui: &mut Ui, key: KeyEvent| {
+        app.handle_event(key);
+        ui.clear();
+        app.view(ui);
+        ui.present();
+    };
+    for key in keys(setup) {
+        frame(&mut app, &mut ui, key);
+    }
+
+    let mut total = Duration::ZERO;
+    let mut slowest = Duration::ZERO;
+    for _ in 0..ROUNDS {
+        for key in script {
+            let start = Instant::now();
+            frame(&mut app, &mut ui, *key);
+            let elapsed = start.elapsed();
+            total += elapsed;
+            slowest = slowest.max(elapsed);
+        }
+    }
+    let average = total / (ROUNDS * script.len()) as u32;
+
+    let ok = average < AVERAGE && slowest < SLOWEST;
+    println!(
+        "{:<10} {:>10.2?} average {:>10.2?} slowest{}",
+        name,
+        average,
+        slowest,
+        if ok { "" } else { "  OVER" }
+    );
+    ok
+}
+
+fn main() {
+    println!("{} TODO and {} DONE items", ITEMS, ITEMS);
+    let mut ok = true;
+
+    // Jumping to the far end and paging back up, then the same from the top.
+    let mut scroll = vec![KeyEvent::Char('G')];
+    scroll.extend([KeyEvent::PageUp; 24]);
+    scroll.push(KeyEvent::Char('g'));
+    scroll.extend([KeyEvent::PageDown; 24]);
+    ok &= case("scroll", app(), "", &scroll);
+
+    // Adding an item, prioritizing, moving, cutting and pasting it, undoing some of that, and
+    // renaming another.
+    let edit = keys("inew item\n++KKJddjjpuuA and more\nu");
+    ok &= case("edit", app(), "", &edit);
+
+    // With the age of every item shown and the stale ones marked, which both look up the activity
+    // log, and items going back and forth between TODO and DONE, which log them again.
+    let mut aged = app();
+    aged.activity = activity();
+    ok &= case("activity", aged, "W", &keys("jjj\n\t\nu\tjjjjJK"));
+
+    // The stats pages go over the whole activity log.
+    let mut stats = app();
+    stats.activity = activity();
+    ok &= case("stats", stats, "s", &keys("\t\t\t"));
+
+    if !ok {
+        process::exit(1);
+    }
+}
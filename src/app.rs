@@ -0,0 +1,3304 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, ErrorKind};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::clock;
+use crate::config::{self, Config, Header};
+use crate::hooks::{self, Hooks};
+use crate::input::KeyEvent;
+use crate::jira;
+use crate::layout::LayoutKind;
+use crate::log::log;
+use crate::open;
+use crate::picker::Picker;
+use crate::registers::Registers;
+use crate::runtime::{Message, Runtime};
+use crate::scripting::Scripts;
+use crate::sound::Sounds;
+use crate::stats;
+use crate::style::{DEFERRED, GAUGE, OVERDUE, REGULAR, SELECTION, SOON, STALE};
+use crate::switcher::Switcher;
+use crate::ui::{EditState, Ui};
+use crate::vec2::Vec2;
+use todo_core::activity::{self, Event, Period, Streak};
+use todo_core::attach;
+use todo_core::boards;
+use todo_core::checklist;
+use todo_core::command::Command;
+use todo_core::contexts;
+use todo_core::date::{self, format_span, DateTime};
+use todo_core::defer;
+use todo_core::due;
+use todo_core::estimate;
+use todo_core::fields;
+use todo_core::habit;
+use todo_core::history::History;
+use todo_core::item::Item;
+use todo_core::json::Value;
+use todo_core::list;
+use todo_core::matrix::{self, Quadrant, QUADRANTS};
+use todo_core::merge::{self, State};
+use todo_core::ops::{self, Op, Undo};
+use todo_core::pick;
+use todo_core::pin;
+use todo_core::points::{self, Points};
+use todo_core::pomodoro::{self, Phase, Pomodoro};
+use todo_core::priority;
+use todo_core::projects;
+use todo_core::query::Query;
+use todo_core::schedule;
+use todo_core::search::{Case, Search};
+use todo_core::sort::Order;
+use todo_core::status::Status;
+use todo_core::storage::{self, Format, Lists};
+use todo_core::tags;
+
+// Where the edit cursor lands when editing is started with `key`, following vim: `I` inserts at the
+// beginning, `a` appends after the first character (that's where the normal mode cursor sits) and
+// `A`/`r` append at the end of the line.
+fn edit_entry_cursor(key: KeyEvent, title: &str) -> Option<usize> {
+    match key {
+        KeyEvent::Char('I') => Some(0),
+        KeyEvent::Char('a') => Some(title.chars().next().map_or(0, char::len_utf8)),
+        KeyEvent::Char('A' | 'r') => Some(title.len()),
+        _ => None,
+    }
+}
+
+fn midnight(now: DateTime) -> DateTime {
+    DateTime {
+        hour: 0,
+        minute: 0,
+        ..now
+    }
+}
+
+fn unknown_date(text: &str) -> String {
+    format!(
+        "Can't tell when `{}` is, try tomorrow, next fri, in 3 days, sep 12 9am or 2026-10-20",
+        text
+    )
+}
+
+// A month, unless the config says otherwise.
+const STALE_AFTER: i64 = 30 * activity::DAY;
+
+// How many days of each habit the lists show.
+const HABIT_DAYS: usize = 7;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+// Keeps the stats going, they are no reason to bother the user when the log can't be written.
+fn record(activity: &mut activity::Log, event: Event, title: &str) {
+    if let Err(error) = activity.add(event, title) {
+        log!(Warn, "event=activity_failed error={:?}", error.to_string());
+    }
+}
+
+// Same for items getting a new title.
+fn rename(activity: &mut activity::Log, from: &str, to: &str) {
+    if let Err(error) = activity.rename(from, to) {
+        log!(Warn, "event=activity_failed error={:?}", error.to_string());
+    }
+}
+
+// What the keys are currently going to.
+pub enum Mode {
+    Normal,
+    // Editing the item under the cursor. `inserting` is set when the item was just created with
+    // `i`, cancelling then takes the item back as well.
+    Edit { inserting: bool },
+    // Text typed after `:`.
+    CommandLine(String),
+    // A parsed command waiting for the user to say y/n.
+    Confirmation(Command),
+    // When to bring the current TODO item back, typed after `z`.
+    Defer(String),
+    // When the current TODO item is due, typed after `!`.
+    Due(String),
+    // What to look for, typed after `/`.
+    Search(String),
+    // The Ctrl+P finder over the lists.
+    Picker(Picker),
+    // The lists there are to switch to, after `b` or `:list`.
+    Lists(Switcher),
+    // The stats screen instead of the lists.
+    Stats(Stats),
+    // TODO items in the Eisenhower matrix instead of the list, with the cursor on the `curr`th item
+    // of `quadrant`.
+    Matrix { quadrant: Quadrant, curr: usize },
+    // The steps and attachments of the current item, with the cursor on the `curr`th of them.
+    Details { curr: usize },
+    // A sync conflict copy next to the lists, side by side with them and what merging it gives.
+    Merge(Box<Merging>),
+}
+
+pub struct Merging {
+    // The conflict copy.
+    other: String,
+    // Where the state of our lists is kept.
+    state_path: String,
+    theirs: Lists,
+    merged: Lists,
+    state: State,
+    // Conflict copies to go through after this one.
+    rest: Vec<String>,
+    scroll: usize,
+    // Items the two sides disagree about, with the cursor on the `curr`th. They start out merged
+    // the way the later change says.
+    disputed: Vec<String>,
+    curr: usize,
+    // The merged title being typed for the current one, after `e`.
+    editing: Option<String>,
+}
+
+impl Merging {
+    // Where `title` is on each side, None where it isn't.
+    fn sides(&self, title: &str, ours: [&[Item]; 3]) -> [Option<Status>; 3] {
+        let find = |lists: [&[Item]; 3]| {
+            let [todos, dones, somedays] = lists;
+            [
+                (Status::Todo, todos),
+                (Status::Done, dones),
+                (Status::Someday, somedays),
+            ]
+            .into_iter()
+            .find(|(_, list)| list.iter().any(|item| item == title))
+            .map(|(status, _)| status)
+        };
+        [
+            find(ours),
+            find(slices(&self.theirs)),
+            find(slices(&self.merged)),
+        ]
+    }
+
+    // Puts `title` where `status` says in the merged lists, or takes it out with None, as the user
+    // decided just now. `like` is where it goes, an item to take the place of.
+    fn decide(&mut self, title: &str, status: Option<Status>, like: &str, now: i64) {
+        let mut at = None;
+        for list in [
+            &mut self.merged.todos,
+            &mut self.merged.dones,
+            &mut self.merged.somedays,
+        ] {
+            if let Some(index) = list.iter().position(|item| item == like) {
+                at = Some(index);
+            }
+            list.retain(|item| item != title && item != like);
+        }
+        if let Some(status) = status {
+            let list = match status {
+                Status::Todo => &mut self.merged.todos,
+                Status::Done => &mut self.merged.dones,
+                Status::Someday => &mut self.merged.somedays,
+            };
+            let at = at.map_or(list.len(), |at| cmp::min(at, list.len()));
+            list.insert(at, Item::parse(title));
+        }
+        self.state.set(title, now, status);
+    }
+}
+
+// Pages of the stats screen, Tab goes round them.
+#[derive(Clone, Copy)]
+pub enum Stats {
+    Days,
+    Weeks,
+    Heatmap,
+}
+
+// Narrows both panels down to some of the items. The others are still there, just not shown.
+#[derive(Clone, PartialEq)]
+pub enum Filter {
+    // Due by the end of today or scheduled for it.
+    Today,
+    // TODO items open for longer than they should be.
+    Stale,
+    // Items with `@context`, the context is without the @.
+    Context(String),
+    // Items tagged `#tag`, without the #.
+    Tag(String),
+    // Items in `+project`, without the +.
+    Project(String),
+    // A filter expression, under its name in the config or as it was typed.
+    Query(String, Query),
+}
+
+impl Filter {
+    fn name(&self) -> String {
+        match self {
+            Filter::Today => "today".to_string(),
+            Filter::Stale => "stale".to_string(),
+            Filter::Context(context) => format!("@{}", context),
+            Filter::Tag(tag) => format!("#{}", tag),
+            Filter::Project(project) => format!("+{}", project),
+            Filter::Query(name, _) => name.clone(),
+        }
+    }
+}
+
+// Deletes a conflict copy along with its state, if it has one.
+fn remove_copy(other: &str) -> io::Result<()> {
+    fs::remove_file(other)?;
+    match fs::remove_file(merge::state_path(other)) {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+fn slices(lists: &Lists) -> [&[Item]; 3] {
+    [&lists.todos, &lists.dones, &lists.somedays]
+}
+
+enum MergeRow {
+    Section(&'static str),
+    // The mark, the line of the item and which list it's in.
+    Item(&'static str, String, Status),
+}
+
+// The lists one under the other for the merge view. Items that aren't in the same list in `ours`
+// get a `*` in their mark.
+fn merge_rows(lists: [&[Item]; 3], ours: Option<&[MergeRow]>) -> Vec<MergeRow> {
+    let ours: Option<HashMap<&str, Status>> = ours.map(|ours| {
+        ours.iter()
+            .filter_map(|row| match row {
+                MergeRow::Item(_, item, status) => Some((item.as_str(), *status)),
+                MergeRow::Section(_) => None,
+            })
+            .collect()
+    });
+    let mut rows = Vec::new();
+    let [todos, dones, somedays] = lists;
+    for (section, status, list) in [
+        ("TODO", Status::Todo, todos),
+        ("DONE", Status::Done, dones),
+        ("SOMEDAY", Status::Someday, somedays),
+    ] {
+        if list.is_empty() {
+            continue;
+        }
+        rows.push(MergeRow::Section(section));
+        for item in list {
+            let item = item.to_string();
+            let changed = ours
+                .as_ref()
+                .is_some_and(|ours| ours.get(item.as_str()) != Some(&status));
+            let mark = match (status, changed) {
+                (Status::Done, false) => "- [x] ",
+                (Status::Done, true) => "* [x] ",
+                (_, false) => "- [ ] ",
+                (_, true) => "* [ ] ",
+            };
+            rows.push(MergeRow::Item(mark, item, status));
+        }
+    }
+    rows
+}
+
+impl Mode {
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Edit { inserting: false } => "edit",
+            Mode::Edit { inserting: true } => "insert",
+            Mode::CommandLine(_) => "command_line",
+            Mode::Confirmation(_) => "confirmation",
+            Mode::Defer(_) => "defer",
+            Mode::Due(_) => "due",
+            Mode::Search(_) => "search",
+            Mode::Picker(_) => "picker",
+            Mode::Lists(_) => "lists",
+            Mode::Stats(_) => "stats",
+            Mode::Matrix { .. } => "matrix",
+            Mode::Details { .. } => "details",
+            Mode::Merge(_) => "merge",
+        }
+    }
+
+    // Whether the keys go to a text field, where Ctrl+Z undoes typing rather than suspending.
+    fn typing(&self) -> bool {
+        match self {
+            Mode::Edit { .. }
+            | Mode::CommandLine(_)
+            | Mode::Defer(_)
+            | Mode::Due(_)
+            | Mode::Search(_)
+            | Mode::Picker(_) => true,
+            Mode::Merge(merging) => merging.editing.is_some(),
+            _ => false,
+        }
+    }
+}
+
+// The whole state of the application. Keys come in through handle_event() which is the only place
+// the state changes, view() then draws whatever the state is without touching it.
+pub struct App {
+    pub todos: Vec<Item>,
+    pub todo_curr: usize,
+    pub dones: Vec<Item>,
+    pub done_curr: usize,
+    pub somedays: Vec<Item>,
+    pub someday_curr: usize,
+    pub panel: Status,
+    pub mode: Mode,
+    pub notification: String,
+    pub quit: bool,
+    // Ctrl+Z, same story as Ctrl+C. Stopping the process is up to the main loop.
+    pub suspend: bool,
+    // Same for ringing the terminal bell.
+    pub bell: bool,
+    // Terminal size, so paging knows how many items fit on the screen.
+    pub size: Vec2,
+    // Shared by the item being edited and the command line, only one of them is open at a time.
+    edit: EditState,
+    suggestions: Vec<String>,
+    history: History,
+    abbreviations: Vec<(String, String)>,
+    templates: Vec<(String, Vec<String>)>,
+    capacity: Option<i64>,
+    // Seconds, 0 when nothing is ever stale.
+    stale_after: i64,
+    archive_after: Option<i64>,
+    filters: Vec<(String, Query)>,
+    sort: Vec<(Status, Order)>,
+    // Panels with an order in the config that were switched to manual ordering with `o`.
+    manual: Vec<Status>,
+    // Whether TODO items show how long they've been open, `W` switches it.
+    show_age: bool,
+    // The streak for the status bar along with how long the activity log was and what day it was
+    // when it was worked out. The log only grows, so until either changes it's still right.
+    streak: Cell<Option<((usize, i64), Streak)>>,
+    points: Option<Points>,
+    // For opening the issues of items mirrored from Jira.
+    jira_url: Option<String>,
+    // Where `:filter save` saves filters, None keeps them for this session only.
+    pub config_path: Option<PathBuf>,
+    // First half of a two key command like `dd`.
+    pending: Option<char>,
+    // Items cut with `dd` or yanked with `yy`. Which panel they came from doesn't matter, pasting
+    // them into the other one is how you move items across while choosing exactly where they land.
+    registers: Registers,
+    // Register picked with `"x` for the next command.
+    register_name: Option<char>,
+    pub scripts: Scripts,
+    pub hooks: Hooks,
+    sounds: Sounds,
+    pub runtime: Runtime,
+    pomodoro: Option<Pomodoro>,
+    pub pomodoros: pomodoro::Log,
+    pub activity: activity::Log,
+    // When tick() last moved the timer on.
+    ticked: Instant,
+    // Local time to the minute, for deciding which items are still deferred. tick() keeps it
+    // current.
+    pub now: DateTime,
+    pub filter: Option<Filter>,
+    // The last thing looked for with `/`, as typed and parsed, for `n` and `N`.
+    search: Option<(String, Search)>,
+    search_case: Case,
+    // The character of the last `f` or `F`, and whether it went down, for `;` and `,`.
+    jump: Option<(char, bool)>,
+    header: Header,
+    // What the lists are kept in, for reading conflict copies of them.
+    format: Format,
+    // The file the lists are in, which list that is (boards::HERE for the current directory) and
+    // the lock on it, all three changed by `:list`.
+    pub file_path: String,
+    pub list: String,
+    pub lock: Option<storage::Lock>,
+    // Where the named lists are kept, None when there's nowhere to.
+    pub lists_dir: Option<PathBuf>,
+    undo: Undo,
+    // What the keys since the last time in normal mode did to the lists, to go on the history as
+    // one change once back there, so adding an item and typing it in is taken back in one go.
+    changes: Vec<Op>,
+    // The lists as they were when the change started. Tests check that what was noted down really
+    // is what happened, a change nobody noted would break taking back anything before it.
+    #[cfg(test)]
+    undo_check: Option<Lists>,
+}
+
+impl App {
+    pub fn new(todos: Vec<Item>, dones: Vec<Item>, config: Config, history: History) -> Self {
+        Self {
+            todos,
+            todo_curr: 0,
+            dones,
+            done_curr: 0,
+            somedays: Vec::new(),
+            someday_curr: 0,
+            panel: Status::Todo,
+            mode: Mode::Normal,
+            notification: String::new(),
+            quit: false,
+            suspend: false,
+            bell: false,
+            size: Vec2::default(),
+            edit: EditState::default(),
+            suggestions: Vec::new(),
+            history,
+            abbreviations: config.abbreviations,
+            templates: config.templates,
+            capacity: config.capacity,
+            stale_after: config.stale.unwrap_or(STALE_AFTER),
+            archive_after: config.archive,
+            filters: config.filters,
+            sort: config.sort,
+            manual: Vec::new(),
+            show_age: config.age,
+            streak: Cell::new(None),
+            points: config.points,
+            jira_url: config
+                .jira
+                .map(|jira| jira.url)
+                .filter(|url| !url.is_empty()),
+            config_path: None,
+            pending: None,
+            registers: Registers::default(),
+            register_name: None,
+            scripts: Scripts::default(),
+            hooks: config.hooks,
+            sounds: config.sounds,
+            runtime: Runtime::default(),
+            pomodoro: None,
+            pomodoros: pomodoro::Log::default(),
+            activity: activity::Log::default(),
+            ticked: Instant::now(),
+            now: clock::local(),
+            filter: None,
+            search: None,
+            jump: None,
+            header: config.header,
+            undo: Undo::default(),
+            changes: Vec::new(),
+            #[cfg(test)]
+            undo_check: None,
+            search_case: config.search_case,
+            format: config.format,
+            file_path: config.format.file_name().to_string(),
+            list: boards::HERE.to_string(),
+            lock: None,
+            lists_dir: None,
+        }
+    }
+
+    // Writes the lists to their file, and what changed in them to the state merging goes by.
+    pub fn save(&self) -> io::Result<()> {
+        let lists = Lists {
+            todos: self.todos.clone(),
+            dones: self.dones.clone(),
+            somedays: self.somedays.clone(),
+        };
+        self.format.storage().save(&lists, &self.file_path)?;
+        log!(
+            Info,
+            "event=saved file={:?} todos={} dones={} somedays={}",
+            self.file_path,
+            self.todos.len(),
+            self.dones.len(),
+            self.somedays.len()
+        );
+        if let Err(error) = merge::record(&self.file_path, &lists, clock::now()) {
+            log!(Warn, "event=state_failed error={:?}", error.to_string());
+        }
+        Ok(())
+    }
+
+    // Saves the lists and opens the ones called `name` instead, boards::HERE for those in the
+    // current directory, creating them when there are none yet. Nothing changes when the other
+    // ones can't be opened. Undo starts over, what it has was done to the lists before.
+    fn switch_list(&mut self, name: &str) {
+        self.mode = Mode::Normal;
+        if name == self.list {
+            self.notification = format!("On list {} already", name);
+            return;
+        }
+        let Some(dir) = &self.lists_dir else {
+            self.notification = "No data directory to keep named lists in".to_string();
+            return;
+        };
+        let path = boards::path(dir, name, self.format);
+        let file_path = path.to_string_lossy().into_owned();
+        let opened = (|| {
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent)?;
+            }
+            let lock = storage::lock(&file_path)?;
+            let lists = match self.format.storage().load(&file_path, &mut |_, _| {}) {
+                Err(error) if error.kind() == ErrorKind::NotFound => Lists::default(),
+                result => result?,
+            };
+            Ok::<_, io::Error>((lock, lists))
+        })();
+        let (lock, lists) = match opened {
+            Ok(opened) => opened,
+            Err(error) => {
+                log!(
+                    Warn,
+                    "event=switch_failed list={:?} error={:?}",
+                    name,
+                    error.to_string()
+                );
+                self.notification = format!("Could not open list {}: {}", name, error);
+                return;
+            }
+        };
+        if let Err(error) = self.save() {
+            log!(Error, "event=save_failed error={:?}", error.to_string());
+            self.notification = format!("Could not save {}: {}", self.file_path, error);
+            return;
+        }
+        self.hooks.run(
+            &mut self.runtime,
+            "on-save",
+            Value::object([
+                ("file", self.file_path.as_str().into()),
+                ("todos", self.todos[..].into()),
+                ("dones", self.dones[..].into()),
+            ]),
+        );
+        log!(Info, "event=switched list={:?} file={:?}", name, file_path);
+        self.todos = lists.todos;
+        self.dones = lists.dones;
+        self.somedays = lists.somedays;
+        (self.todo_curr, self.done_curr, self.someday_curr) = (0, 0, 0);
+        self.panel = Status::Todo;
+        self.filter = None;
+        self.undo = Undo::default();
+        self.changes.clear();
+        #[cfg(test)]
+        {
+            self.undo_check = None;
+        }
+        self.lock = Some(lock);
+        self.file_path = file_path;
+        self.list = name.to_string();
+        self.notification = match name {
+            boards::HERE => format!("Switched to {} in this directory", self.file_path),
+            name => format!("Switched to list {}", name),
+        };
+        self.archive_done(&self.file_path.clone());
+    }
+
+    fn handle_lists(&mut self, key: KeyEvent) {
+        let Mode::Lists(switcher) = &mut self.mode else {
+            return;
+        };
+        match key {
+            KeyEvent::Enter => {
+                let name = switcher.names[switcher.curr].clone();
+                self.switch_list(&name);
+            }
+            KeyEvent::Escape | KeyEvent::Char('q' | 'b') => self.mode = Mode::Normal,
+            KeyEvent::Up | KeyEvent::Char('k') => switcher.curr = switcher.curr.saturating_sub(1),
+            KeyEvent::Down | KeyEvent::Char('j') => {
+                switcher.curr = cmp::min(switcher.curr + 1, switcher.names.len() - 1);
+            }
+            _ => {}
+        }
+    }
+
+    // Going over the whole log every frame adds up as it grows, so it's only done again once
+    // something was logged or the day is over.
+    fn streak(&self) -> Streak {
+        let now = clock::now();
+        let offset = clock::offset(now);
+        let key = (
+            self.activity.entries.len(),
+            (now + offset).div_euclid(activity::DAY),
+        );
+        match self.streak.get() {
+            Some((cached, streak)) if cached == key => streak,
+            _ => {
+                let streak = self.activity.streak(now, offset);
+                self.streak.set(Some((key, streak)));
+                streak
+            }
+        }
+    }
+
+    // How many items fit in a panel. Notification, progress and the panel header take up the first
+    // rows.
+    fn page(&self) -> usize {
+        let rows = if self.header.notification { 3 } else { 2 };
+        cmp::max(self.size.y - rows, 1) as usize
+    }
+
+    pub fn handle_event(&mut self, key: KeyEvent) {
+        log!(Debug, "event=key key={:?} mode={}", key, self.mode.name());
+        self.notification.clear();
+        // Without curses Ctrl+C doesn't raise SIGINT, it arrives as a key press instead.
+        if key == KeyEvent::Ctrl('c') {
+            self.quit = true;
+            return;
+        }
+        if key == KeyEvent::Ctrl('z') && !self.mode.typing() {
+            self.suspend = true;
+            return;
+        }
+        let (mode, panel) = (self.mode.name(), self.panel);
+        let normal = matches!(self.mode, Mode::Normal) && self.pending.is_none();
+        if normal && matches!(key, KeyEvent::Char('u') | KeyEvent::Ctrl('r')) {
+            self.undo_or_redo(key == KeyEvent::Char('u'));
+            return;
+        }
+        self.begin();
+        match &self.mode {
+            Mode::Normal => self.handle_normal(key),
+            Mode::Edit { inserting } => self.handle_edit(key, *inserting),
+            Mode::CommandLine(_) => self.handle_command_line(key),
+            Mode::Confirmation(_) => self.handle_confirmation(key),
+            Mode::Defer(_) => self.handle_defer(key),
+            Mode::Due(_) => self.handle_due(key),
+            Mode::Search(_) => self.handle_search(key),
+            Mode::Picker(_) => self.handle_picker(key),
+            Mode::Lists(_) => self.handle_lists(key),
+            Mode::Stats(page) => {
+                self.mode = match (key, page) {
+                    (KeyEvent::Tab, Stats::Days) => Mode::Stats(Stats::Weeks),
+                    (KeyEvent::Tab, Stats::Weeks) => Mode::Stats(Stats::Heatmap),
+                    (KeyEvent::Tab, Stats::Heatmap) => Mode::Stats(Stats::Days),
+                    (KeyEvent::Escape | KeyEvent::Char('s' | 'q'), _) => Mode::Normal,
+                    (_, page) => Mode::Stats(*page),
+                }
+            }
+            Mode::Matrix { quadrant, curr } => self.handle_matrix(key, *quadrant, *curr),
+            Mode::Details { curr } => self.handle_details(key, *curr),
+            Mode::Merge(_) => self.handle_merge(key),
+        }
+        self.settle();
+        self.commit();
+        if mode != self.mode.name() {
+            log!(Debug, "event=mode from={} to={}", mode, self.mode.name());
+        }
+        if panel != self.panel {
+            log!(Debug, "event=panel to={:?}", self.panel);
+        }
+    }
+
+    pub fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::HookFailed { name, error } => {
+                self.notification = format!("Hook {} failed: {}", name, error);
+            }
+            Message::Request {
+                method,
+                params,
+                reply,
+            } => {
+                log!(Info, "event=request method={:?}", method);
+                self.begin();
+                let result = self.handle_request(&method, &params);
+                self.settle();
+                self.commit();
+                // The client may have given up already.
+                let _ = reply.send(result);
+            }
+        }
+    }
+
+    // Moves the pomodoro timer on to `now` and brings back deferred items whose time has come. The
+    // main loop calls this every time round, keys or no keys, and redraws when it returns true.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.ticked);
+        self.ticked = now;
+        let before = self.now;
+        let woken = self.wake_deferred(clock::local());
+        if self.now != before && self.became_overdue(before) {
+            self.play("overdue");
+        }
+        let Some(pomodoro) = &mut self.pomodoro else {
+            return woken;
+        };
+        let shown = pomodoro.remaining.as_secs();
+        let ended = pomodoro.advance(elapsed);
+        let redraw = woken || ended.is_some() || pomodoro.remaining.as_secs() != shown;
+        let item = pomodoro.item.clone();
+        match ended {
+            Some(Phase::Work) => {
+                if let Err(error) = self.pomodoros.add(&item) {
+                    log!(
+                        Warn,
+                        "event=pomodoro_log_failed error={:?}",
+                        error.to_string()
+                    );
+                }
+                self.notification = format!(
+                    "Pomodoro #{} on {} done, take a break",
+                    self.pomodoros.count(&item),
+                    item
+                );
+            }
+            Some(Phase::Break) => self.notification = format!("Break is over, back to {}", item),
+            None => return redraw,
+        }
+        log!(Info, "event=pomodoro phase={:?}", ended);
+        self.play("pomodoro");
+        let phase = match ended {
+            Some(Phase::Work) => Phase::Break,
+            _ => Phase::Work,
+        };
+        self.hooks.run(
+            &mut self.runtime,
+            "on-pomodoro",
+            Value::object([
+                ("title", item.as_str().into()),
+                ("phase", phase.name().into()),
+                ("completed", self.pomodoros.count(&item).into()),
+            ]),
+        );
+        redraw
+    }
+
+    // Takes `defer:` off the items deferred until `now` or earlier, once a minute is enough.
+    fn wake_deferred(&mut self, now: DateTime) -> bool {
+        if now == self.now {
+            return false;
+        }
+        self.now = now;
+        let editing = match self.mode {
+            Mode::Edit { .. } if self.panel == Status::Todo => Some(self.todo_curr),
+            _ => None,
+        };
+        let mut woken = Vec::new();
+        for (index, item) in self.todos.iter_mut().enumerate() {
+            // The item being edited gets its turn a minute later.
+            if Some(index) != editing && defer::until(item).is_some_and(|until| until <= now) {
+                let from = item.clone();
+                defer::set(&mut item.title, None);
+                woken.push((from, item.clone()));
+            }
+        }
+        // Nobody asked for it, so it's nothing to take back. Taking back the defer still has to
+        // work, with the item as it is now.
+        for (from, to) in &woken {
+            rename(&mut self.activity, from, to);
+            self.undo.rebase(from, to);
+            for op in &mut self.changes {
+                op.rebase(from, to);
+            }
+            #[cfg(test)]
+            if let Some(lists) = &mut self.undo_check {
+                for item in lists.todos.iter_mut().filter(|item| *item == from) {
+                    item.clone_from(to);
+                }
+            }
+        }
+        match &woken[..] {
+            [] => return false,
+            [(_, item)] => self.notification = format!("{} is back", item),
+            items => self.notification = format!("{} deferred items are back", items.len()),
+        }
+        log!(Info, "event=undeferred count={}", woken.len());
+        true
+    }
+
+    // Whether any TODO item that wasn't overdue at `before` is now.
+    fn became_overdue(&self, before: DateTime) -> bool {
+        let overdue = |item: &Item, now| due::countdown(item, now).is_some_and(|(_, late)| late);
+        self.todos
+            .iter()
+            .any(|item| overdue(item, self.now) && !overdue(item, before))
+    }
+
+    fn play(&mut self, event: &str) {
+        self.bell |= self.sounds.play(&mut self.runtime, event);
+    }
+
+    // How long the main loop may sleep before the timer shown in the header has to change.
+    pub fn timer_timeout(&self) -> Option<Duration> {
+        self.pomodoro.as_ref().map(|pomodoro| {
+            let fraction = Duration::from_nanos(pomodoro.remaining.subsec_nanos() as u64);
+            if fraction.is_zero() {
+                Duration::from_secs(1)
+            } else {
+                fraction
+            }
+        })
+    }
+
+    // Lets scripts, hooks and the history know about the new TODO item at `index`.
+    fn added(&mut self, index: usize) {
+        let item = &mut self.todos[index];
+        if let Err(error) = self.scripts.on_add(item) {
+            log!(Warn, "event=script_failed error={:?}", error);
+            self.notification = error;
+        }
+        self.hooks
+            .run(&mut self.runtime, "on-add", hooks::item(item, Status::Todo));
+        record(&mut self.activity, Event::Added, item);
+        if let Err(error) = self.history.add(&item.to_string()) {
+            log!(Warn, "event=history_failed error={:?}", error.to_string());
+            self.notification = format!("Could not save history: {}", error);
+        }
+    }
+
+    // Same for the item that was just moved to the end of DONE.
+    fn completed(&mut self) {
+        let Some(done) = self.dones.last() else {
+            return;
+        };
+        record(&mut self.activity, Event::Done, done);
+        self.hooks.run(
+            &mut self.runtime,
+            "on-done",
+            hooks::item(done, Status::Done),
+        );
+        if let Err(error) = self.scripts.on_complete(done) {
+            log!(Warn, "event=script_failed error={:?}", error);
+            self.notification = error;
+        }
+        self.play("done");
+    }
+
+    // Appends the items of template `name` to TODO and returns how many there were.
+    fn add_template(&mut self, name: &str) -> Result<usize, String> {
+        let items = self
+            .templates
+            .iter()
+            .find(|(other, _)| other == name)
+            .map(|(_, items)| items.clone())
+            .ok_or_else(|| {
+                let names: Vec<&str> = self.templates.iter().map(|(name, _)| &name[..]).collect();
+                match &names[..] {
+                    [] => format!("No template {}, there are none in the config", name),
+                    names => format!("No template {}, try one of {}", name, names.join(", ")),
+                }
+            })?;
+        log!(Info, "event=template name={:?} items={}", name, items.len());
+        let count = items.len();
+        for item in items {
+            self.add(item);
+        }
+        Ok(count)
+    }
+
+    // Adds `item` to the end of TODO.
+    fn add(&mut self, item: String) {
+        self.todos.push(Item::parse(&item));
+        let at = self.todos.len() - 1;
+        self.added(at);
+        self.changes.push(Op::Insert {
+            status: Status::Todo,
+            at,
+            item: self.todos[at].clone(),
+        });
+    }
+
+    // Moves the DONE items done longer ago than the config allows to the archive next to
+    // `file_path`, and tells how many. Items the activity log doesn't know when were done stay.
+    pub fn archive_done(&mut self, file_path: &str) {
+        let Some(after) = self.archive_after else {
+            return;
+        };
+        let now = clock::now();
+        let activity = &self.activity;
+        let is_old = |item: &Item| {
+            activity
+                .closed(item)
+                .is_some_and(|closed| now - closed >= after)
+        };
+        let old: Vec<Item> = self
+            .dones
+            .iter()
+            .filter(|item| is_old(item))
+            .cloned()
+            .collect();
+        if old.is_empty() {
+            return;
+        }
+        match storage::archive(&old, file_path) {
+            Ok(()) => {
+                self.dones.retain(|item| !is_old(item));
+                self.clamp_cursors();
+                log!(Info, "event=archived count={}", old.len());
+                self.notification = format!(
+                    "Archived {} DONE items to {}",
+                    old.len(),
+                    storage::archive_path(file_path)
+                );
+            }
+            Err(error) => {
+                log!(Warn, "event=archive_failed error={:?}", error.to_string());
+                self.notification = format!("Could not archive DONE items: {}", error);
+            }
+        }
+    }
+
+    // Shows only what `source` lets through, the saved filter of that name or else a filter
+    // expression, or everything when it's empty.
+    fn apply_filter(&mut self, source: &str) {
+        if source.is_empty() {
+            self.filter = None;
+            self.notification = "Showing everything".to_string();
+            return;
+        }
+        let saved = self.filters.iter().find(|(name, _)| name == source);
+        let query = match saved {
+            Some((_, query)) => Ok(query.clone()),
+            None => Query::parse(source, self.now),
+        };
+        match query {
+            Ok(query) => {
+                self.filter = Some(Filter::Query(source.to_string(), query));
+                self.notification = format!("Only {}, :filter shows everything again", source);
+            }
+            Err(error) => self.notification = error,
+        }
+    }
+
+    fn save_filter(&mut self, name: String, source: &str) {
+        let query = match Query::parse(source, self.now) {
+            Ok(query) => query,
+            Err(error) => {
+                self.notification = error;
+                return;
+            }
+        };
+        self.notification = match &self.config_path {
+            Some(path) => match config::save_filter(path, &name, source) {
+                Ok(()) => format!("Saved filter {}, v switches between filters", name),
+                Err(error) => {
+                    log!(
+                        Warn,
+                        "event=filter_save_failed error={:?}",
+                        error.to_string()
+                    );
+                    format!("Could not save filter {}: {}", name, error)
+                }
+            },
+            None => format!("Saved filter {} until you quit", name),
+        };
+        self.filters.retain(|(other, _)| *other != name);
+        self.filters.push((name.clone(), query.clone()));
+        self.filter = Some(Filter::Query(name, query));
+    }
+
+    // Requests from the control socket and D-Bus.
+    fn handle_request(&mut self, method: &str, params: &Value) -> Result<Value, String> {
+        let title = || {
+            let title = params
+                .get("title")
+                .and_then(Value::as_str)
+                .ok_or("missing `title` parameter")?;
+            if title.is_empty() || title.contains(['\n', '\r']) {
+                return Err("title must be a single non-empty line".to_string());
+            }
+            Ok(title.to_string())
+        };
+        match method {
+            "add" => {
+                let mut title = title()?;
+                fields::resolve_dates(&mut title, self.now)
+                    .map_err(|value| unknown_date(&value))?;
+                self.add(title);
+                self.notification = "Added 1 item from outside".to_string();
+                Ok(Value::Null)
+            }
+            "template" => {
+                let name = params
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or("missing `name` parameter")?;
+                let count = self.add_template(name)?;
+                self.notification = format!("Added {} items from {}", count, name);
+                Ok(count.into())
+            }
+            "complete" => {
+                let title = title()?;
+                // The item being edited has to stay where it is.
+                if let Mode::Edit { .. } = self.mode {
+                    return Err("busy editing an item, try again in a moment".to_string());
+                }
+                let mut index = self
+                    .todos
+                    .iter()
+                    .position(|item| item == title.as_str())
+                    .ok_or_else(|| format!("no TODO item `{}`", title))?;
+                self.changes.push(Op::Transfer {
+                    from: (Status::Todo, index),
+                    to: (Status::Done, self.dones.len()),
+                    item: self.todos[index].clone(),
+                });
+                list::transfer(&mut self.dones, &mut self.todos, &mut index);
+                if index < self.todo_curr {
+                    self.todo_curr -= 1;
+                }
+                self.clamp_cursors();
+                self.completed();
+                self.notification = "Completed 1 item from outside".to_string();
+                Ok(Value::Null)
+            }
+            "list" => Ok(Value::object([
+                ("todos", self.todos[..].into()),
+                ("dones", self.dones[..].into()),
+            ])),
+            _ => Err(format!("unknown method `{}`", method)),
+        }
+    }
+
+    fn handle_edit(&mut self, key: KeyEvent, inserting: bool) {
+        let (list, curr, suggestions) = match self.panel {
+            Status::Todo => (&mut self.todos, &mut self.todo_curr, &self.suggestions[..]),
+            Status::Done => (&mut self.dones, &mut self.done_curr, &[][..]),
+            Status::Someday => (&mut self.somedays, &mut self.someday_curr, &[][..]),
+        };
+        let Some(item) = list.get_mut(*curr) else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        match key {
+            KeyEvent::Escape => {
+                *item = Item::parse(&self.edit.original);
+                self.mode = Mode::Normal;
+                if inserting {
+                    self.changes.push(Op::Delete {
+                        status: self.panel,
+                        at: *curr,
+                        item: item.clone(),
+                    });
+                    list::delete(list, curr);
+                }
+            }
+            KeyEvent::Enter => {
+                self.mode = Mode::Normal;
+                if let Err(value) = fields::resolve_dates(&mut item.title, self.now) {
+                    self.notification = unknown_date(&value);
+                }
+                // Typed as the whole line, the due date is read out of it again.
+                *item = Item::parse(&item.title);
+                if inserting {
+                    // New items belong where they were added, the filter shouldn't hide them.
+                    match &self.filter {
+                        Some(Filter::Today) if !schedule::is_today(item, self.now) => {
+                            schedule::set(&mut item.title, Some(midnight(self.now)));
+                        }
+                        Some(Filter::Context(context)) if !contexts::has(item, context) => {
+                            item.title.push_str(" @");
+                            item.title.push_str(context);
+                        }
+                        Some(Filter::Tag(tag)) => tags::set(&mut item.title, tag, true),
+                        Some(Filter::Project(project)) if !projects::has(item, project) => {
+                            item.title.push_str(" +");
+                            item.title.push_str(project);
+                        }
+                        _ => {}
+                    }
+                    self.suggestions.clear();
+                    let index = *curr;
+                    self.added(index);
+                }
+                // Typing isn't noted down key by key, the edit is one change from what it was
+                // to what it is now.
+                let (status, at) = (self.panel, self.list(self.panel).1);
+                let to = self.list(status).0[at].clone();
+                let from = Item::parse(&self.edit.original);
+                if to != from {
+                    let op = Op::Rename {
+                        status,
+                        at,
+                        from,
+                        to,
+                    };
+                    // A new item went in the activity log with the title it ended up with.
+                    if inserting {
+                        self.changes.push(op);
+                    } else {
+                        self.note(op);
+                    }
+                }
+            }
+            key => {
+                self.edit
+                    .handle_key(&mut item.title, key, suggestions, &self.abbreviations);
+            }
+        }
+    }
+
+    fn handle_command_line(&mut self, key: KeyEvent) {
+        let Mode::CommandLine(buffer) = &mut self.mode else {
+            return;
+        };
+        match key {
+            KeyEvent::Enter => match Command::parse(buffer) {
+                Ok(Command::Template(name)) => {
+                    self.mode = Mode::Normal;
+                    self.notification = match self.add_template(&name) {
+                        Ok(count) => format!("Added {} items from {}", count, name),
+                        Err(error) => error,
+                    };
+                }
+                Ok(Command::Filter(source)) => {
+                    self.mode = Mode::Normal;
+                    self.apply_filter(&source);
+                }
+                Ok(Command::Attach(path)) => {
+                    self.mode = Mode::Normal;
+                    let (panel, curr) = (self.panel, self.list(self.panel).1);
+                    let attached =
+                        self.edit_item(panel, curr, |item| attach::add(&mut item.title, &path));
+                    self.notification = match attached {
+                        Some(()) => format!("Attached {}, x shows the attachments", path),
+                        None => "Nothing to attach it to".to_string(),
+                    };
+                }
+                Ok(Command::Sort(order)) => {
+                    self.mode = Mode::Normal;
+                    let title = self.header.title(self.panel).to_string();
+                    let panel = self.panel;
+                    let (list, curr) = match panel {
+                        Status::Todo => (&mut self.todos, &mut self.todo_curr),
+                        Status::Done => (&mut self.dones, &mut self.done_curr),
+                        Status::Someday => (&mut self.somedays, &mut self.someday_curr),
+                    };
+                    self.notification = match order.order(list, &self.activity, self.now) {
+                        Some(sorted) => {
+                            self.changes.extend(ops::reorder(panel, list, &sorted));
+                            list::reorder(list, curr, sorted);
+                            format!("Sorted {} by {}, u puts it back", title, order.name())
+                        }
+                        None => format!("{} is sorted by {} already", title, order.name()),
+                    };
+                }
+                Ok(Command::SaveFilter(name, source)) => {
+                    self.mode = Mode::Normal;
+                    self.save_filter(name, &source);
+                }
+                Ok(Command::List(name)) => self.switch_list(&name),
+                Ok(Command::Lists) => {
+                    self.mode = Mode::Lists(Switcher::new(self.lists_dir.as_deref(), &self.list));
+                }
+                Ok(command) => {
+                    self.notification = format!(
+                        "{} (y/n)",
+                        command.describe(&self.todos, &self.dones, &self.somedays, &self.panel)
+                    );
+                    self.mode = Mode::Confirmation(command);
+                }
+                Err(error) => {
+                    // Commands defined by scripts run right away, there's no telling what they
+                    // would do to describe it in a prompt.
+                    let buffer = buffer.clone();
+                    let scripted = self.rewrite(|app| {
+                        app.scripts
+                            .command(&buffer, &mut app.todos, &mut app.dones, app.panel)
+                    });
+                    self.notification = match scripted {
+                        Some(Ok(message)) => message,
+                        Some(Err(error)) => {
+                            log!(Warn, "event=script_failed error={:?}", error);
+                            error
+                        }
+                        None => error,
+                    };
+                    self.mode = Mode::Normal;
+                    self.clamp_cursors();
+                }
+            },
+            KeyEvent::Escape => self.mode = Mode::Normal,
+            key => {
+                self.edit.handle_key(buffer, key, &[], &self.abbreviations);
+            }
+        }
+    }
+
+    fn handle_confirmation(&mut self, key: KeyEvent) {
+        let Mode::Confirmation(command) = std::mem::replace(&mut self.mode, Mode::Normal) else {
+            return;
+        };
+        if key == KeyEvent::Char('y') {
+            log!(Info, "event=command command={:?}", command);
+            // Both end up with DONE items either finished or gone.
+            let fired = match command {
+                Command::AllDone => Some(("on-done", &self.todos)),
+                Command::PurgeDone => Some(("on-delete", &self.dones)),
+                _ => None,
+            };
+            if let Some((hook, items)) = fired {
+                for item in items {
+                    self.hooks
+                        .run(&mut self.runtime, hook, hooks::item(item, Status::Done));
+                }
+            }
+            let recorded = match command {
+                Command::AllDone => Some((Event::Done, &self.todos)),
+                Command::AllTodo => Some((Event::Undone, &self.dones)),
+                _ => None,
+            };
+            if let Some((event, items)) = recorded {
+                for item in items {
+                    record(&mut self.activity, event, item);
+                }
+            }
+            self.notification = self.rewrite(|app| {
+                command.run(
+                    &mut app.todos,
+                    &mut app.todo_curr,
+                    &mut app.dones,
+                    &mut app.done_curr,
+                    &mut app.somedays,
+                    &app.panel,
+                )
+            });
+        } else {
+            self.notification = "Cancelled".to_string();
+        }
+    }
+
+    fn handle_defer(&mut self, key: KeyEvent) {
+        let Mode::Defer(buffer) = &mut self.mode else {
+            return;
+        };
+        match key {
+            KeyEvent::Enter => {
+                let text = buffer.trim().to_string();
+                self.mode = Mode::Normal;
+                self.defer(&text);
+            }
+            KeyEvent::Escape => self.mode = Mode::Normal,
+            key => {
+                self.edit.handle_key(buffer, key, &[], &self.abbreviations);
+            }
+        }
+    }
+
+    fn handle_due(&mut self, key: KeyEvent) {
+        let Mode::Due(buffer) = &mut self.mode else {
+            return;
+        };
+        match key {
+            KeyEvent::Enter => {
+                let text = buffer.trim().to_string();
+                self.mode = Mode::Normal;
+                self.set_due(&text);
+            }
+            KeyEvent::Escape => self.mode = Mode::Normal,
+            key => {
+                self.edit.handle_key(buffer, key, &[], &self.abbreviations);
+            }
+        }
+    }
+
+    fn handle_search(&mut self, key: KeyEvent) {
+        let Mode::Search(buffer) = &mut self.mode else {
+            return;
+        };
+        match key {
+            KeyEvent::Enter => {
+                let pattern = buffer.clone();
+                self.mode = Mode::Normal;
+                match Search::parse(&pattern, self.search_case) {
+                    Ok(search) => {
+                        if !self.find(true, |item| search.matches(item)) {
+                            self.notification = format!("Pattern not found: {}", pattern);
+                        }
+                        self.search = Some((pattern, search));
+                    }
+                    Err(error) => self.notification = error,
+                }
+            }
+            KeyEvent::Escape => self.mode = Mode::Normal,
+            key => {
+                self.edit.handle_key(buffer, key, &[], &self.abbreviations);
+            }
+        }
+    }
+
+    // Every item the filter lets through, for the finder to pick from.
+    fn pickable(&self) -> Vec<(Status, usize, &str)> {
+        [Status::Todo, Status::Done, Status::Someday]
+            .into_iter()
+            .flat_map(|panel| {
+                let (list, _) = self.list(panel);
+                list.iter()
+                    .enumerate()
+                    .filter(move |&(index, _)| self.shown(panel, index))
+                    .map(move |(index, item)| (panel, index, item.title.as_str()))
+            })
+            .collect()
+    }
+
+    fn handle_picker(&mut self, key: KeyEvent) {
+        let Mode::Picker(picker) = &self.mode else {
+            return;
+        };
+        let found: Vec<(Status, usize)> = picker
+            .matches(self.pickable())
+            .into_iter()
+            .map(|(panel, index, _)| (panel, index))
+            .collect();
+        let Mode::Picker(picker) = &mut self.mode else {
+            return;
+        };
+        match key {
+            KeyEvent::Enter => {
+                if let Some(&(panel, index)) = found.get(picker.curr) {
+                    self.panel = panel;
+                    *self.curr_mut(panel) = index;
+                }
+                self.mode = Mode::Normal;
+            }
+            KeyEvent::Escape => self.mode = Mode::Normal,
+            KeyEvent::Up | KeyEvent::Ctrl('p') => picker.curr = picker.curr.saturating_sub(1),
+            KeyEvent::Down | KeyEvent::Ctrl('n') => {
+                picker.curr = cmp::min(picker.curr + 1, found.len().saturating_sub(1));
+            }
+            key => {
+                if self.edit.handle_key(&mut picker.query, key, &[], &[]) {
+                    picker.curr = 0;
+                }
+            }
+        }
+    }
+
+    // Moves the cursor to the next item of the panel that `matches`, or the one before, going round
+    // past the end. Items the filter hides don't count.
+    fn find(&mut self, forward: bool, matches: impl Fn(&str) -> bool) -> bool {
+        let (list, curr) = self.list(self.panel);
+        let len = list.len();
+        let found = (1..=len)
+            .map(|step| {
+                if forward {
+                    (curr + step) % len
+                } else {
+                    (curr + len - step % len) % len
+                }
+            })
+            .find(|&index| matches(&list[index]) && self.shown(self.panel, index));
+        if let Some(index) = found {
+            *self.curr_mut(self.panel) = index;
+        }
+        found.is_some()
+    }
+
+    // Moves to the next item matching the last search, or the one before, going on into the other
+    // panels (SOMEDAY too when it has anything) past the end of this one and round to the start.
+    fn search_next(&mut self, forward: bool) {
+        let Some((pattern, search)) = &self.search else {
+            self.notification = "No search to repeat, / searches first".to_string();
+            return;
+        };
+        let mut panels = vec![Status::Todo, Status::Done];
+        if !self.somedays.is_empty() {
+            panels.push(Status::Someday);
+        }
+        let places: Vec<(Status, usize)> = panels
+            .iter()
+            .flat_map(|&panel| (0..self.list(panel).0.len()).map(move |index| (panel, index)))
+            .collect();
+        let here = (self.panel, self.list(self.panel).1);
+        let len = places.len();
+        let curr = places.iter().position(|&place| place == here).unwrap_or(0);
+        let found = (1..=len)
+            .map(|step| {
+                if forward {
+                    (curr + step) % len
+                } else {
+                    (curr + len - step % len) % len
+                }
+            })
+            .find(|&at| {
+                let (panel, index) = places[at];
+                search.matches(&self.list(panel).0[index]) && self.shown(panel, index)
+            });
+        let Some(at) = found else {
+            self.notification = format!("Pattern not found: {}", pattern);
+            return;
+        };
+        if forward && at <= curr {
+            self.notification = "Search went round to the top".to_string();
+        } else if !forward && at >= curr {
+            self.notification = "Search went round to the bottom".to_string();
+        }
+        let (panel, index) = places[at];
+        self.panel = panel;
+        *self.curr_mut(panel) = index;
+    }
+
+    // Moves the cursor to the next item starting with `c`, or the one before, whatever the case.
+    fn jump_to(&mut self, c: char, forward: bool) {
+        let c = c.to_lowercase().next().unwrap_or(c);
+        let starts = |item: &str| {
+            let first = item.chars().next().and_then(|x| x.to_lowercase().next());
+            first == Some(c)
+        };
+        if !self.find(forward, starts) {
+            self.notification = format!("No item starting with {}", c);
+        }
+    }
+
+    // Makes the current TODO item due at `text`, or not due at all when that's empty.
+    fn set_due(&mut self, text: &str) {
+        let at = self.todo_curr;
+        let Some(item) = self.todos.get(at) else {
+            return;
+        };
+        if text.is_empty() {
+            if item.due.is_some() {
+                self.edit_item(Status::Todo, at, |item| item.due = None);
+                self.notification = format!("{} isn't due any more", self.todos[at]);
+            }
+            return;
+        }
+        let due = match DateTime::parse(text) {
+            Some(due) => Some(due),
+            None => date::parse_when(text, self.now),
+        };
+        match due {
+            Some(due) => {
+                self.edit_item(Status::Todo, at, |item| item.due = Some(due));
+                self.notification = format!("Due {}", due.to_item().replace('T', " "));
+            }
+            None => self.notification = unknown_date(text),
+        }
+    }
+
+    // Defers the current TODO item until `text`, or brings it back when that's empty.
+    fn defer(&mut self, text: &str) {
+        let at = self.todo_curr;
+        let Some(item) = self.todos.get(at) else {
+            return;
+        };
+        if text.is_empty() {
+            if defer::until(item).is_some() {
+                self.edit_item(Status::Todo, at, |item| defer::set(&mut item.title, None));
+                self.notification = format!("{} is back", self.todos[at]);
+            }
+            return;
+        }
+        match date::parse_when(text, self.now) {
+            Some(until) if until > self.now => {
+                self.edit_item(Status::Todo, at, |item| {
+                    defer::set(&mut item.title, Some(until))
+                });
+                // Out of the way at the bottom of the list until then, with the other deferred
+                // ones.
+                let item = self.todos.remove(at);
+                self.changes.push(Op::Drag {
+                    status: Status::Todo,
+                    from: at,
+                    to: self.todos.len(),
+                    item: item.clone(),
+                });
+                self.todos.push(item);
+                self.clamp_cursors();
+                self.notification = format!("Deferred until {}", until);
+            }
+            Some(until) => self.notification = format!("{} has already passed", until),
+            None => {
+                self.notification = unknown_date(text);
+            }
+        }
+    }
+
+    // The items of `panel` and where its cursor is.
+    fn list(&self, panel: Status) -> (&Vec<Item>, usize) {
+        match panel {
+            Status::Todo => (&self.todos, self.todo_curr),
+            Status::Done => (&self.dones, self.done_curr),
+            Status::Someday => (&self.somedays, self.someday_curr),
+        }
+    }
+
+    // Runs `f` on the lists in one piece.
+    fn with_lists<T>(&mut self, f: impl FnOnce(&mut Lists) -> T) -> T {
+        let mut lists = Lists {
+            todos: mem::take(&mut self.todos),
+            dones: mem::take(&mut self.dones),
+            somedays: mem::take(&mut self.somedays),
+        };
+        let result = f(&mut lists);
+        self.todos = lists.todos;
+        self.dones = lists.dones;
+        self.somedays = lists.somedays;
+        result
+    }
+
+    // A key or request in normal mode starts a new change. Whatever was noted down before without
+    // one, like putting the lists in order once they're loaded, is nothing to take back.
+    fn begin(&mut self) {
+        if !matches!(self.mode, Mode::Normal) {
+            return;
+        }
+        self.changes.clear();
+        #[cfg(test)]
+        {
+            self.undo_check = Some(self.with_lists(|lists| lists.clone()));
+        }
+    }
+
+    // Back in normal mode the change is over and goes on the history.
+    fn commit(&mut self) {
+        if !matches!(self.mode, Mode::Normal) {
+            return;
+        }
+        let op = ops::batch(mem::take(&mut self.changes));
+        #[cfg(test)]
+        if let Some(mut before) = self.undo_check.take() {
+            if let Some(op) = &op {
+                assert!(op.apply(&mut before), "{:?} doesn't fit the lists", op);
+            }
+            let after = self.with_lists(|lists| lists.clone());
+            assert_eq!(before, after, "the lists changed without a note for undo");
+        }
+        if let Some(op) = op {
+            self.undo.record(op);
+        }
+    }
+
+    // Changes item `at` of `status` with `f` and notes down what it did. None when there's no such
+    // item.
+    fn edit_item<T>(
+        &mut self,
+        status: Status,
+        at: usize,
+        f: impl FnOnce(&mut Item) -> T,
+    ) -> Option<T> {
+        let item = self.list_mut(status).0.get_mut(at)?;
+        let from = item.clone();
+        let result = f(item);
+        if *item != from {
+            let to = item.clone();
+            self.note(Op::Rename {
+                status,
+                at,
+                from,
+                to,
+            });
+        }
+        Some(result)
+    }
+
+    // Notes down `op` for undo. The activity log hears about the items it gave a new title, changing
+    // the due date or priority doesn't make an item new.
+    fn note(&mut self, op: Op) {
+        for (from, to) in op.renames() {
+            rename(&mut self.activity, from, to);
+        }
+        self.changes.push(op);
+    }
+
+    // Lets `f` do anything at all to the lists, like commands and scripts can, and works out what
+    // that was from the lists before and after.
+    fn rewrite<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let before = self.with_lists(|lists| lists.clone());
+        let result = f(self);
+        if let Some(op) = self.with_lists(|lists| ops::diff(&before, lists)) {
+            self.note(op);
+        }
+        result
+    }
+
+    // Takes the last change back with `u`, or does the last one taken back again with Ctrl+R, and
+    // puts the cursor on the item it was about.
+    fn undo_or_redo(&mut self, undo: bool) {
+        let mut history = mem::take(&mut self.undo);
+        let done = self.with_lists(|lists| {
+            let done = if undo {
+                history.undo(lists)
+            } else {
+                history.redo(lists)
+            };
+            done.map(|done| done.cloned())
+        });
+        self.undo = history;
+        let op = match done {
+            None if undo => {
+                self.notification = "Nothing to undo".to_string();
+                return;
+            }
+            None => {
+                self.notification = "Nothing to redo".to_string();
+                return;
+            }
+            Some(Err(error)) => {
+                self.notification = error;
+                return;
+            }
+            Some(Ok(op)) => op,
+        };
+        let applied = if undo { op.inverse() } else { op.clone() };
+        for (from, to) in applied.renames() {
+            rename(&mut self.activity, from, to);
+        }
+        let (status, at) = applied.place();
+        if status != Status::Someday || self.panel == Status::Someday {
+            self.panel = status;
+        }
+        *self.curr_mut(status) = at;
+        self.clamp_cursors();
+        self.settle();
+        // The lists are back the way they were, which was in order then too.
+        self.changes.clear();
+        let what = match &op {
+            Op::Insert { item, .. } => format!("adding {}", item),
+            Op::Delete { item, .. } => format!("deleting {}", item),
+            Op::Rename { to, .. } => format!("the edit of {}", to),
+            Op::Drag { item, .. } => format!("moving {}", item),
+            Op::Transfer { to, item, .. } => {
+                format!("moving {} to {}", item, self.header.title(to.0))
+            }
+            Op::Splice(_) | Op::Batch(_) => "the last change".to_string(),
+        };
+        self.notification = if undo {
+            format!("Undid {}, Ctrl+R does it again", what)
+        } else {
+            format!("Redid {}", what)
+        };
+    }
+
+    fn lists(&self) -> [&[Item]; 3] {
+        [&self.todos, &self.dones, &self.somedays]
+    }
+
+    fn curr_mut(&mut self, panel: Status) -> &mut usize {
+        match panel {
+            Status::Todo => &mut self.todo_curr,
+            Status::Done => &mut self.done_curr,
+            Status::Someday => &mut self.someday_curr,
+        }
+    }
+
+    // Shows only the items with the next tag of all the lists, or the previous one, going back to
+    // everything after the last one.
+    // Or with `project` set the next `+project`.
+    fn cycle_tag(&mut self, forward: bool, project: bool) {
+        let items = self.todos.iter().chain(&self.dones).chain(&self.somedays);
+        let all = if project {
+            projects::all(items)
+        } else {
+            tags::all(items)
+        };
+        let position = match &self.filter {
+            Some(Filter::Tag(tag)) if !project => all.iter().position(|other| other == tag),
+            Some(Filter::Project(name)) if project => all.iter().position(|other| other == name),
+            _ => None,
+        };
+        let next = match (position, forward) {
+            (None, true) => all.first(),
+            (None, false) => all.last(),
+            (Some(position), true) => all.get(position + 1),
+            (Some(position), false) => position.checked_sub(1).and_then(|x| all.get(x)),
+        };
+        let (key, kind) = if project {
+            ('p', "project")
+        } else {
+            ('t', "tag")
+        };
+        self.filter = next.map(|name| match project {
+            true => Filter::Project(name.to_string()),
+            false => Filter::Tag(name.to_string()),
+        });
+        self.notification = match &self.filter {
+            Some(filter) => format!(
+                "Only {}, ]{} for the next {} and [{} the one before",
+                filter.name(),
+                key,
+                kind,
+                key
+            ),
+            None if all.is_empty() && project => "No +projects anywhere".to_string(),
+            None if all.is_empty() => "No #tags anywhere".to_string(),
+            None => format!("All {}s", kind),
+        };
+    }
+
+    // Whether item `index` of `panel` is shown with the current filter. The item being edited always
+    // is, it shouldn't vanish halfway through typing.
+    fn shown(&self, panel: Status, index: usize) -> bool {
+        let Some(filter) = &self.filter else {
+            return true;
+        };
+        let (list, curr) = self.list(panel);
+        if let Mode::Edit { .. } = self.mode {
+            if panel == self.panel && index == curr {
+                return true;
+            }
+        }
+        match filter {
+            Filter::Today => schedule::is_today(&list[index], self.now),
+            Filter::Stale => panel == Status::Todo && self.is_stale(&list[index], clock::now()),
+            Filter::Context(context) => contexts::has(&list[index], context),
+            Filter::Tag(tag) => tags::has(&list[index], tag),
+            Filter::Project(project) => projects::has(&list[index], project),
+            Filter::Query(_, query) => query.matches(&list[index], panel, self.now),
+        }
+    }
+
+    // Whether a TODO item has been open for stale_after or longer at unix time `now`. The activity
+    // log is what knows when items were added, so items from before it are never stale. Deferring
+    // one puts it off until it's back, still as old as it was.
+    fn is_stale(&self, item: &str, now: i64) -> bool {
+        self.stale_after > 0
+            && !habit::is_habit(item)
+            && !defer::is_deferred(item, self.now)
+            && self
+                .activity
+                .opened(item)
+                .is_some_and(|opened| now - opened >= self.stale_after)
+    }
+
+    // Indices of the items of `panel` the filter lets through.
+    fn filtered(&self, panel: Status) -> Vec<usize> {
+        (0..self.list(panel).0.len())
+            .filter(|&index| self.shown(panel, index))
+            .collect()
+    }
+
+    // Where a movement key takes the cursor when only the filtered items are shown, None for keys
+    // that don't move it.
+    fn filtered_move(&self, key: KeyEvent, page: usize) -> Option<usize> {
+        let (_, curr) = self.list(self.panel);
+        let shown = self.filtered(self.panel);
+        // The first one at the cursor or below it.
+        let at = shown.partition_point(|&index| index < curr);
+        let last = shown.len().saturating_sub(1);
+        let target = match key {
+            KeyEvent::Char('k') | KeyEvent::Up => at.checked_sub(1),
+            KeyEvent::Char('j') | KeyEvent::Down if shown.get(at) == Some(&curr) => Some(at + 1),
+            KeyEvent::Char('j') | KeyEvent::Down => Some(at),
+            KeyEvent::Char('g') | KeyEvent::Home => Some(0),
+            KeyEvent::Char('G') | KeyEvent::End => Some(last),
+            KeyEvent::PageUp => Some(at.saturating_sub(page)),
+            KeyEvent::PageDown => Some(cmp::min(at + page, last)),
+            _ => return None,
+        };
+        Some(
+            target
+                .and_then(|target| shown.get(target).copied())
+                .unwrap_or(curr),
+        )
+    }
+
+    // The order `panel` is kept in, None when it's up to the user.
+    fn order(&self, panel: Status) -> Option<&Order> {
+        if self.manual.contains(&panel) {
+            return None;
+        }
+        self.sort
+            .iter()
+            .find(|(other, _)| *other == panel)
+            .map(|(_, order)| order)
+    }
+
+    // Puts things back in order after anything that may have changed the lists: sorted panels
+    // sorted, pinned items on top and the cursors on items the filter shows. The item being edited
+    // stays put until it's done.
+    pub fn settle(&mut self) {
+        if !matches!(self.mode, Mode::Edit { .. }) {
+            for (panel, order) in &self.sort {
+                if self.manual.contains(panel) {
+                    continue;
+                }
+                let (list, curr) = match panel {
+                    Status::Todo => (&mut self.todos, &mut self.todo_curr),
+                    Status::Done => (&mut self.dones, &mut self.done_curr),
+                    Status::Someday => (&mut self.somedays, &mut self.someday_curr),
+                };
+                if let Some(order) = order.order(list, &self.activity, self.now) {
+                    self.changes.extend(ops::reorder(*panel, list, &order));
+                    list::reorder(list, curr, order);
+                }
+            }
+        }
+        if let Some(order) = pin::order(&self.todos) {
+            self.changes
+                .extend(ops::reorder(Status::Todo, &self.todos, &order));
+            list::reorder(&mut self.todos, &mut self.todo_curr, order);
+        }
+        self.snap_to_filter();
+    }
+
+    // Keeps the cursors on items that are shown, moving down to the next one or else up to the
+    // previous one when the item under them went away or stopped matching.
+    fn snap_to_filter(&mut self) {
+        if self.filter.is_none() {
+            return;
+        }
+        for panel in [Status::Todo, Status::Done, Status::Someday] {
+            let (_, curr) = self.list(panel);
+            let shown = self.filtered(panel);
+            let at = shown.partition_point(|&index| index < curr);
+            let Some(&target) = shown.get(at).or(shown.last()) else {
+                continue;
+            };
+            *self.curr_mut(panel) = target;
+        }
+    }
+
+    // Indices of the TODO items in `quadrant`. Deferred ones are left out until they're back.
+    fn quadrant_items(&self, quadrant: Quadrant) -> Vec<usize> {
+        (0..self.todos.len())
+            .filter(|&index| {
+                let item = &self.todos[index];
+                !defer::is_deferred(item, self.now) && matrix::quadrant(item, self.now) == quadrant
+            })
+            .collect()
+    }
+
+    fn handle_matrix(&mut self, key: KeyEvent, quadrant: Quadrant, curr: usize) {
+        let items = self.quadrant_items(quadrant);
+        let position = QUADRANTS
+            .iter()
+            .position(|&other| other == quadrant)
+            .unwrap_or(0);
+        self.mode = match key {
+            KeyEvent::Escape | KeyEvent::Char('e' | 'q') => Mode::Normal,
+            KeyEvent::Tab => Mode::Matrix {
+                quadrant: QUADRANTS[(position + 1) % QUADRANTS.len()],
+                curr: 0,
+            },
+            KeyEvent::BackTab => Mode::Matrix {
+                quadrant: QUADRANTS[(position + QUADRANTS.len() - 1) % QUADRANTS.len()],
+                curr: 0,
+            },
+            KeyEvent::Char('k') | KeyEvent::Up => Mode::Matrix {
+                quadrant,
+                curr: curr.saturating_sub(1),
+            },
+            KeyEvent::Char('j') | KeyEvent::Down => Mode::Matrix {
+                quadrant,
+                curr: cmp::min(curr + 1, items.len().saturating_sub(1)),
+            },
+            KeyEvent::Char(c @ '1'..='4') => {
+                let target = QUADRANTS[c as usize - '1' as usize];
+                if let Some(&index) = items.get(curr) {
+                    let now = self.now;
+                    match self.edit_item(Status::Todo, index, |item| {
+                        matrix::move_to(item, target, now)
+                    }) {
+                        Some(Ok(())) => self.notification = format!("Moved to {}", target.name()),
+                        Some(Err(error)) => self.notification = error,
+                        None => {}
+                    }
+                }
+                // Stays on the same row of the quadrant, which is now the next item.
+                let left = self.quadrant_items(quadrant).len();
+                Mode::Matrix {
+                    quadrant,
+                    curr: cmp::min(curr, left.saturating_sub(1)),
+                }
+            }
+            _ => Mode::Matrix { quadrant, curr },
+        };
+    }
+
+    fn handle_details(&mut self, key: KeyEvent, curr: usize) {
+        let panel = self.panel;
+        let (list, index) = self.list(panel);
+        let Some(item) = list.get(index) else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        let steps = checklist::steps(item).len();
+        let attachments = attach::of(item);
+        let count = steps + attachments.len();
+        self.mode = match key {
+            KeyEvent::Escape | KeyEvent::Char('x' | 'q') => Mode::Normal,
+            KeyEvent::Char('k') | KeyEvent::Up => Mode::Details {
+                curr: curr.saturating_sub(1),
+            },
+            KeyEvent::Char('j') | KeyEvent::Down => Mode::Details {
+                curr: cmp::min(curr + 1, count.saturating_sub(1)),
+            },
+            KeyEvent::Char(' ') | KeyEvent::Enter if curr < steps => {
+                let progress = self.edit_item(panel, index, |item| {
+                    checklist::toggle(&mut item.title, curr);
+                    checklist::progress(item)
+                });
+                if let Some(Some((done, total))) = progress {
+                    if done == total && panel == Status::Todo {
+                        self.notification =
+                            format!("All {} steps done, Enter on the item finishes it", total);
+                    }
+                }
+                Mode::Details { curr }
+            }
+            KeyEvent::Char(' ') | KeyEvent::Enter => {
+                if let Some(path) = attachments.get(curr - steps) {
+                    self.notification = match open::open(&open::expand(path)) {
+                        Ok(()) => format!("Opening {}", path),
+                        Err(error) => format!("Could not open {}: {}", path, error),
+                    };
+                }
+                Mode::Details { curr }
+            }
+            _ => Mode::Details { curr },
+        };
+    }
+
+    // What `L` opens for `item`, its Jira issue or the first URL in it.
+    pub fn link(&self, item: &str) -> Option<String> {
+        if let Some(link) = self.jira_url.as_ref().and_then(|url| jira::link(item, url)) {
+            return Some(link);
+        }
+        item.split_whitespace()
+            .find(|word| word.starts_with("https://") || word.starts_with("http://"))
+            .map(String::from)
+            .or_else(|| {
+                attach::of(item)
+                    .into_iter()
+                    .next()
+                    .map(|path| open::expand(&path))
+            })
+    }
+
+    // Goes through the sync conflict copies of `file_path` one by one, each merged with what's in
+    // the lists now.
+    pub fn open_conflicts(&mut self, file_path: &str, conflicts: Vec<String>) {
+        log!(Info, "event=conflicts count={}", conflicts.len());
+        self.next_conflict(merge::state_path(file_path), conflicts);
+    }
+
+    fn next_conflict(&mut self, state_path: String, mut rest: Vec<String>) {
+        self.mode = Mode::Normal;
+        while !rest.is_empty() {
+            let other = rest.remove(0);
+            let ours = Lists {
+                todos: self.todos.clone(),
+                dones: self.dones.clone(),
+                somedays: self.somedays.clone(),
+            };
+            let loaded = (|| {
+                let theirs = self.format.storage().load(&other, &mut |_, _| {})?;
+                let their_state = State::load(&merge::state_path(&other))?;
+                Ok::<_, io::Error>((theirs, their_state, State::load(&state_path)?))
+            })();
+            match loaded {
+                Ok((theirs, their_state, our_state)) => {
+                    let (merged, state) =
+                        merge::merge(&ours, &our_state, &theirs, &their_state, clock::now());
+                    let disputed = merge::disputed(&ours, &our_state, &theirs, &their_state);
+                    self.mode = Mode::Merge(Box::new(Merging {
+                        other,
+                        state_path,
+                        theirs,
+                        merged,
+                        state,
+                        rest,
+                        scroll: 0,
+                        disputed,
+                        curr: 0,
+                        editing: None,
+                    }));
+                    return;
+                }
+                Err(error) => {
+                    log!(Warn, "event=conflict_failed error={:?}", error.to_string());
+                    self.notification = error.to_string();
+                }
+            }
+        }
+    }
+
+    fn handle_merge(&mut self, key: KeyEvent) {
+        let Mode::Merge(mut merging) = mem::replace(&mut self.mode, Mode::Normal) else {
+            return;
+        };
+        let title = merging.disputed.get(merging.curr).cloned();
+        if let (Some(mut buffer), Some(title)) = (merging.editing.take(), title.clone()) {
+            match key {
+                KeyEvent::Enter => {
+                    let buffer = buffer.trim().to_string();
+                    if !buffer.is_empty() && buffer != title {
+                        let [ours, theirs, merged] = merging.sides(&title, self.lists());
+                        let status = merged.or(ours).or(theirs).unwrap_or(Status::Todo);
+                        let now = clock::now();
+                        merging.decide(&buffer, Some(status), &title, now);
+                        merging.state.set(&title, now, None);
+                        merging.disputed[merging.curr] = buffer;
+                    }
+                }
+                KeyEvent::Escape => {}
+                key => {
+                    self.edit
+                        .handle_key(&mut buffer, key, &[], &self.abbreviations);
+                    merging.editing = Some(buffer);
+                }
+            }
+            self.mode = Mode::Merge(merging);
+            return;
+        }
+        match key {
+            KeyEvent::Char(side @ ('h' | 'l')) if title.is_some() => {
+                let title = title.unwrap_or_default();
+                let [ours, theirs, _] = merging.sides(&title, self.lists());
+                let (status, name) = if side == 'h' {
+                    (ours, "ours")
+                } else {
+                    (theirs, "theirs")
+                };
+                merging.decide(&title, status, &title, clock::now());
+                self.notification = format!("Keeping {} as it is in {}", title, name);
+                merging.curr = cmp::min(merging.curr + 1, merging.disputed.len() - 1);
+                self.mode = Mode::Merge(merging);
+            }
+            KeyEvent::Char('e') if title.is_some() => {
+                let title = title.unwrap_or_default();
+                self.edit = EditState::new(&title, title.len());
+                merging.editing = Some(title);
+                self.mode = Mode::Merge(merging);
+            }
+            KeyEvent::Char('k') | KeyEvent::Up if !merging.disputed.is_empty() => {
+                merging.curr = merging.curr.saturating_sub(1);
+                self.mode = Mode::Merge(merging);
+            }
+            KeyEvent::Char('j') | KeyEvent::Down if !merging.disputed.is_empty() => {
+                merging.curr = cmp::min(merging.curr + 1, merging.disputed.len() - 1);
+                self.mode = Mode::Merge(merging);
+            }
+            KeyEvent::Enter => {
+                let Merging {
+                    other,
+                    state_path,
+                    merged,
+                    state,
+                    rest,
+                    ..
+                } = *merging;
+                self.rewrite(|app| {
+                    app.todos = merged.todos;
+                    app.dones = merged.dones;
+                    app.somedays = merged.somedays;
+                });
+                for (list, curr) in [
+                    (&self.todos, &mut self.todo_curr),
+                    (&self.dones, &mut self.done_curr),
+                    (&self.somedays, &mut self.someday_curr),
+                ] {
+                    *curr = cmp::min(*curr, list.len().saturating_sub(1));
+                }
+                self.notification = match state.save(&state_path).and_then(|()| remove_copy(&other))
+                {
+                    Ok(()) => format!("Merged {}", other),
+                    Err(error) => error.to_string(),
+                };
+                self.next_conflict(state_path, rest);
+            }
+            KeyEvent::Char('d') => {
+                self.notification = match remove_copy(&merging.other) {
+                    Ok(()) => format!("Deleted {}", merging.other),
+                    Err(error) => error.to_string(),
+                };
+                self.next_conflict(merging.state_path, merging.rest);
+            }
+            KeyEvent::Escape | KeyEvent::Char('q') => {
+                self.notification = format!("Left {} for next time", merging.other);
+                self.next_conflict(merging.state_path, merging.rest);
+            }
+            KeyEvent::Char('k') | KeyEvent::Up => {
+                merging.scroll = merging.scroll.saturating_sub(1);
+                self.mode = Mode::Merge(merging);
+            }
+            KeyEvent::Char('j') | KeyEvent::Down => {
+                merging.scroll += 1;
+                self.mode = Mode::Merge(merging);
+            }
+            _ => self.mode = Mode::Merge(merging),
+        }
+    }
+
+    fn list_mut(&mut self, panel: Status) -> (&mut Vec<Item>, &mut usize) {
+        match panel {
+            Status::Todo => (&mut self.todos, &mut self.todo_curr),
+            Status::Done => (&mut self.dones, &mut self.done_curr),
+            Status::Someday => (&mut self.somedays, &mut self.someday_curr),
+        }
+    }
+
+    // Puts the cursor on a random TODO item out of those shown, the longer one has been open the
+    // likelier. Items the activity log doesn't know count as new.
+    fn pick(&mut self) {
+        let now = clock::now();
+        let candidates: Vec<usize> = (0..self.todos.len())
+            .filter(|&index| {
+                self.shown(Status::Todo, index) && !defer::is_deferred(&self.todos[index], self.now)
+            })
+            .collect();
+        let weights: Vec<u64> = candidates
+            .iter()
+            .map(|&index| {
+                let since = self.activity.opened(&self.todos[index]).unwrap_or(now);
+                1 + ((now - since) / activity::DAY).max(0) as u64
+            })
+            .collect();
+        // A fresh RandomState is seeded differently every time, which is all the randomness this
+        // needs.
+        let roll = RandomState::new().build_hasher().finish();
+        let Some(chosen) = pick::weighted(&weights, roll) else {
+            self.notification = "Nothing to pick from".to_string();
+            return;
+        };
+        self.panel = Status::Todo;
+        self.todo_curr = candidates[chosen];
+        self.notification = format!(
+            "How about {}? t starts a pomodoro on it",
+            self.todos[self.todo_curr]
+        );
+    }
+
+    // Moves the current item to the end of list `to` as it is, due dates, tags and all. Returns
+    // whether there was anything to move.
+    fn move_item(&mut self, to: Status) -> bool {
+        let from = self.panel;
+        let (list, curr) = self.list_mut(from);
+        let at = *curr;
+        let Some(item) = list::delete(list, curr) else {
+            return false;
+        };
+        self.changes.push(Op::Transfer {
+            from: (from, at),
+            to: (to, self.list(to).0.len()),
+            item: item.clone(),
+        });
+        match (from, to) {
+            // Parked items are out of TODO as far as the stats go, like deleted ones.
+            (Status::Todo, Status::Someday) => record(&mut self.activity, Event::Deleted, &item),
+            (_, Status::Todo) => record(&mut self.activity, Event::Undone, &item),
+            _ => {}
+        }
+        self.list_mut(to).0.push(item);
+        if to == Status::Done {
+            self.completed();
+        }
+        true
+    }
+
+    // The local midnight at the start of today as unix time.
+    fn today(&self) -> i64 {
+        midnight(self.now).to_unix(clock::offset(clock::now()))
+    }
+
+    // Checks the habit under the TODO cursor off for today.
+    fn check_habit(&mut self) {
+        let Some(item) = self.todos.get(self.todo_curr) else {
+            return;
+        };
+        let today = self.today();
+        let checked = self.activity.checked(item);
+        if checked.last().is_some_and(|&time| time >= today) {
+            self.notification = "Already done today, see you tomorrow".to_string();
+            return;
+        }
+        record(&mut self.activity, Event::Checked, item);
+        let done = habit::days(self.activity.checked(item), today, 365);
+        self.notification = format!(
+            "Done for today, {} days in a row{}",
+            habit::streak(&done),
+            self.gained(item)
+        );
+    }
+
+    // What getting `item` done is worth, for the end of the notification.
+    fn gained(&self, item: &str) -> String {
+        match &self.points {
+            Some(points) => format!(" +{} points", points.score(item)),
+            None => String::new(),
+        }
+    }
+
+    // Scripts may change the lists in any way they like.
+    fn clamp_cursors(&mut self) {
+        self.todo_curr = self.todo_curr.min(self.todos.len().saturating_sub(1));
+        self.done_curr = self.done_curr.min(self.dones.len().saturating_sub(1));
+        self.someday_curr = self.someday_curr.min(self.somedays.len().saturating_sub(1));
+    }
+
+    fn handle_normal(&mut self, key: KeyEvent) {
+        if self.pending.is_none() {
+            // Seeing what a script did means copying the lists, only worth it when there is one.
+            let swallowed = match self.scripts.handles_keys() {
+                true => self.rewrite(|app| {
+                    app.scripts
+                        .on_key(key, &mut app.todos, &mut app.dones, app.panel)
+                }),
+                false => Ok(false),
+            };
+            self.clamp_cursors();
+            match swallowed {
+                Ok(true) => return,
+                Ok(false) => {}
+                Err(error) => {
+                    log!(Warn, "event=script_failed error={:?}", error);
+                    self.notification = error;
+                    return;
+                }
+            }
+        }
+
+        let page = self.page();
+        if self.filter.is_some() && self.pending.is_none() {
+            if let Some(target) = self.filtered_move(key, page) {
+                *self.curr_mut(self.panel) = target;
+                return;
+            }
+        }
+        let prefix = self.pending.take();
+        let name = self.register_name.take();
+
+        if key == KeyEvent::Char('>') && prefix.is_none() && self.panel == Status::Todo {
+            if self.move_item(Status::Someday) {
+                self.notification =
+                    "Parked for someday, Enter on it in SOMEDAY brings it back".to_string();
+            }
+            return;
+        }
+        if matches!(key, KeyEvent::Char('J' | 'K')) && prefix.is_none() {
+            if let Some(order) = self.order(self.panel) {
+                self.notification = format!(
+                    "Sorted by {}, o switches to putting items in order by hand",
+                    order.name()
+                );
+                return;
+            }
+        }
+        if key == KeyEvent::Char('L') && prefix.is_none() {
+            let (list, curr) = self.list(self.panel);
+            let Some(item) = list.get(curr) else {
+                return;
+            };
+            self.notification = match self.link(item) {
+                Some(link) => match open::open(&link) {
+                    Ok(()) => format!("Opening {}", link),
+                    Err(error) => format!("Could not open {}: {}", link, error),
+                },
+                None => "Nothing to open, the item has no link or issue key".to_string(),
+            };
+            return;
+        }
+        if key == KeyEvent::Char('o') && prefix.is_none() {
+            let title = self.header.title(self.panel);
+            let configured = self.sort.iter().find(|(panel, _)| *panel == self.panel);
+            self.notification = match configured {
+                None => format!("No order for {} in [sort] in the config", title),
+                Some(_) if self.manual.contains(&self.panel) => {
+                    self.manual.retain(|&panel| panel != self.panel);
+                    let order = self.order(self.panel).map(Order::name).unwrap_or_default();
+                    format!("{} sorted by {}", title, order)
+                }
+                Some(_) => {
+                    self.manual.push(self.panel);
+                    format!("{} in the order you put it in, J/K move items", title)
+                }
+            };
+            return;
+        }
+        if matches!(key, KeyEvent::Char('[' | ']')) && prefix.is_none() {
+            self.pending = Some(if key == KeyEvent::Char('[') { '[' } else { ']' });
+            return;
+        }
+        if matches!(prefix, Some('[' | ']')) {
+            if matches!(key, KeyEvent::Char('t' | 'p')) {
+                self.cycle_tag(prefix == Some(']'), key == KeyEvent::Char('p'));
+            }
+            return;
+        }
+        if matches!(key, KeyEvent::Char('f' | 'F')) && prefix.is_none() {
+            self.pending = Some(if key == KeyEvent::Char('f') { 'f' } else { 'F' });
+            return;
+        }
+        if matches!(prefix, Some('f' | 'F')) {
+            if let KeyEvent::Char(c) = key {
+                self.jump = Some((c, prefix == Some('f')));
+                self.jump_to(c, prefix == Some('f'));
+            }
+            return;
+        }
+        if matches!(key, KeyEvent::Char('n' | 'N')) && prefix.is_none() {
+            self.search_next(key == KeyEvent::Char('n'));
+            return;
+        }
+        if matches!(key, KeyEvent::Char(';' | ',')) && prefix.is_none() {
+            match self.jump {
+                Some((c, forward)) => self.jump_to(c, forward == (key == KeyEvent::Char(';'))),
+                None => self.notification = "No jump to repeat, f<char> jumps first".to_string(),
+            }
+            return;
+        }
+        if prefix == Some('m') {
+            let to = match key {
+                KeyEvent::Char('t') => Status::Todo,
+                KeyEvent::Char('d') => Status::Done,
+                KeyEvent::Char('s') => Status::Someday,
+                _ => return,
+            };
+            let title = self.header.title(to).to_string();
+            if to == self.panel {
+                self.notification = format!("Already in {}", title);
+            } else if self.move_item(to) {
+                self.notification = format!("Moved to {}", title);
+            }
+            return;
+        }
+
+        // `other` is where Enter moves items to.
+        let status = self.panel;
+        let (list, curr, other) = match self.panel {
+            Status::Todo => (&mut self.todos, &mut self.todo_curr, &mut self.dones),
+            Status::Done => (&mut self.dones, &mut self.done_curr, &mut self.todos),
+            Status::Someday => (&mut self.somedays, &mut self.someday_curr, &mut self.todos),
+        };
+
+        // Keys completing a command like `"a` are not edit entries.
+        let entry = list
+            .get_mut(*curr)
+            .filter(|_| prefix.is_none())
+            .and_then(|item| {
+                let line = item.to_string();
+                edit_entry_cursor(key, &line).map(|cursor| (item, line, cursor))
+            });
+        if let Some((item, line, cursor)) = entry {
+            // Edited as the line it's saved as, due date and all, until Enter reads it back.
+            self.edit = EditState::new(&line, cursor);
+            *item = Item {
+                title: line,
+                ..Item::default()
+            };
+            self.mode = Mode::Edit { inserting: false };
+            return;
+        }
+
+        match key {
+            KeyEvent::Char('K' | 'J') => {
+                let from = *curr;
+                if key == KeyEvent::Char('K') {
+                    list::drag_up(list, curr);
+                } else {
+                    list::drag_down(list, curr);
+                }
+                if *curr != from {
+                    self.changes.push(Op::Drag {
+                        status,
+                        from,
+                        to: *curr,
+                        item: list[*curr].clone(),
+                    });
+                }
+            }
+            KeyEvent::Char('i') => match self.panel {
+                Status::Todo => {
+                    list.insert(*curr, Item::default());
+                    self.changes.push(Op::Insert {
+                        status,
+                        at: *curr,
+                        item: Item::default(),
+                    });
+                    self.edit = EditState::default();
+                    self.mode = Mode::Edit { inserting: true };
+                    self.suggestions = other
+                        .iter()
+                        .chain(list.iter())
+                        .map(ToString::to_string)
+                        .chain(self.history.entries.iter().cloned())
+                        .collect();
+                    self.notification = "What needs to be done?".to_string();
+                }
+                Status::Done | Status::Someday => {
+                    self.notification =
+                        "Can't insert new items here. Only TODO is allowed.".to_string();
+                }
+            },
+            KeyEvent::Char(c) if prefix == Some('"') && Registers::is_valid_name(c) => {
+                self.register_name = Some(c);
+            }
+            KeyEvent::Char('"') => self.pending = Some('"'),
+            KeyEvent::Char('m') if !list.is_empty() => {
+                self.pending = Some('m');
+                self.notification = "Move to (t)odo, (d)one or (s)omeday?".to_string();
+            }
+            KeyEvent::Char('d') if prefix == Some('d') => {
+                let at = *curr;
+                if let Some(item) = list::delete(list, curr) {
+                    self.changes.push(Op::Delete {
+                        status,
+                        at,
+                        item: item.clone(),
+                    });
+                    if self.panel == Status::Todo {
+                        record(&mut self.activity, Event::Deleted, &item);
+                    }
+                    self.hooks.run(
+                        &mut self.runtime,
+                        "on-delete",
+                        hooks::item(&item, self.panel),
+                    );
+                    self.registers.store(name, vec![item]);
+                    self.notification = match self.panel {
+                        Status::Todo | Status::Someday => "Cut!",
+                        Status::Done => "Into The Abyss!",
+                    }
+                    .to_string();
+                }
+            }
+            KeyEvent::Char('d') => {
+                self.pending = Some('d');
+                self.register_name = name;
+            }
+            KeyEvent::Char('y') if prefix == Some('y') => {
+                if let Some(item) = list.get(*curr) {
+                    self.registers.store(name, vec![item.clone()]);
+                    self.notification = "Yanked!".to_string();
+                }
+            }
+            KeyEvent::Char('y') => {
+                self.pending = Some('y');
+                self.register_name = name;
+            }
+            KeyEvent::Char('p') => {
+                for item in self.registers.get(name).to_vec() {
+                    list::paste(list, curr, item.clone());
+                    self.changes.push(Op::Insert {
+                        status,
+                        at: *curr,
+                        item,
+                    });
+                }
+            }
+            KeyEvent::Char('t') => match (&self.pomodoro, list.get(*curr)) {
+                (Some(pomodoro), _) => {
+                    self.notification = format!("Stopped the pomodoro on {}", pomodoro.item);
+                    self.pomodoro = None;
+                }
+                (None, Some(item)) if self.panel == Status::Todo => {
+                    self.notification = format!("Working on {} for 25 minutes", item);
+                    self.pomodoro = Some(Pomodoro::start(item));
+                    self.ticked = Instant::now();
+                }
+                (None, _) => {}
+            },
+            KeyEvent::Char('+' | '-') => {
+                let at = *curr;
+                let level = self.edit_item(status, at, |item| {
+                    if key == KeyEvent::Char('+') {
+                        priority::raise(item)
+                    } else {
+                        priority::lower(item)
+                    }
+                });
+                if let Some(level) = level {
+                    self.notification = match level {
+                        Some(level) => format!("Priority {}", level),
+                        None => "No priority".to_string(),
+                    };
+                }
+            }
+            KeyEvent::Char('!') if self.panel == Status::Todo => {
+                if let Some(item) = list.get(*curr) {
+                    let due = item.due.map(DateTime::to_item).unwrap_or_default();
+                    self.edit = EditState::new(&due, due.len());
+                    self.mode = Mode::Due(due);
+                }
+            }
+            KeyEvent::Char('z') if self.panel == Status::Todo && !list.is_empty() => {
+                self.edit = EditState::default();
+                self.mode = Mode::Defer(String::new());
+            }
+            KeyEvent::Char('s') => self.mode = Mode::Stats(Stats::Days),
+            KeyEvent::Char('R') => self.pick(),
+            KeyEvent::Char('W') => {
+                self.show_age = !self.show_age;
+                self.notification = if self.show_age {
+                    "Showing how long TODO items have been open"
+                } else {
+                    "Not showing how long TODO items have been open"
+                }
+                .to_string();
+            }
+            KeyEvent::Char('O') => {
+                self.filter = match self.filter {
+                    Some(Filter::Stale) => None,
+                    _ => Some(Filter::Stale),
+                };
+                self.notification = match self.filter {
+                    Some(_) => {
+                        let now = clock::now();
+                        let count = self
+                            .todos
+                            .iter()
+                            .filter(|item| self.is_stale(item, now))
+                            .count();
+                        format!(
+                            "{} stale items, Enter to finish, z to defer or dd to delete them",
+                            count
+                        )
+                    }
+                    None => "Showing everything".to_string(),
+                };
+            }
+            KeyEvent::Char('x') => match list.get(*curr) {
+                Some(item)
+                    if !checklist::steps(item).is_empty() || !attach::of(item).is_empty() =>
+                {
+                    self.mode = Mode::Details { curr: 0 };
+                }
+                Some(_) => {
+                    self.notification =
+                        "Nothing to show, add steps like `; [ ] first; [ ] second` \
+                                         or attach a file with :attach"
+                            .to_string();
+                }
+                None => {}
+            },
+            KeyEvent::Char('e') => {
+                self.mode = Mode::Matrix {
+                    quadrant: Quadrant::DoFirst,
+                    curr: 0,
+                }
+            }
+            KeyEvent::Char('T') => {
+                self.filter = match self.filter {
+                    Some(Filter::Today) => None,
+                    _ => Some(Filter::Today),
+                };
+                self.notification = match self.filter {
+                    Some(_) => "Today only, T shows everything again",
+                    None => "Showing everything",
+                }
+                .to_string();
+            }
+            // Goes round the contexts of the TODO items and then back to all of them.
+            KeyEvent::Char('c') => {
+                let all = contexts::all(self.todos.iter());
+                let next = match &self.filter {
+                    Some(Filter::Context(context)) => all
+                        .iter()
+                        .position(|other| other == context)
+                        .map_or(0, |position| position + 1),
+                    _ => 0,
+                };
+                self.filter = all
+                    .get(next)
+                    .map(|context| Filter::Context(context.to_string()));
+                self.notification = match &self.filter {
+                    Some(filter) => format!("Only {}, c for the next context", filter.name()),
+                    None if all.is_empty() => "No @contexts in TODO".to_string(),
+                    None => "All contexts".to_string(),
+                };
+            }
+            // The same for the saved filters, in the order of the config.
+            KeyEvent::Char('v') => {
+                let next = match &self.filter {
+                    Some(Filter::Query(name, _)) => self
+                        .filters
+                        .iter()
+                        .position(|(other, _)| other == name)
+                        .map_or(0, |position| position + 1),
+                    _ => 0,
+                };
+                self.filter = self
+                    .filters
+                    .get(next)
+                    .map(|(name, query)| Filter::Query(name.clone(), query.clone()));
+                self.notification = match &self.filter {
+                    Some(filter) => format!("Only {}, v for the next filter", filter.name()),
+                    None if self.filters.is_empty() => {
+                        "No saved filters, add one with :filter save <name> <expression>"
+                            .to_string()
+                    }
+                    None => "Showing everything".to_string(),
+                };
+            }
+            KeyEvent::Char('*') if self.panel == Status::Todo => {
+                let (at, today) = (*curr, midnight(self.now));
+                let added = self.edit_item(status, at, |item| {
+                    let added = schedule::scheduled(item).is_none();
+                    schedule::set(&mut item.title, added.then_some(today));
+                    added
+                });
+                if let Some(added) = added {
+                    self.notification = if added {
+                        "Added to today"
+                    } else {
+                        "Taken off today"
+                    }
+                    .to_string();
+                }
+            }
+            KeyEvent::Char('P') if self.panel == Status::Todo => {
+                let at = *curr;
+                let pinned = self.edit_item(status, at, |item| {
+                    let pinned = !pin::is_pinned(item);
+                    tags::set(&mut item.title, pin::TAG, pinned);
+                    pinned
+                });
+                if let Some(pinned) = pinned {
+                    self.notification = if pinned {
+                        "Pinned to the top"
+                    } else {
+                        "Unpinned"
+                    }
+                    .to_string();
+                }
+            }
+            KeyEvent::Char('D') if !list.is_empty() => {
+                list::duplicate(list, curr);
+                self.changes.push(Op::Insert {
+                    status,
+                    at: *curr,
+                    item: list[*curr].clone(),
+                });
+            }
+            KeyEvent::Char('M') => {
+                let at = *curr;
+                let removed = list.get(at..at + 2).map(<[Item]>::to_vec);
+                list::join(list, curr);
+                if let Some(removed) = removed {
+                    self.changes
+                        .extend(ops::splice(status, at, &removed, &list[at..=at]));
+                }
+            }
+            KeyEvent::Char('S') => {
+                let (at, len) = (*curr, list.len());
+                let removed = list.get(at).cloned();
+                list::split(list, curr);
+                if let Some(removed) = removed {
+                    let end = at + 1 + list.len() - len;
+                    self.changes
+                        .extend(ops::splice(status, at, &[removed], &list[at..end]));
+                }
+            }
+            KeyEvent::Char('k') | KeyEvent::Up => list::up(curr),
+            KeyEvent::Char('j') | KeyEvent::Down => list::down(list, curr),
+            KeyEvent::Char('g') | KeyEvent::Home => list::first(curr),
+            KeyEvent::Char('G') | KeyEvent::End => list::last(list, curr),
+            KeyEvent::PageUp => list::page_up(curr, page),
+            KeyEvent::PageDown => list::page_down(list, curr, page),
+            KeyEvent::Enter
+                if self.panel == Status::Todo
+                    && list.get(*curr).is_some_and(|item| habit::is_habit(item)) =>
+            {
+                self.check_habit();
+            }
+            KeyEvent::Enter if !list.is_empty() => {
+                self.changes.push(Op::Transfer {
+                    from: (status, *curr),
+                    to: (status.toggle(), other.len()),
+                    item: list[*curr].clone(),
+                });
+                list::transfer(other, list, curr);
+                self.notification = match self.panel {
+                    Status::Todo => "DONE!",
+                    Status::Done => "No, not done yet...",
+                    Status::Someday => "Today is the day!",
+                }
+                .to_string();
+                match self.panel {
+                    Status::Todo => {
+                        if let Some(item) = self.dones.last() {
+                            self.notification += &self.gained(item);
+                        }
+                        self.completed();
+                    }
+                    Status::Done | Status::Someday => {
+                        if let Some(item) = self.todos.last() {
+                            record(&mut self.activity, Event::Undone, item);
+                        }
+                    }
+                }
+            }
+            KeyEvent::Char(':') => {
+                self.edit = EditState::default();
+                self.mode = Mode::CommandLine(String::new());
+            }
+            KeyEvent::Char('/') => {
+                self.edit = EditState::default();
+                self.mode = Mode::Search(String::new());
+            }
+            KeyEvent::Ctrl('p') => {
+                self.edit = EditState::default();
+                self.mode = Mode::Picker(Picker::default());
+            }
+            KeyEvent::Char('b') => {
+                self.mode = Mode::Lists(Switcher::new(self.lists_dir.as_deref(), &self.list));
+            }
+            // SOMEDAY only comes round when there's something in it.
+            KeyEvent::Tab => {
+                self.panel = match self.panel {
+                    Status::Done if !self.somedays.is_empty() => Status::Someday,
+                    panel => panel.toggle(),
+                }
+            }
+            KeyEvent::Right if self.panel == Status::Todo => self.panel = self.panel.toggle(),
+            KeyEvent::Left if self.panel != Status::Todo => self.panel = self.panel.toggle(),
+            KeyEvent::Char('q') => self.quit = true,
+            _ => {}
+        }
+    }
+
+    pub fn view(&self, ui: &mut Ui) {
+        if let Mode::Stats(page) = self.mode {
+            match page {
+                Stats::Days => self.view_stats(ui, false),
+                Stats::Weeks => self.view_stats(ui, true),
+                Stats::Heatmap => self.view_heatmap(ui),
+            }
+            return;
+        }
+        if let Mode::Matrix { quadrant, curr } = self.mode {
+            self.view_matrix(ui, quadrant, curr);
+            return;
+        }
+        if let Mode::Details { curr } = self.mode {
+            self.view_details(ui, curr);
+            return;
+        }
+        if let Mode::Merge(merging) = &self.mode {
+            self.view_merge(ui, merging);
+            return;
+        }
+        let x = self.size.x;
+        ui.begin(Vec2::new(0, 0), LayoutKind::Vert);
+        {
+            let prompt = matches!(
+                self.mode,
+                Mode::CommandLine(_) | Mode::Search(_) | Mode::Defer(_) | Mode::Due(_)
+            );
+            let top = self.header.notification || prompt || !self.notification.is_empty();
+            if let Mode::CommandLine(buffer) = &self.mode {
+                ui.begin_layout(LayoutKind::Horz);
+                ui.label_fixed_width(":", 1, REGULAR);
+                ui.edit_field(buffer, &self.edit, x - 1, &[]);
+                ui.end_layout();
+            } else if let Mode::Search(buffer) = &self.mode {
+                ui.begin_layout(LayoutKind::Horz);
+                ui.label_fixed_width("/", 1, REGULAR);
+                ui.edit_field(buffer, &self.edit, x - 1, &[]);
+                ui.end_layout();
+            } else if let Mode::Defer(buffer) | Mode::Due(buffer) = &self.mode {
+                let prompt = match self.mode {
+                    Mode::Due(_) => "Due: ",
+                    _ => "Defer until: ",
+                };
+                let width = prompt.len() as i32;
+                ui.begin_layout(LayoutKind::Horz);
+                ui.label_fixed_width(prompt, width, REGULAR);
+                ui.edit_field(buffer, &self.edit, x - width, &[]);
+                ui.end_layout();
+            } else if top {
+                ui.label_fixed_width(&self.notification, x, REGULAR);
+            }
+            let total = self.todos.len() + self.dones.len();
+            let ratio = if total > 0 {
+                self.dones.len() as f32 / total as f32
+            } else {
+                0.0
+            };
+            let mut label = format!("{}/{} done", self.dones.len(), total);
+            if self.list != boards::HERE {
+                label = format!("{}  {}", self.list, label);
+            }
+            if let Some(pomodoro) = &self.pomodoro {
+                let seconds = pomodoro.remaining.as_secs();
+                label += &format!(
+                    "  {} {:02}:{:02} {} (#{})",
+                    pomodoro.phase.name(),
+                    seconds / 60,
+                    seconds % 60,
+                    pomodoro.item,
+                    self.pomodoros.count(&pomodoro.item) + 1
+                );
+            }
+            let streak = self.streak();
+            if streak.best > 0 {
+                label += &format!("  streak {} (best {})", streak.current, streak.best);
+                // Evening and nothing done yet, a gentle nudge while there's still time.
+                if streak.current > 0 && !streak.done_today && self.now.hour >= 18 {
+                    label += ", one more today keeps it going";
+                }
+            }
+            if let Some(
+                filter @ (Filter::Context(_)
+                | Filter::Tag(_)
+                | Filter::Project(_)
+                | Filter::Query(..)),
+            ) = &self.filter
+            {
+                label += &format!("  {}", filter.name());
+            }
+            // Estimated work left in what's shown of TODO.
+            let estimate = match self.filter {
+                None => estimate::total(&self.todos),
+                Some(_) => estimate::total(
+                    self.filtered(Status::Todo)
+                        .into_iter()
+                        .map(|index| &self.todos[index]),
+                ),
+            };
+            if estimate > 0 {
+                label += &format!("  ~{}", format_span(estimate));
+            }
+            if let Some(capacity) = self.capacity {
+                let today: i64 = self
+                    .todos
+                    .iter()
+                    .filter_map(|item| {
+                        estimate::parse(item).filter(|_| schedule::is_today(item, self.now))
+                    })
+                    .sum();
+                if today > capacity {
+                    label += &format!(
+                        "  today ~{} is over the {} capacity",
+                        format_span(today),
+                        format_span(capacity)
+                    );
+                }
+            }
+            if self.header.notification || !top {
+                ui.gauge(&label, ratio, x, GAUGE);
+            }
+
+            ui.begin_layout(LayoutKind::Horz);
+            {
+                self.view_panel(ui, Status::Todo, x / 2);
+                // SOMEDAY takes DONE's place while it's focused.
+                match self.panel {
+                    Status::Someday => self.view_panel(ui, Status::Someday, x / 2),
+                    _ => self.view_panel(ui, Status::Done, x / 2),
+                }
+            }
+            ui.end_layout();
+        }
+        ui.end();
+        if let Mode::Picker(picker) = &self.mode {
+            let items = self.pickable();
+            let total = items.len();
+            let found = picker.matches(items);
+            picker.view(ui, &self.edit, &found, total, &self.header, self.size);
+        }
+        if let Mode::Lists(switcher) = &self.mode {
+            let here = format!("./{}", self.format.file_name());
+            switcher.view(ui, &self.list, &here, self.size);
+        }
+    }
+
+    fn view_panel(&self, ui: &mut Ui, panel: Status, width: i32) {
+        let title = self.header.title(panel);
+        let (mark, list, curr, suggestions) = match panel {
+            Status::Todo => ("- [ ] ", &self.todos, self.todo_curr, &self.suggestions[..]),
+            Status::Done => ("- [x] ", &self.dones, self.done_curr, &[][..]),
+            Status::Someday => ("- [ ] ", &self.somedays, self.someday_curr, &[][..]),
+        };
+        let focused = panel == self.panel;
+        // Scroll just far enough for the cursor to be on the last row. Only the rows that fit are
+        // drawn at all, the list may well be tens of thousands of items long. Filtering has to go
+        // through all of them, which is only done when there's a filter.
+        let page = self.page();
+        let (visible, count, scroll): (Vec<usize>, usize, usize) = match self.filter {
+            None => {
+                let scroll = (curr + 1).saturating_sub(page);
+                let visible = (scroll..cmp::min(scroll + page, list.len())).collect();
+                (visible, list.len(), scroll)
+            }
+            Some(_) => {
+                let shown = self.filtered(panel);
+                let count = shown.len();
+                let at = shown.partition_point(|&index| index < curr);
+                let scroll = (at + 1).saturating_sub(page);
+                let visible = shown.into_iter().skip(scroll).take(page).collect();
+                (visible, count, scroll)
+            }
+        };
+        // The rows make room for a scrollbar when they don't all fit.
+        let scrolling = count > page;
+        let row_width = if scrolling { width - 1 } else { width };
+        // Counted again every frame, it's only the length unless there's a filter.
+        let title = match &self.filter {
+            Some(filter) => format!("{} ({}/{} {})", title, count, list.len(), filter.name()),
+            None => format!("{} ({})", title, list.len()),
+        };
+        let title = self.header.align.pad(&title, width as usize);
+
+        let now = clock::now();
+        let today = self.today();
+        let (checked, unchecked) = if ui.unicode {
+            ('■', '□')
+        } else {
+            ('x', '.')
+        };
+
+        ui.begin_layout(LayoutKind::Vert);
+        {
+            ui.label_fixed_width(&title, width, if focused { SELECTION } else { REGULAR });
+            ui.begin_layout(LayoutKind::Horz);
+            ui.begin_layout(LayoutKind::Vert);
+            for index in visible {
+                let item = &list[index];
+                let line = item.to_string();
+                let mut text = checklist::summary(&line);
+                // Recomputed on every frame, so the countdown is never behind the clock.
+                let countdown = match panel {
+                    Status::Todo => due::countdown(item, self.now),
+                    _ => None,
+                };
+                if let Some((left, _)) = &countdown {
+                    text = Cow::Owned(format!("{} {}", text, left));
+                }
+                let overdue = countdown.is_some_and(|(_, overdue)| overdue);
+                let soon = panel == Status::Todo && !overdue && due::is_soon(item, self.now);
+                if panel == Status::Todo && habit::is_habit(item) {
+                    let days = habit::days(self.activity.checked(item), today, HABIT_DAYS);
+                    let grid: String = days
+                        .iter()
+                        .map(|&done| if done { checked } else { unchecked })
+                        .collect();
+                    text = Cow::Owned(format!("{} {}", text, grid));
+                }
+                let opened = match panel {
+                    Status::Todo if self.show_age => self.activity.opened(item),
+                    _ => None,
+                };
+                let text = match opened {
+                    Some(opened) => {
+                        let span = format_span(now - opened);
+                        // Just the largest unit, `12d` rather than `12d 3h`.
+                        let age = span.split(' ').next().unwrap_or(&span);
+                        Cow::Owned(format!("{} ({})", text, age))
+                    }
+                    _ => text,
+                };
+                let mark = if panel == Status::Todo && pin::is_pinned(item) {
+                    "^ [ ] "
+                } else {
+                    mark
+                };
+                if focused && index == curr {
+                    if let Mode::Edit { .. } = self.mode {
+                        ui.edit_field(item, &self.edit, row_width, suggestions);
+                    } else {
+                        ui.list_item(mark, &text, row_width, SELECTION);
+                    }
+                } else if panel == Status::Todo && defer::is_deferred(item, self.now) {
+                    ui.list_item(mark, &text, row_width, DEFERRED);
+                } else if overdue {
+                    ui.list_item(mark, &text, row_width, OVERDUE);
+                } else if soon {
+                    ui.list_item(mark, &text, row_width, SOON);
+                } else if panel == Status::Todo && self.is_stale(item, now) {
+                    ui.list_item(mark, &text, row_width, STALE);
+                } else {
+                    ui.list_item(mark, &text, row_width, REGULAR);
+                }
+            }
+            ui.end_layout();
+            if scrolling {
+                ui.scrollbar(page, count, scroll, REGULAR);
+            }
+            ui.end_layout();
+        }
+        ui.end_layout();
+    }
+
+    // The four quadrants in a grid, each as tall as half of what's left under the header and as
+    // wide as half the screen, blank rows and all so the grid stays put.
+    fn view_matrix(&self, ui: &mut Ui, focus: Quadrant, curr: usize) {
+        let x = self.size.x;
+        let width = x / 2;
+        let height = cmp::max((self.size.y - 2) / 2, 2) as usize;
+        ui.begin(Vec2::new(0, 0), LayoutKind::Vert);
+        {
+            if self.notification.is_empty() {
+                ui.label_fixed_width(
+                    "Tab for the next quadrant, 1-4 move the item there, Esc to go back",
+                    x,
+                    REGULAR,
+                );
+            } else {
+                ui.label_fixed_width(&self.notification, x, REGULAR);
+            }
+            for row in 0..2 {
+                ui.begin_layout(LayoutKind::Horz);
+                for column in 0..2 {
+                    let quadrant = QUADRANTS[row * 2 + column];
+                    let items = self.quadrant_items(quadrant);
+                    let focused = quadrant == focus;
+                    let title = format!(
+                        "{} {} ({})",
+                        row * 2 + column + 1,
+                        quadrant.name(),
+                        items.len()
+                    );
+                    let page = height - 1;
+                    let scroll = if focused {
+                        (curr + 1).saturating_sub(page)
+                    } else {
+                        0
+                    };
+                    ui.begin_layout(LayoutKind::Vert);
+                    ui.label_fixed_width(&title, width, if focused { SELECTION } else { REGULAR });
+                    for line in scroll..scroll + page {
+                        match items.get(line) {
+                            Some(&index) => {
+                                let style = if focused && line == curr {
+                                    SELECTION
+                                } else {
+                                    REGULAR
+                                };
+                                ui.list_item(
+                                    "- [ ] ",
+                                    &self.todos[index].to_string(),
+                                    width,
+                                    style,
+                                );
+                            }
+                            None => ui.label_fixed_width("", width, REGULAR),
+                        }
+                    }
+                    ui.end_layout();
+                }
+                ui.end_layout();
+            }
+        }
+        ui.end();
+    }
+
+    // Our lists, the conflict copy and what merging them gives side by side. Items that aren't
+    // where they are in ours are marked with a `*`.
+    fn view_merge(&self, ui: &mut Ui, merging: &Merging) {
+        let x = self.size.x;
+        let width = x / 3;
+        let page = cmp::max(self.size.y - 2, 1) as usize;
+        let ours = merge_rows(self.lists(), None);
+        let [theirs, merged] =
+            [&merging.theirs, &merging.merged].map(|lists| merge_rows(slices(lists), Some(&ours)));
+        let longest = cmp::max(ours.len(), cmp::max(theirs.len(), merged.len()));
+        let mut scroll = cmp::min(merging.scroll, longest.saturating_sub(page));
+        let current = merging.disputed.get(merging.curr).map(String::as_str);
+        // Wherever the current one is, in the merged lists unless it's deleted in there.
+        let row = current.and_then(|current| {
+            [&merged, &theirs, &ours].into_iter().find_map(|rows| {
+                rows.iter()
+                    .position(|row| matches!(row, MergeRow::Item(_, item, _) if item == current))
+            })
+        });
+        if let Some(row) = row {
+            let rows = page.saturating_sub(1).max(1);
+            scroll = scroll.clamp((row + 1).saturating_sub(rows), row);
+        }
+        let name = Path::new(&merging.other)
+            .file_name()
+            .map_or(Cow::Borrowed(merging.other.as_str()), |name| {
+                name.to_string_lossy()
+            });
+        ui.begin(Vec2::new(0, 0), LayoutKind::Vert);
+        {
+            if let Some(buffer) = &merging.editing {
+                let prompt = "Merged: ";
+                let width = prompt.len() as i32;
+                ui.begin_layout(LayoutKind::Horz);
+                ui.label_fixed_width(prompt, width, REGULAR);
+                ui.edit_field(buffer, &self.edit, x - width, &[]);
+                ui.end_layout();
+            } else if !self.notification.is_empty() {
+                ui.label_fixed_width(&self.notification, x, REGULAR);
+            } else if merging.disputed.is_empty() {
+                ui.label_fixed_width(
+                    "Enter merges it in, d deletes it, Esc leaves it",
+                    x,
+                    REGULAR,
+                );
+            } else {
+                let hint = format!(
+                    "{}/{} disputed: h keeps ours, l theirs, e edits, Enter merges",
+                    merging.curr + 1,
+                    merging.disputed.len()
+                );
+                ui.label_fixed_width(&hint, x, REGULAR);
+            }
+            ui.begin_layout(LayoutKind::Horz);
+            for (title, rows) in [("Ours", &ours), (&*name, &theirs), ("Merged", &merged)] {
+                ui.begin_layout(LayoutKind::Vert);
+                ui.label_fixed_width(title, width, SELECTION);
+                for line in scroll..scroll + page - 1 {
+                    match rows.get(line) {
+                        Some(MergeRow::Section(section)) => {
+                            ui.label_fixed_width(section, width, REGULAR)
+                        }
+                        Some(MergeRow::Item(mark, item, _)) => {
+                            let style = if Some(item.as_str()) == current {
+                                SELECTION
+                            } else {
+                                REGULAR
+                            };
+                            ui.list_item(mark, item, width, style)
+                        }
+                        None => ui.label_fixed_width("", width, REGULAR),
+                    }
+                }
+                ui.end_layout();
+            }
+            ui.end_layout();
+        }
+        ui.end();
+    }
+
+    // The current item on top and its steps and attachments under it.
+    fn view_details(&self, ui: &mut Ui, curr: usize) {
+        let x = self.size.x;
+        let (list, index) = self.list(self.panel);
+        let line = list.get(index).map(ToString::to_string).unwrap_or_default();
+        let attachments = attach::of(&line);
+        let steps = checklist::steps(&line)
+            .into_iter()
+            .map(|(done, step)| (if done { "- [x] " } else { "- [ ] " }, step))
+            .chain(attachments.iter().map(|path| ("- attach: ", path.as_str())))
+            .collect::<Vec<_>>();
+        let page = cmp::max(self.size.y - 3, 1) as usize;
+        let scroll = (curr + 1).saturating_sub(page);
+        ui.begin(Vec2::new(0, 0), LayoutKind::Vert);
+        {
+            if self.notification.is_empty() {
+                ui.label_fixed_width(
+                    "Space ticks a step off or opens an attachment, Esc to go back",
+                    x,
+                    REGULAR,
+                );
+            } else {
+                ui.label_fixed_width(&self.notification, x, REGULAR);
+            }
+            ui.label_fixed_width(&checklist::summary(&line), x, SELECTION);
+            for (line, (mark, step)) in steps.iter().enumerate().skip(scroll).take(page) {
+                let style = if line == curr { SELECTION } else { REGULAR };
+                ui.list_item(mark, step, x, style);
+            }
+        }
+        ui.end();
+    }
+
+    fn view_stats(&self, ui: &mut Ui, weeks: bool) {
+        let x = self.size.x;
+        let offset = clock::offset(clock::now());
+        let today = DateTime {
+            hour: 0,
+            minute: 0,
+            ..self.now
+        };
+        let (step, last, unit) = if weeks {
+            (7, today.add_days(-(today.weekday() as i64)), "weeks")
+        } else {
+            (1, today, "days")
+        };
+        // As many days or weeks as fit between the header and the summary, a fortnight at most.
+        // The points take another row.
+        let count = (self.size.y - 4 - self.points.is_some() as i32).clamp(1, 14) as i64;
+        let starts: Vec<DateTime> = (0..count)
+            .rev()
+            .map(|back| last.add_days(-back * step))
+            .collect();
+        let bounds: Vec<i64> = starts
+            .iter()
+            .chain([&last.add_days(step)])
+            .map(|start| start.to_unix(offset))
+            .collect();
+        let periods = self.activity.periods(&bounds, self.todos.len());
+        let labels: Vec<String> = starts
+            .iter()
+            .map(|start| {
+                let date = format!("{:02}-{:02}", start.month, start.day);
+                if weeks {
+                    date
+                } else {
+                    format!("{} {}", WEEKDAYS[start.weekday() as usize], date)
+                }
+            })
+            .collect();
+
+        ui.begin(Vec2::new(0, 0), LayoutKind::Vert);
+        {
+            let header = if weeks {
+                "Per week, Tab for the heatmap, Esc to go back"
+            } else {
+                "Per day, Tab for weeks, Esc to go back"
+            };
+            ui.label_fixed_width(header, x, REGULAR);
+            ui.begin_layout(LayoutKind::Horz);
+            {
+                let column = |value: fn(&Period) -> usize| periods.iter().map(value).collect();
+                let columns: [(&str, Vec<usize>); 3] = [
+                    ("Added", column(|period| period.added)),
+                    ("Completed", column(|period| period.done)),
+                    ("Open", column(|period| period.open)),
+                ];
+                for (title, values) in columns {
+                    let bars: Vec<(&str, usize)> =
+                        labels.iter().map(String::as_str).zip(values).collect();
+                    ui.begin_layout(LayoutKind::Vert);
+                    ui.label_fixed_width(title, x / 3, SELECTION);
+                    ui.bar_chart(&bars, x / 3 - 1, GAUGE);
+                    ui.end_layout();
+                }
+            }
+            ui.end_layout();
+
+            let average = match self.activity.completion_time() {
+                Some((time, count)) => format!(
+                    "Average time to complete: {} over {} items",
+                    format_span(time),
+                    count
+                ),
+                None => "Nothing completed since the stats started".to_string(),
+            };
+            ui.label_fixed_width(&average, x, REGULAR);
+            if let Some(points) = &self.points {
+                let total = points.total(&self.activity.entries);
+                let (level, into, needed) = points::level(total);
+                ui.label_fixed_width(
+                    &format!(
+                        "Points: {}, level {} ({}/{} to level {})",
+                        total,
+                        level,
+                        into,
+                        needed,
+                        level + 1
+                    ),
+                    x,
+                    REGULAR,
+                );
+            }
+            let open = self.todos.len();
+            let before = self.activity.open_at(bounds[0], open);
+            ui.label_fixed_width(
+                &format!(
+                    "Backlog: {} open, {:+} over the last {} {}",
+                    open,
+                    open as i64 - before as i64,
+                    count,
+                    unit
+                ),
+                x,
+                REGULAR,
+            );
+        }
+        ui.end();
+    }
+
+    fn view_heatmap(&self, ui: &mut Ui) {
+        let x = self.size.x;
+        let today = DateTime {
+            hour: 0,
+            minute: 0,
+            ..self.now
+        };
+        // As many weeks as fit next to the weekday names, a year at most.
+        let weeks = ((x - 4) / 2).clamp(1, 53) as usize;
+        ui.begin(Vec2::new(0, 0), LayoutKind::Vert);
+        {
+            ui.label_fixed_width("Done per day, Tab for days, Esc to go back", x, REGULAR);
+            for row in stats::heatmap(&self.activity, today, weeks, ui.unicode) {
+                ui.label_fixed_width(&row, x, GAUGE);
+            }
+        }
+        ui.end();
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::{self, Stdout, Write};
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use crossterm::{cursor, terminal, QueueableCommand};
+
+use super::Backend;
+use crate::input::KeyEvent;
+use crate::style::{self, Color, Style};
+use crate::vec2::Vec2;
+
+pub struct CrosstermBackend {
+    stdout: Stdout,
+    unicode: bool,
+}
+
+impl CrosstermBackend {
+    pub fn init() -> Self {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode().expect("Could not put the terminal into raw mode");
+        stdout
+            .queue(terminal::EnterAlternateScreen)
+            .and_then(|stdout| stdout.queue(cursor::Hide))
+            .and_then(|stdout| stdout.flush())
+            .expect("Could not set up the terminal");
+
+        Self {
+            stdout,
+            unicode: super::unicode_locale(),
+        }
+    }
+}
+
+fn color(color: Color) -> crossterm::style::Color {
+    use crossterm::style::Color as C;
+    match color {
+        Color::Black => C::Black,
+        Color::Red => C::DarkRed,
+        Color::Green => C::DarkGreen,
+        Color::Yellow => C::DarkYellow,
+        Color::Blue => C::DarkBlue,
+        Color::Magenta => C::DarkMagenta,
+        Color::Cyan => C::DarkCyan,
+        Color::White => C::Grey,
+    }
+}
+
+pub fn decode_key(key: event::KeyEvent) -> KeyEvent {
+    use KeyEvent::*;
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    match key.code {
+        KeyCode::Enter => Enter,
+        KeyCode::Tab => Tab,
+        KeyCode::BackTab => BackTab,
+        KeyCode::Esc => Escape,
+        KeyCode::F(n) => F(n),
+        KeyCode::Backspace => Backspace,
+        KeyCode::Delete => Delete,
+        KeyCode::Up => Up,
+        KeyCode::Down => Down,
+        KeyCode::Left if ctrl => CtrlLeft,
+        KeyCode::Left if alt => AltLeft,
+        KeyCode::Left => Left,
+        KeyCode::Right if ctrl => CtrlRight,
+        KeyCode::Right if alt => AltRight,
+        KeyCode::Right => Right,
+        KeyCode::Home => Home,
+        KeyCode::End => End,
+        KeyCode::PageUp => PageUp,
+        KeyCode::PageDown => PageDown,
+        // ^\ through ^_ come through as Ctrl+4 through Ctrl+7, same as they're typed on a US
+        // keyboard. Curses reports them by the punctuation, so do the same here.
+        KeyCode::Char(c @ '4'..='7') if ctrl => Ctrl((c as u8 - b'4' + b'\\') as char),
+        KeyCode::Char(c) if ctrl => Ctrl(c.to_ascii_lowercase()),
+        KeyCode::Char(c) if alt => Alt(c),
+        KeyCode::Char(c) if !c.is_control() => Char(c),
+        _ => Unknown,
+    }
+}
+
+pub fn restore() {
+    let mut stdout = io::stdout();
+    let _ = stdout
+        .queue(cursor::Show)
+        .and_then(|stdout| stdout.queue(terminal::LeaveAlternateScreen))
+        .and_then(|stdout| stdout.flush());
+    let _ = terminal::disable_raw_mode();
+}
+
+impl Backend for CrosstermBackend {
+    fn unicode(&self) -> bool {
+        self.unicode
+    }
+
+    fn size(&self) -> Vec2 {
+        let (x, y) = terminal::size().unwrap_or((80, 24));
+        Vec2::new(x as i32, y as i32)
+    }
+
+    fn clear(&mut self) {
+        let _ = self.stdout.queue(terminal::Clear(terminal::ClearType::All));
+    }
+
+    fn draw(&mut self, pos: Vec2, text: &str, style: Style) {
+        let attributes = [
+            (style::BOLD, Attribute::Bold),
+            (style::DIM, Attribute::Dim),
+            (style::UNDERLINE, Attribute::Underlined),
+            (style::REVERSE, Attribute::Reverse),
+        ];
+        let _ = self
+            .stdout
+            .queue(cursor::MoveTo(pos.x as u16, pos.y as u16))
+            .and_then(|stdout| stdout.queue(SetForegroundColor(color(style.fg))))
+            .and_then(|stdout| stdout.queue(SetBackgroundColor(color(style.bg))));
+        for (flag, attribute) in attributes {
+            if style.attrs & flag != 0 {
+                let _ = self.stdout.queue(SetAttribute(attribute));
+            }
+        }
+        let _ = self
+            .stdout
+            .queue(Print(text))
+            .and_then(|stdout| stdout.queue(SetAttribute(Attribute::Reset)));
+    }
+
+    fn present(&mut self) {
+        let _ = self.stdout.flush();
+    }
+
+    fn bell(&mut self) {
+        let _ = self
+            .stdout
+            .write_all(b"\x07")
+            .and_then(|_| self.stdout.flush());
+    }
+
+    fn poll_key(&mut self, timeout: Duration) -> Option<KeyEvent> {
+        if !event::poll(timeout).unwrap_or(false) {
+            return None;
+        }
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => Some(decode_key(key)),
+            Ok(Event::Resize(_, _)) => Some(KeyEvent::Resize),
+            _ => None,
+        }
+    }
+
+    fn shutdown(&mut self) {
+        restore();
+    }
+
+    fn resume(&mut self) {
+        let _ = terminal::enable_raw_mode();
+        let _ = self
+            .stdout
+            .queue(terminal::EnterAlternateScreen)
+            .and_then(|stdout| stdout.queue(cursor::Hide))
+            .and_then(|stdout| stdout.flush());
+    }
+}
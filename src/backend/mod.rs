@@ -0,0 +1,67 @@
+use std::env;
+use std::time::Duration;
+
+use crate::input::KeyEvent;
+use crate::style::Style;
+use crate::vec2::Vec2;
+
+// With both features ncurses wins, leaving crossterm nothing to do.
+#[cfg(all(feature = "crossterm", not(feature = "ncurses")))]
+pub mod crossterm_backend;
+#[cfg(feature = "ncurses")]
+pub mod ncurses_backend;
+// For the tests and the benchmarks.
+pub mod test_backend;
+
+#[cfg(not(any(feature = "ncurses", feature = "crossterm")))]
+compile_error! {"Enable at least one of the `ncurses` or `crossterm` features to get a backend"}
+
+// Everything the Ui needs from a terminal. Widgets only ever talk to this, which keeps curses out
+// of the way on platforms that don't have it.
+pub trait Backend {
+    // Whether non-ASCII glyphs like block characters will show up properly.
+    fn unicode(&self) -> bool;
+    fn size(&self) -> Vec2;
+    fn clear(&mut self);
+    fn draw(&mut self, pos: Vec2, text: &str, style: Style);
+    fn present(&mut self);
+    // Rings the terminal bell.
+    fn bell(&mut self);
+    // Waits up to `timeout` for the next key press.
+    fn poll_key(&mut self, timeout: Duration) -> Option<KeyEvent>;
+    // Gives the terminal back in the state we found it, when exiting or for the shell while we are
+    // suspended.
+    fn shutdown(&mut self);
+    // Takes the terminal back after shutdown() when continuing from Ctrl+Z. Whatever is on the
+    // screen by then belongs to someone else, the next frame has to be drawn in full.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    fn resume(&mut self);
+}
+
+// ncurses is the default wherever it's compiled in. The crossterm backend is what Windows and
+// systems without the ncurses headers build with.
+pub fn init() -> Box<dyn Backend> {
+    #[cfg(feature = "ncurses")]
+    return Box::new(ncurses_backend::NcursesBackend::init());
+    #[cfg(all(feature = "crossterm", not(feature = "ncurses")))]
+    return Box::new(crossterm_backend::CrosstermBackend::init());
+}
+
+// Whether the terminal can show non-ASCII glyphs, going by the locale. Windows consoles handle
+// unicode fine.
+pub fn unicode_locale() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_default();
+    cfg!(windows) || locale.to_uppercase().replace('-', "").contains("UTF8")
+}
+
+// Puts the terminal back the way shutdown() does, for when the backend is out of reach like in the
+// panic hook.
+pub fn restore() {
+    #[cfg(feature = "ncurses")]
+    ncurses_backend::restore();
+    #[cfg(all(feature = "crossterm", not(feature = "ncurses")))]
+    crossterm_backend::restore();
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use ncurses::*;
+
+use super::Backend;
+use crate::input::KeyEvent;
+use crate::style::{self, Color, Style};
+use crate::vec2::Vec2;
+
+const ESCAPE_DELAY: i32 = 25;
+
+pub struct NcursesBackend {
+    unicode: bool,
+    // Color pairs are only allocated the first time a particular fg/bg combination is actually
+    // drawn, so the palette can define as many styles as it likes upfront.
+    pairs: HashMap<(Color, Color), i16>,
+}
+
+impl NcursesBackend {
+    pub fn init() -> Self {
+        let locale = setlocale(LcCategory::all, "");
+
+        initscr();
+        noecho();
+        keypad(stdscr(), true);
+        // Esc cancels editing, there's no reason to wait the default full second for it to turn
+        // out not to be the start of an escape sequence.
+        set_escdelay(ESCAPE_DELAY);
+        curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+
+        start_color();
+
+        Self {
+            unicode: locale.to_uppercase().replace('-', "").contains("UTF8"),
+            pairs: HashMap::new(),
+        }
+    }
+
+    fn pair(&mut self, fg: Color, bg: Color) -> i16 {
+        // Pair 0 is reserved by curses for the terminal defaults and can't be redefined.
+        let next = self.pairs.len() as i16 + 1;
+        *self.pairs.entry((fg, bg)).or_insert_with(|| {
+            init_pair(next, color(fg), color(bg));
+            next
+        })
+    }
+
+    fn attrs(&mut self, style: Style) -> attr_t {
+        let mut attrs = COLOR_PAIR(self.pair(style.fg, style.bg));
+        if style.attrs & style::BOLD != 0 {
+            attrs |= A_BOLD();
+        }
+        if style.attrs & style::DIM != 0 {
+            attrs |= A_DIM();
+        }
+        if style.attrs & style::UNDERLINE != 0 {
+            attrs |= A_UNDERLINE();
+        }
+        if style.attrs & style::REVERSE != 0 {
+            attrs |= A_REVERSE();
+        }
+        attrs
+    }
+}
+
+fn color(color: Color) -> i16 {
+    match color {
+        Color::Black => COLOR_BLACK,
+        Color::Red => COLOR_RED,
+        Color::Green => COLOR_GREEN,
+        Color::Yellow => COLOR_YELLOW,
+        Color::Blue => COLOR_BLUE,
+        Color::Magenta => COLOR_MAGENTA,
+        Color::Cyan => COLOR_CYAN,
+        Color::White => COLOR_WHITE,
+    }
+}
+
+// get_wch() gives characters, whole ones however many bytes they are in UTF-8, apart from the
+// codes of special keys.
+pub fn decode_key(input: WchResult) -> KeyEvent {
+    use KeyEvent::*;
+    let code = match input {
+        WchResult::KeyCode(code) => return decode_special(code),
+        WchResult::Char(code) => code,
+    };
+    match code {
+        10 => Enter,
+        9 => Tab,
+        // Terminals send Alt+key as Esc immediately followed by the key. Curses only recognizes
+        // the escape sequences listed in terminfo, everything else shows up as a lone Esc, so
+        // whatever comes right after it (within the escape delay) decides what it was.
+        27 => match get_wch() {
+            None => Escape,
+            Some(WchResult::Char(next)) => match char::from_u32(next) {
+                Some(c) if !c.is_control() => Alt(c),
+                _ => {
+                    unget_wch(next);
+                    Escape
+                }
+            },
+            Some(WchResult::KeyCode(next)) => {
+                ungetch(next);
+                Escape
+            }
+        },
+        8 | 127 => Backspace,
+        1..=26 => Ctrl((b'a' + code as u8 - 1) as char),
+        28..=31 => Ctrl((b'\\' + code as u8 - 28) as char),
+        code => match char::from_u32(code) {
+            Some(c) if !c.is_control() => Char(c),
+            _ => Unknown,
+        },
+    }
+}
+
+fn decode_special(code: i32) -> KeyEvent {
+    use KeyEvent::*;
+    match code {
+        constants::KEY_ENTER => Enter,
+        constants::KEY_BTAB => BackTab,
+        constants::KEY_BACKSPACE => Backspace,
+        constants::KEY_DC => Delete,
+        constants::KEY_UP => Up,
+        constants::KEY_DOWN => Down,
+        constants::KEY_LEFT => Left,
+        constants::KEY_RIGHT => Right,
+        constants::KEY_HOME => Home,
+        constants::KEY_END => End,
+        constants::KEY_PPAGE => PageUp,
+        constants::KEY_NPAGE => PageDown,
+        constants::KEY_RESIZE => Resize,
+        code if (constants::KEY_F0 + 1..=constants::KEY_F0 + 24).contains(&code) => {
+            F((code - constants::KEY_F0) as u8)
+        }
+        // Modified arrows don't have fixed key codes, curses assigns them at runtime from the
+        // terminfo extended capabilities. kLFT5 is Ctrl+Left, kLFT3 is Alt+Left and so on.
+        _ => match keyname(code).as_deref() {
+            Some("kLFT5") => CtrlLeft,
+            Some("kRIT5") => CtrlRight,
+            Some("kLFT3") => AltLeft,
+            Some("kRIT3") => AltRight,
+            _ => Unknown,
+        },
+    }
+}
+
+pub fn restore() {
+    endwin();
+}
+
+impl Backend for NcursesBackend {
+    fn unicode(&self) -> bool {
+        self.unicode
+    }
+
+    fn size(&self) -> Vec2 {
+        let mut x = 0;
+        let mut y = 0;
+        getmaxyx(stdscr(), &mut y, &mut x);
+        Vec2::new(x, y)
+    }
+
+    fn clear(&mut self) {
+        erase();
+    }
+
+    fn draw(&mut self, pos: Vec2, text: &str, style: Style) {
+        let attrs = self.attrs(style);
+        mv(pos.y, pos.x);
+        attron(attrs);
+        addstr(text);
+        attroff(attrs);
+    }
+
+    fn present(&mut self) {
+        refresh();
+    }
+
+    fn bell(&mut self) {
+        beep();
+    }
+
+    fn poll_key(&mut self, duration: Duration) -> Option<KeyEvent> {
+        timeout(duration.as_millis() as i32);
+        let input = get_wch()?;
+        // If Esc turns out to start an Alt combination the rest of it is already here.
+        timeout(ESCAPE_DELAY);
+        Some(decode_key(input))
+    }
+
+    fn shutdown(&mut self) {
+        restore();
+    }
+
+    fn resume(&mut self) {
+        // The first refresh after endwin() puts the terminal back into curses mode, clearok makes
+        // it repaint the whole screen instead of trusting what it thinks is there.
+        clearok(stdscr(), true);
+        refresh();
+    }
+}
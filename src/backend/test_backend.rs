@@ -0,0 +1,94 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::Backend;
+use crate::input::KeyEvent;
+use crate::style::Style;
+use crate::vec2::Vec2;
+
+// What the test backend drew, one row of characters per line of the terminal. Shared with the
+// test so it can look at the screen after handing the backend to the Ui.
+pub type Screen = Rc<RefCell<Vec<Vec<char>>>>;
+
+// Stands in for a terminal in tests. Draws into an in-memory grid and plays back a script of keys,
+// poll_key() returns None once the script runs out.
+pub struct TestBackend {
+    size: Vec2,
+    keys: VecDeque<KeyEvent>,
+    screen: Screen,
+}
+
+impl TestBackend {
+    pub fn new(size: Vec2, keys: Vec<KeyEvent>) -> (Self, Screen) {
+        let screen = Rc::new(RefCell::new(vec![
+            vec![' '; size.x as usize];
+            size.y as usize
+        ]));
+        let backend = Self {
+            size,
+            keys: keys.into(),
+            screen: Rc::clone(&screen),
+        };
+        (backend, screen)
+    }
+}
+
+// Turns typed text into key presses, with `\n` for Enter, `\t` for Tab and `\x1b` for Escape.
+pub fn keys(text: &str) -> Vec<KeyEvent> {
+    text.chars()
+        .map(|c| match c {
+            '\n' => KeyEvent::Enter,
+            '\t' => KeyEvent::Tab,
+            '\x1b' => KeyEvent::Escape,
+            c => KeyEvent::Char(c),
+        })
+        .collect()
+}
+
+// The screen as text, trailing blanks trimmed off every line.
+pub fn text(screen: &Screen) -> String {
+    screen
+        .borrow()
+        .iter()
+        .map(|row| row.iter().collect::<String>().trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Backend for TestBackend {
+    fn unicode(&self) -> bool {
+        true
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    fn clear(&mut self) {
+        for row in self.screen.borrow_mut().iter_mut() {
+            row.fill(' ');
+        }
+    }
+
+    fn draw(&mut self, pos: Vec2, text: &str, _style: Style) {
+        let mut screen = self.screen.borrow_mut();
+        let row = &mut screen[pos.y as usize];
+        for (x, symbol) in (pos.x as usize..row.len()).zip(text.chars()) {
+            row[x] = symbol;
+        }
+    }
+
+    fn present(&mut self) {}
+
+    fn bell(&mut self) {}
+
+    fn poll_key(&mut self, _timeout: Duration) -> Option<KeyEvent> {
+        self.keys.pop_front()
+    }
+
+    fn shutdown(&mut self) {}
+
+    fn resume(&mut self) {}
+}
@@ -0,0 +1,72 @@
+use crate::style::Style;
+use crate::vec2::Vec2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub symbol: char,
+    pub style: Style,
+}
+
+// A screen's worth of cells. The Ui draws every frame into one of these and only the cells that
+// differ from the previous frame are sent to the backend, so an unchanged list isn't repainted
+// over a slow connection 60 times a second.
+pub struct Buffer {
+    pub size: Vec2,
+    cells: Vec<Cell>,
+}
+
+impl Buffer {
+    pub fn new(size: Vec2, blank: Style) -> Self {
+        let len = (size.x.max(0) * size.y.max(0)) as usize;
+        Self {
+            size,
+            cells: vec![
+                Cell {
+                    symbol: ' ',
+                    style: blank
+                };
+                len
+            ],
+        }
+    }
+
+    // Writes `text` starting at `pos`, clipped to the right edge of the screen.
+    pub fn put_str(&mut self, pos: Vec2, text: &str, style: Style) {
+        if pos.y < 0 || pos.y >= self.size.y {
+            return;
+        }
+        let row = (pos.y * self.size.x) as usize;
+        for (x, symbol) in (pos.x..self.size.x).zip(text.chars()) {
+            if x >= 0 {
+                self.cells[row + x as usize] = Cell { symbol, style };
+            }
+        }
+    }
+
+    // Runs of cells that changed since `previous`, each one a single style so it can be drawn
+    // with one call.
+    pub fn diff(&self, previous: &Buffer) -> Vec<(Vec2, String, Style)> {
+        assert!(self.size.x == previous.size.x && self.size.y == previous.size.y);
+        let mut runs: Vec<(Vec2, String, Style)> = Vec::new();
+        for y in 0..self.size.y {
+            let mut run: Option<(Vec2, String, Style)> = None;
+            for x in 0..self.size.x {
+                let index = (y * self.size.x + x) as usize;
+                let cell = self.cells[index];
+                if cell == previous.cells[index] {
+                    runs.extend(run.take());
+                    continue;
+                }
+                match &mut run {
+                    Some((_, text, style)) if *style == cell.style => text.push(cell.symbol),
+                    _ => {
+                        runs.extend(run.take());
+                        run = Some((Vec2::new(x, y), cell.symbol.to_string(), cell.style));
+                    }
+                }
+            }
+            runs.extend(run);
+        }
+        runs
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use todo_core::date::DateTime;
+
+// Dates in items are local time, and todo-core leaves finding out what that is to us.
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as i64)
+}
+
+pub fn local() -> DateTime {
+    let now = now();
+    DateTime::from_unix(now, offset(now))
+}
+
+// How many seconds local time is ahead of UTC at unix time `secs`.
+#[cfg(unix)]
+pub fn offset(secs: i64) -> i64 {
+    unsafe {
+        let time = secs as libc::time_t;
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return 0;
+        }
+        tm.tm_gmtoff as i64
+    }
+}
+
+// Declared by hand rather than pulling in a crate for a single function, like the console handler.
+#[cfg(windows)]
+mod windows {
+    #[repr(C)]
+    pub struct SystemTime {
+        pub fields: [u16; 8],
+    }
+
+    #[repr(C)]
+    pub struct TimeZoneInformation {
+        pub bias: i32,
+        pub standard_name: [u16; 32],
+        pub standard_date: SystemTime,
+        pub standard_bias: i32,
+        pub daylight_name: [u16; 32],
+        pub daylight_date: SystemTime,
+        pub daylight_bias: i32,
+    }
+
+    pub const TIME_ZONE_ID_DAYLIGHT: u32 = 2;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn GetTimeZoneInformation(information: *mut TimeZoneInformation) -> u32;
+    }
+}
+
+// Windows only tells the offset in effect right now, which is off by an hour for times on the
+// other side of a daylight saving switch.
+#[cfg(windows)]
+pub fn offset(_secs: i64) -> i64 {
+    unsafe {
+        let mut information: windows::TimeZoneInformation = std::mem::zeroed();
+        let bias = match windows::GetTimeZoneInformation(&mut information) {
+            u32::MAX => return 0,
+            windows::TIME_ZONE_ID_DAYLIGHT => information.bias + information.daylight_bias,
+            _ => information.bias + information.standard_bias,
+        };
+        -(bias as i64) * 60
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn offset(_secs: i64) -> i64 {
+    0
+}
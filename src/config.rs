@@ -0,0 +1,466 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+
+use todo_core::date::parse_span;
+use todo_core::paths::config_path;
+use todo_core::points::Points;
+use todo_core::query::Query;
+use todo_core::search::Case;
+use todo_core::sort::Order;
+use todo_core::status::Status;
+use todo_core::storage::Format;
+
+use crate::clock;
+use crate::hooks::{self, Hooks};
+use crate::jira::Jira;
+use crate::sound::{self, Sounds};
+use crate::style::{Color, Selection};
+
+// The config is a tiny INI dialect:
+//
+//     # comment
+//     [abbreviations]
+//     ;std = "#standup discuss "
+//     [hooks]
+//     on-done = jq -r .title >> ~/done.log
+//     [webhooks]
+//     on-done = https://hooks.slack.com/services/...
+//     [reminders]
+//     lead = 1d, 1h
+//     [today]
+//     capacity = 6h
+//     [stale]
+//     after = 30d
+//     [archive]
+//     after = 14d
+//     [templates]
+//     release = bump the version #release; tag it #release
+//     [filters]
+//     work = tag:#work AND status:todo AND due<=+7d
+//     [sort]
+//     todo = due, quadrant
+//     [list]
+//     age = yes
+//     [storage]
+//     format = todotxt
+//     [points]
+//     enabled = yes
+//     hour = 20
+//     [sync]
+//     remote = me@example.com:notes/TODO
+//     [jira]
+//     url = https://example.atlassian.net
+//     [search]
+//     case = smart
+//     [sounds]
+//     done = bell
+//     [theme]
+//     glyphs = ascii
+//     selection = marker
+//     tag:work = blue
+//     [header]
+//     todo = Backlog
+//     align = center
+//
+// Values may be wrapped in double quotes to keep leading or trailing whitespace. Only `#` starts a
+// comment since `;` is a perfectly good first character for an abbreviation.
+#[derive(Default)]
+pub struct Config {
+    pub abbreviations: Vec<(String, String)>,
+    pub hooks: Hooks,
+    // How long ahead of due times `cli-todo daemon` reminds of them, in seconds.
+    pub reminders: Vec<i64>,
+    // Named lists of items to add in one go, written as one line split at `;` like `S` splits.
+    pub templates: Vec<(String, Vec<String>)>,
+    // How much work fits in a day, in seconds, for warning about overplanned days.
+    pub capacity: Option<i64>,
+    // How long an item may stay open before it's stale, in seconds. 0 turns it off, None leaves
+    // it to the app.
+    pub stale: Option<i64>,
+    // How long DONE items stay before they're archived at startup, in seconds.
+    pub archive: Option<i64>,
+    // Named filter expressions, the last one wins when a name comes up twice.
+    pub filters: Vec<(String, Query)>,
+    // Panels kept sorted instead of in the order items were put in.
+    pub sort: Vec<(Status, Order)>,
+    // Whether TODO items show how long they've been open.
+    pub age: bool,
+    // Points for getting things done, None when they're off.
+    pub points: Option<Points>,
+    // Where `cli-todo sync` syncs to when --remote doesn't say, anything scp takes.
+    pub remote: Option<String>,
+    // Set once anything is set in [jira].
+    pub jira: Option<Jira>,
+    // Whether `/` cares about case.
+    pub search_case: Case,
+    // What plays when an item is done, a pomodoro phase ends or an item becomes overdue.
+    pub sounds: Sounds,
+    // Tags that don't get their color from their name.
+    pub tag_colors: Vec<(String, Color)>,
+    // Whether to draw blocks, lines and marks outside ASCII, None goes by the locale.
+    pub unicode: Option<bool>,
+    pub selection: Selection,
+    pub header: Header,
+    // What the lists are kept in, `--format` goes over it.
+    pub format: Format,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    // `text` padded out to `width` columns, or as is when it doesn't fit.
+    pub fn pad(self, text: &str, width: usize) -> String {
+        let space = width.saturating_sub(text.chars().count());
+        let before = match self {
+            Align::Left => 0,
+            Align::Center => space / 2,
+            Align::Right => space,
+        };
+        format!("{}{}", " ".repeat(before), text)
+    }
+}
+
+// What the panels are called and where their titles go, and whether the row for notifications
+// stays when there's nothing in it.
+#[derive(Debug, Clone)]
+pub struct Header {
+    pub todo: String,
+    pub done: String,
+    pub someday: String,
+    pub align: Align,
+    // Without it notifications and prompts take the place of the status bar while they're up.
+    pub notification: bool,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Self {
+            todo: "TODO".to_string(),
+            done: "DONE".to_string(),
+            someday: "SOMEDAY".to_string(),
+            align: Align::Left,
+            notification: true,
+        }
+    }
+}
+
+impl Header {
+    pub fn title(&self, panel: Status) -> &str {
+        match panel {
+            Status::Todo => &self.todo,
+            Status::Done => &self.done,
+            Status::Someday => &self.someday,
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "yes" | "true" | "on" => Some(true),
+        "no" | "false" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_value(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+impl Config {
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = config_path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(source) => Self::parse(&source, &path.display().to_string()),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(format!(
+                "{}: ERROR: could not read config: {}",
+                path.display(),
+                error
+            )),
+        }
+    }
+
+    pub fn parse(source: &str, file_path: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut section = String::new();
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: &str| format!("{}:{}: ERROR: {}", file_path, index + 1, message);
+
+            if let Some(name) = line.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `key = value`"))?;
+            let key = key.trim();
+            let value = parse_value(value.trim());
+
+            match section.as_str() {
+                "abbreviations" => {
+                    config
+                        .abbreviations
+                        .push((key.to_string(), value.to_string()));
+                }
+                "hooks" => {
+                    if !hooks::NAMES.contains(&key) {
+                        return Err(error(&format!(
+                            "unknown hook `{}`, expected one of {}",
+                            key,
+                            hooks::NAMES.join(", ")
+                        )));
+                    }
+                    config.hooks.add(key, value);
+                }
+                "webhooks" => {
+                    if !hooks::NAMES.contains(&key) {
+                        return Err(error(&format!(
+                            "unknown hook `{}`, expected one of {}",
+                            key,
+                            hooks::NAMES.join(", ")
+                        )));
+                    }
+                    if !value.starts_with("https://") && !value.starts_with("http://") {
+                        return Err(error(&format!("expected a URL, got `{}`", value)));
+                    }
+                    config.hooks.add_webhook(key, value);
+                }
+                "reminders" if key == "lead" => {
+                    config.reminders = value
+                        .split(',')
+                        .map(|lead| {
+                            parse_span(lead.trim()).ok_or_else(|| {
+                                error(&format!(
+                                    "invalid lead time `{}`, expected something like 10m, 2h or 1d",
+                                    lead.trim()
+                                ))
+                            })
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                "reminders" => return Err(error(&format!("unknown key `{}`", key))),
+                "today" if key == "capacity" => {
+                    config.capacity = Some(parse_span(value).ok_or_else(|| {
+                        error(&format!(
+                            "invalid capacity `{}`, expected something like 6h",
+                            value
+                        ))
+                    })?);
+                }
+                "today" => return Err(error(&format!("unknown key `{}`", key))),
+                "stale" if key == "after" => {
+                    config.stale = Some(parse_span(value).ok_or_else(|| {
+                        error(&format!(
+                            "invalid time `{}`, expected something like 30d or 4w",
+                            value
+                        ))
+                    })?);
+                }
+                "stale" => return Err(error(&format!("unknown key `{}`", key))),
+                "archive" if key == "after" => {
+                    config.archive = Some(parse_span(value).ok_or_else(|| {
+                        error(&format!(
+                            "invalid time `{}`, expected something like 14d or 4w",
+                            value
+                        ))
+                    })?);
+                }
+                "archive" => return Err(error(&format!("unknown key `{}`", key))),
+                "templates" => {
+                    let items: Vec<String> = value
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .map(String::from)
+                        .collect();
+                    if items.is_empty() {
+                        return Err(error(&format!("template `{}` has no items", key)));
+                    }
+                    config.templates.push((key.to_string(), items));
+                }
+                "filters" => {
+                    let query = Query::parse(value, clock::local())
+                        .map_err(|message| error(&format!("filter `{}`: {}", key, message)))?;
+                    config.filters.retain(|(name, _)| name != key);
+                    config.filters.push((key.to_string(), query));
+                }
+                "sort" => {
+                    let panel = match key {
+                        "todo" => Status::Todo,
+                        "done" => Status::Done,
+                        "someday" => Status::Someday,
+                        _ => {
+                            return Err(error(&format!(
+                                "unknown panel `{}`, expected todo, done or someday",
+                                key
+                            )))
+                        }
+                    };
+                    let order = Order::parse(value).map_err(|message| error(&message))?;
+                    config.sort.retain(|(other, _)| *other != panel);
+                    config.sort.push((panel, order));
+                }
+                "list" if key == "age" => {
+                    config.age = parse_bool(value)
+                        .ok_or_else(|| error(&format!("expected yes or no, got `{}`", value)))?;
+                }
+                "list" => return Err(error(&format!("unknown key `{}`", key))),
+                "points" if key == "enabled" => {
+                    let enabled = parse_bool(value)
+                        .ok_or_else(|| error(&format!("expected yes or no, got `{}`", value)))?;
+                    config.points = enabled.then(|| config.points.take().unwrap_or_default());
+                }
+                "points" => {
+                    let points = config.points.get_or_insert_with(Points::default);
+                    let field = match key {
+                        "done" => &mut points.done,
+                        "hour" => &mut points.hour,
+                        "important" => &mut points.important,
+                        "urgent" => &mut points.urgent,
+                        _ => return Err(error(&format!("unknown key `{}`", key))),
+                    };
+                    *field = value.parse().map_err(|_| {
+                        error(&format!("expected a number of points, got `{}`", value))
+                    })?;
+                }
+                "storage" if key == "format" => {
+                    config.format = Format::parse(value).map_err(|message| error(&message))?;
+                }
+                "storage" => return Err(error(&format!("unknown key `{}`", key))),
+                "sync" if key == "remote" => config.remote = Some(value.to_string()),
+                "sync" => return Err(error(&format!("unknown key `{}`", key))),
+                "jira" => {
+                    let jira = config.jira.get_or_insert_with(Jira::default);
+                    let field = match key {
+                        "url" => &mut jira.url,
+                        "user" => &mut jira.user,
+                        "token" => &mut jira.token,
+                        "jql" => &mut jira.jql,
+                        "done" => &mut jira.done,
+                        _ => return Err(error(&format!("unknown key `{}`", key))),
+                    };
+                    *field = value.to_string();
+                }
+                "search" if key == "case" => {
+                    config.search_case = Case::parse(value).ok_or_else(|| {
+                        error(&format!(
+                            "expected smart, ignore or sensitive, got `{}`",
+                            value
+                        ))
+                    })?;
+                }
+                "search" => return Err(error(&format!("unknown key `{}`", key))),
+                "sounds" => {
+                    if !sound::EVENTS.contains(&key) {
+                        return Err(error(&format!(
+                            "unknown event `{}`, expected one of {}",
+                            key,
+                            sound::EVENTS.join(", ")
+                        )));
+                    }
+                    config.sounds.set(key, value);
+                }
+                "theme" if key == "glyphs" => {
+                    config.unicode = match value {
+                        "auto" => None,
+                        "unicode" => Some(true),
+                        "ascii" => Some(false),
+                        _ => {
+                            return Err(error(&format!(
+                                "expected auto, unicode or ascii, got `{}`",
+                                value
+                            )))
+                        }
+                    };
+                }
+                "theme" if key == "selection" => {
+                    config.selection = Selection::parse(value).ok_or_else(|| {
+                        error(&format!(
+                            "expected reverse, marker or both, got `{}`",
+                            value
+                        ))
+                    })?;
+                }
+                "theme" => {
+                    let Some(tag) = key.strip_prefix("tag:") else {
+                        return Err(error(&format!(
+                            "unknown key `{}`, expected glyphs, selection or tag:<name>",
+                            key
+                        )));
+                    };
+                    let tag = tag.trim_start_matches('#');
+                    let color = Color::parse(value).ok_or_else(|| {
+                        let names: Vec<_> = Color::ALL.iter().map(|color| color.name()).collect();
+                        error(&format!(
+                            "unknown color `{}`, expected one of {}",
+                            value,
+                            names.join(", ")
+                        ))
+                    })?;
+                    config.tag_colors.retain(|(other, _)| other != tag);
+                    config.tag_colors.push((tag.to_string(), color));
+                }
+                "header" => match key {
+                    "todo" | "done" | "someday" => {
+                        if value.is_empty() {
+                            return Err(error(&format!("empty title for {}", key)));
+                        }
+                        let field = match key {
+                            "todo" => &mut config.header.todo,
+                            "done" => &mut config.header.done,
+                            _ => &mut config.header.someday,
+                        };
+                        *field = value.to_string();
+                    }
+                    "align" => {
+                        config.header.align = match value {
+                            "left" => Align::Left,
+                            "center" => Align::Center,
+                            "right" => Align::Right,
+                            _ => {
+                                return Err(error(&format!(
+                                    "expected left, center or right, got `{}`",
+                                    value
+                                )))
+                            }
+                        };
+                    }
+                    "notification" => {
+                        config.header.notification = parse_bool(value).ok_or_else(|| {
+                            error(&format!("expected yes or no, got `{}`", value))
+                        })?;
+                    }
+                    _ => return Err(error(&format!("unknown key `{}`", key))),
+                },
+                _ => return Err(error(&format!("unknown section `{}`", section))),
+            }
+        }
+        Ok(config)
+    }
+}
+
+// Appends a filter to the config at `path` in a section of its own, which leaves whatever else is in
+// there exactly as it was.
+pub fn save_filter(path: &Path, name: &str, source: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "\n[filters]\n{} = {}", name, source)
+}
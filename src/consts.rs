@@ -1,2 +0,0 @@
-pub const REGULAR_PAIR: i16 = 0;
-pub const HIGHLIGHT_PAIR: i16 = 1;
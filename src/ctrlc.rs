@@ -1,29 +1,237 @@
+#[cfg(unix)]
+use std::os::fd::RawFd;
+#[cfg(unix)]
+use std::sync::atomic::AtomicI32;
 use std::sync::atomic::{AtomicBool, Ordering};
-
-#[cfg(not(unix))]
-compile_error! {"Windows is not supported right now"}
+use std::time::Duration;
+#[cfg(unix)]
+use std::{mem, ptr};
 
 // We are just trying to flip a bunch of bits in a single-threaded environment with no plans of
 // making it multi-threaded. No need to make it overcomplicated. Just a single atomic bool with
 // relaxed ordering should be enough.
 static CTRLC: AtomicBool = AtomicBool::new(false);
+// Ctrl+Z and coming back to the foreground afterwards.
+#[cfg(unix)]
+static SUSPEND: AtomicBool = AtomicBool::new(false);
+#[cfg(unix)]
+static RESUME: AtomicBool = AtomicBool::new(false);
+
+// Written to from the signal handler, so whoever waits in poll(2) or select(2) wakes up on a
+// signal like on any other input. -1 until init() made it.
+#[cfg(unix)]
+static PIPE: [AtomicI32; 2] = [AtomicI32::new(-1), AtomicI32::new(-1)];
+
+#[cfg(unix)]
+extern "C" fn callback(signum: i32) {
+    match signum {
+        libc::SIGTSTP => SUSPEND.store(true, Ordering::Relaxed),
+        libc::SIGCONT => RESUME.store(true, Ordering::Relaxed),
+        _ => CTRLC.store(true, Ordering::Relaxed),
+    }
+    let fd = PIPE[1].load(Ordering::Relaxed);
+    if fd >= 0 {
+        // write(2) is async-signal-safe. When the pipe is full there's enough in it to wake
+        // anyone up already.
+        let byte = 1u8;
+        unsafe {
+            libc::write(fd, &byte as *const u8 as *const libc::c_void, 1);
+        }
+    }
+}
+
+// Besides Ctrl+C, `kill` and system shutdown send SIGTERM and closing the terminal sends SIGHUP.
+// All of them mean "wrap up", so they all go through the same path that saves the lists on the way
+// out instead of killing the process with the edits still in memory. Ctrl+Z sends SIGTSTP, which
+// is ours to handle so the terminal can be given back before stopping, and SIGCONT comes when the
+// shell puts us back into the foreground.
+#[cfg(unix)]
+const SIGNALS: [i32; 5] = [
+    libc::SIGINT,
+    libc::SIGTERM,
+    libc::SIGHUP,
+    libc::SIGTSTP,
+    libc::SIGCONT,
+];
+
+#[cfg(unix)]
+fn handle(signum: i32, handler: libc::sighandler_t) {
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = handler;
+        // Leaving out SA_RESTART means a signal interrupts the blocking read in getch() as well,
+        // so it gets noticed right away instead of when the input timeout runs out.
+        action.sa_flags = 0;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(signum, &action, ptr::null_mut()) != 0 {
+            // sigaction(2) only fails for invalid signals. This means we are on a really weird
+            // UNIX or there is a bug in libc crate.
+            unreachable!()
+        }
+    }
+}
+
+#[cfg(unix)]
+pub fn init() {
+    unsafe {
+        let mut fds = [0; 2];
+        // Without the pipe signals are still caught, there is just nothing to poll for them.
+        if libc::pipe(fds.as_mut_ptr()) == 0 {
+            for fd in fds {
+                // Non-blocking so neither the handler nor poll() ever hang on it, close-on-exec so
+                // hooks don't inherit it.
+                libc::fcntl(
+                    fd,
+                    libc::F_SETFL,
+                    libc::fcntl(fd, libc::F_GETFL) | libc::O_NONBLOCK,
+                );
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+            PIPE[0].store(fds[0], Ordering::Relaxed);
+            PIPE[1].store(fds[1], Ordering::Relaxed);
+        }
+    }
+    // sigaction(2) rather than signal(2), whose semantics differ between unices.
+    for signum in SIGNALS {
+        handle(signum, callback as *const () as libc::sighandler_t);
+    }
+}
+
+// Whether Ctrl+Z was pressed since the last call.
+#[cfg(unix)]
+pub fn poll_suspend() -> bool {
+    SUSPEND.swap(false, Ordering::Relaxed)
+}
+
+// Whether we were continued after being stopped since the last call, the terminal may have been
+// messed with in the meantime.
+#[cfg(unix)]
+pub fn poll_resume() -> bool {
+    RESUME.swap(false, Ordering::Relaxed)
+}
+
+// Stops the process like Ctrl+Z would have without our handler, and returns once the shell
+// continues it. The terminal has to be given back before this.
+#[cfg(unix)]
+pub fn stop() {
+    handle(libc::SIGTSTP, libc::SIG_DFL);
+    unsafe {
+        libc::raise(libc::SIGTSTP);
+    }
+    handle(libc::SIGTSTP, callback as *const () as libc::sighandler_t);
+    // The caller takes the terminal back anyway.
+    RESUME.store(false, Ordering::Relaxed);
+}
+
+// Runs `f` with the signals blocked, so threads it starts inherit a mask that keeps them from ever
+// receiving one. That leaves the main thread as the only one the kernel can deliver them to, which
+// is the one that has to be woken up.
+#[cfg(unix)]
+pub fn masked<T>(f: impl FnOnce() -> T) -> T {
+    unsafe {
+        let mut set: libc::sigset_t = mem::zeroed();
+        let mut old: libc::sigset_t = mem::zeroed();
+        libc::sigemptyset(&mut set);
+        for signum in SIGNALS {
+            libc::sigaddset(&mut set, signum);
+        }
+        libc::pthread_sigmask(libc::SIG_BLOCK, &set, &mut old);
+        let result = f();
+        libc::pthread_sigmask(libc::SIG_SETMASK, &old, ptr::null_mut());
+        result
+    }
+}
 
-extern "C" fn callback(_signum: i32) {
-    CTRLC.store(true, Ordering::Relaxed);
+#[cfg(not(unix))]
+pub fn masked<T>(f: impl FnOnce() -> T) -> T {
+    f()
 }
 
+// Becomes readable when a signal comes in, for loops that would rather sleep until something
+// happens than wake up every now and then to call poll().
+#[cfg(unix)]
+pub fn fd() -> Option<RawFd> {
+    let fd = PIPE[0].load(Ordering::Relaxed);
+    (fd >= 0).then_some(fd)
+}
+
+// Sleeps for `timeout`, or until a signal comes in if that's sooner.
+#[cfg(unix)]
+pub fn sleep(timeout: Duration) {
+    let Some(fd) = fd() else {
+        std::thread::sleep(timeout);
+        return;
+    };
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    unsafe {
+        libc::poll(&mut pollfd, 1, timeout.as_millis() as libc::c_int);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn sleep(timeout: Duration) {
+    let deadline = std::time::Instant::now() + timeout;
+    while !CTRLC.load(Ordering::Relaxed) && std::time::Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+// Windows has no signals, Ctrl+C and Ctrl+Break are delivered to a console control handler on a
+// thread of its own instead. In the UI the crossterm backend puts the console into raw mode where
+// Ctrl+C arrives as a regular key press, but while loading and in `cli-todo serve` this is what
+// keeps Ctrl+C from killing the process before it had a chance to save.
+#[cfg(windows)]
+mod windows {
+    // Declared by hand rather than pulling in a crate for a single function.
+    pub const CTRL_C_EVENT: u32 = 0;
+    pub const CTRL_BREAK_EVENT: u32 = 1;
+
+    pub type HandlerRoutine = unsafe extern "system" fn(ctrl_type: u32) -> i32;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        pub fn SetConsoleCtrlHandler(handler: Option<HandlerRoutine>, add: i32) -> i32;
+    }
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn handler(ctrl_type: u32) -> i32 {
+    match ctrl_type {
+        windows::CTRL_C_EVENT | windows::CTRL_BREAK_EVENT => {
+            CTRLC.store(true, Ordering::Relaxed);
+            // Handled, don't run the default handler which would exit right away.
+            1
+        }
+        // Closing the console, logging off and shutting down go on to the default handler.
+        _ => 0,
+    }
+}
+
+#[cfg(windows)]
 pub fn init() {
     unsafe {
-        // See signal(2) Portability section. Though for our specific case of flipping some bits on
-        // SIGINT this might not be that important.
-        if libc::signal(libc::SIGINT, callback as libc::sighandler_t) == libc::SIG_ERR {
-            // signal(2) usually fails when the first argument is invalid. This means we are
-            // on a really weird UNIX or there is a bug in libc crate.
+        // Only fails for invalid arguments, and ours are fine.
+        if windows::SetConsoleCtrlHandler(Some(handler), 1) == 0 {
             unreachable!()
         }
     }
 }
 
+#[cfg(not(any(unix, windows)))]
+pub fn init() {}
+
 pub fn poll() -> bool {
+    #[cfg(unix)]
+    if let Some(fd) = fd() {
+        // Empty the pipe so the next poll(2) on it sleeps again.
+        let mut buffer = [0u8; 64];
+        while unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) } > 0
+        {
+        }
+    }
     CTRLC.swap(false, Ordering::Relaxed)
 }
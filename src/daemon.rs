@@ -0,0 +1,212 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use todo_core::date::{format_span, DateTime};
+use todo_core::due::{self, Reminder};
+use todo_core::json::Value;
+use todo_core::paths::data_dir;
+
+use crate::clock;
+use crate::config::Config;
+use crate::ctrlc;
+use crate::hooks::Hooks;
+use crate::log::log;
+
+// How often the file is checked for changes and reminders are looked for. Due times only go down to
+// the minute anyway.
+const INTERVAL: Duration = Duration::from_secs(15);
+// When the config doesn't say: an hour ahead, and once more when it's due.
+const DEFAULT_LEADS: [i64; 2] = [60 * 60, 0];
+
+fn lock_path() -> Result<PathBuf, String> {
+    data_dir()
+        .map(|dir| dir.join("daemon.lock"))
+        .ok_or_else(|| "ERROR: could not find the data directory, set HOME or XDG_DATA_HOME".into())
+}
+
+// Who holds the lock, as written into it by them. Windows won't let anyone else read a locked
+// file, so there it stays unknown.
+fn pid(path: &PathBuf) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// One daemon per user is enough, two would remind of everything twice. Like the storage lock the OS
+// lets go of it when the daemon dies, so there is no stale pid file to clean up after a crash.
+fn lock() -> Result<File, String> {
+    let path = lock_path()?;
+    let error = |error: io::Error| format!("{}: ERROR: {}", path.display(), error);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(error)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(&path)
+        .map_err(error)?;
+    match file.try_lock() {
+        Ok(()) => {
+            file.set_len(0)
+                .and_then(|()| write!(file, "{}", process::id()))
+                .map_err(error)?;
+            Ok(file)
+        }
+        Err(TryLockError::WouldBlock) => Err(match pid(&path) {
+            Some(pid) => format!(
+                "{}: ERROR: the daemon is already running as pid {}",
+                path.display(),
+                pid
+            ),
+            None => format!("{}: ERROR: the daemon is already running", path.display()),
+        }),
+        Err(TryLockError::Error(error)) => Err(format!("{}: ERROR: {}", path.display(), error)),
+    }
+}
+
+// `cli-todo daemon status`: whether a daemon holds the lock. Returns whether one does.
+pub fn status() -> Result<bool, String> {
+    let path = lock_path()?;
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            println!("The daemon is not running");
+            return Ok(false);
+        }
+        Err(error) => return Err(format!("{}: ERROR: {}", path.display(), error)),
+    };
+    match file.try_lock() {
+        Ok(()) => {
+            println!("The daemon is not running");
+            Ok(false)
+        }
+        Err(TryLockError::WouldBlock) => {
+            match pid(&path) {
+                Some(pid) => println!("The daemon is running as pid {}", pid),
+                None => println!("The daemon is running"),
+            }
+            Ok(true)
+        }
+        Err(TryLockError::Error(error)) => Err(format!("{}: ERROR: {}", path.display(), error)),
+    }
+}
+
+// A desktop notification with whatever the platform comes with. The reminder went to stdout
+// already, so there is nothing to do when there's no way to show one.
+#[cfg(unix)]
+fn notify(summary: &str, body: &str) -> io::Result<()> {
+    use std::process::{Command, Stdio};
+
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {:?} with title {:?}",
+            body, summary
+        ));
+        command
+    };
+    #[cfg(not(target_os = "macos"))]
+    let mut command = {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name", "cli-todo", summary, body]);
+        command
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|_| ())
+}
+
+#[cfg(not(unix))]
+fn notify(_summary: &str, _body: &str) -> io::Result<()> {
+    Ok(())
+}
+
+fn remind(hooks: &Hooks, reminder: &Reminder, now: i64) {
+    let due = DateTime::from_unix(reminder.due, clock::offset(reminder.due));
+    // Checks happen every INTERVAL, so a reminder an hour ahead comes a few seconds short of that.
+    let left = (reminder.due - now + 59) / 60 * 60;
+    let body = if left > 0 {
+        format!("Due in {} ({})", format_span(left), due)
+    } else {
+        format!("Due now ({})", due)
+    };
+    log!(
+        Info,
+        "event=reminder item={:?} due={:?}",
+        reminder.item.title,
+        due.to_string()
+    );
+    // Whoever started the daemon in the background may well have closed the terminal since.
+    let _ = writeln!(io::stdout(), "{}: {}", reminder.item.title, body);
+    if hooks.has("on-reminder") {
+        hooks.run_and_wait(
+            "on-reminder",
+            Value::object([
+                ("title", reminder.item.to_string().as_str().into()),
+                ("due", due.to_string().as_str().into()),
+                ("message", body.as_str().into()),
+            ]),
+        );
+    } else if let Err(error) = notify(reminder.item, &body) {
+        log!(Warn, "event=notify_failed error={:?}", error.to_string());
+    }
+}
+
+// `cli-todo daemon`: reminds of TODO items ahead of their due times while nothing else is open.
+// The file is only ever read, so it's fine for the UI or the server to have it locked.
+pub fn run(file_path: &str, config: Config) -> Result<(), String> {
+    let _lock = lock()?;
+    let leads = if config.reminders.is_empty() {
+        DEFAULT_LEADS.to_vec()
+    } else {
+        config.reminders
+    };
+    log!(Info, "event=daemon file={:?} leads={:?}", file_path, leads);
+    println!("Watching {} for due items", file_path);
+
+    let mut todos = Vec::new();
+    // Saving writes the file in place, a change in size catches reading it halfway through that
+    // when the modification time stays the same.
+    let mut seen = None;
+    // Reminders that came up while the daemon wasn't running are not made up for, only those
+    // missed while the machine was asleep.
+    let mut since = clock::now();
+    while !ctrlc::poll() {
+        #[cfg(unix)]
+        if ctrlc::poll_suspend() {
+            ctrlc::stop();
+        }
+        let current = fs::metadata(file_path)
+            .ok()
+            .map(|metadata| (metadata.modified().ok(), metadata.len()));
+        if current != seen {
+            seen = current;
+            match config.format.storage().load(file_path, &mut |_, _| {}) {
+                Ok(lists) => {
+                    log!(Debug, "event=reloaded todos={}", lists.todos.len());
+                    todos = lists.todos;
+                }
+                Err(error) if error.kind() == ErrorKind::NotFound => todos.clear(),
+                // Keep reminding of what was there before, the next save probably fixes it.
+                Err(error) => {
+                    log!(Warn, "event=load_failed error={:?}", error.to_string());
+                    eprintln!("{}", error);
+                }
+            }
+        }
+        let now = clock::now();
+        for reminder in due::reminders(&todos, &leads, since, now, clock::offset(now)) {
+            remind(&config.hooks, &reminder, now);
+        }
+        since = now;
+        ctrlc::sleep(INTERVAL);
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use todo_core::item::Item;
+use todo_core::json::Value;
+use zbus::blocking::connection;
+use zbus::blocking::Connection;
+use zbus::fdo;
+use zbus::object_server::SignalEmitter;
+
+use crate::ctrlc;
+use crate::log::log;
+use crate::runtime::{Message, Runtime};
+
+const NAME: &str = "org.todo.List";
+const PATH: &str = "/org/todo/List";
+// Same as for the control socket, the UI loop only fails to answer this fast when it is stuck.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// The org.todo.List interface on the session bus, for desktop applets and the like. Calls are
+// handed to the UI loop just like the control socket's requests, this is the same API spoken over
+// D-Bus.
+struct List {
+    // Interfaces have to be Sync, a Sender is only Send.
+    sender: Mutex<Sender<Message>>,
+}
+
+impl List {
+    fn request(&self, method: &str, params: Value) -> fdo::Result<Value> {
+        let (reply, replies) = mpsc::channel();
+        let message = Message::Request {
+            method: method.to_string(),
+            params,
+            reply,
+        };
+        let sender = self
+            .sender
+            .lock()
+            .map_err(|_| fdo::Error::Disconnected(String::new()))?;
+        sender
+            .send(message)
+            .map_err(|_| fdo::Error::Disconnected("cli-todo is exiting".to_string()))?;
+        drop(sender);
+        replies
+            .recv_timeout(REPLY_TIMEOUT)
+            .map_err(|_| fdo::Error::TimedOut("cli-todo did not answer in time".to_string()))?
+            .map_err(fdo::Error::Failed)
+    }
+}
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[zbus::interface(name = "org.todo.List")]
+impl List {
+    fn add(&self, title: &str) -> fdo::Result<()> {
+        self.request("add", Value::object([("title", title.into())]))
+            .map(|_| ())
+    }
+
+    fn complete(&self, title: &str) -> fdo::Result<()> {
+        self.request("complete", Value::object([("title", title.into())]))
+            .map(|_| ())
+    }
+
+    #[zbus(out_args("todos", "dones"))]
+    fn list(&self) -> fdo::Result<(Vec<String>, Vec<String>)> {
+        let lists = self.request("list", Value::Null)?;
+        Ok((strings(lists.get("todos")), strings(lists.get("dones"))))
+    }
+
+    // Sent whenever either list changes, however it happened. Call List for what's in them now.
+    #[zbus(signal)]
+    async fn changed(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+pub struct Bus {
+    connection: Connection,
+    hash: u64,
+}
+
+fn hash(todos: &[Item], dones: &[Item]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (todos, dones).hash(&mut hasher);
+    hasher.finish()
+}
+
+// Claims org.todo.List on the session bus. Without a session bus, or with the name already taken
+// by another session, there is simply no D-Bus interface.
+pub fn start(runtime: &mut Runtime, todos: &[Item], dones: &[Item]) -> Option<Bus> {
+    let list = List {
+        sender: Mutex::new(runtime.sender()),
+    };
+    let connection = connection::Builder::session()
+        .and_then(|builder| builder.name(NAME))
+        .and_then(|builder| builder.serve_at(PATH, list))
+        .and_then(|builder| ctrlc::masked(|| builder.build()));
+    let connection = match connection {
+        Ok(connection) => connection,
+        Err(error) => {
+            log!(Warn, "event=dbus_failed error={:?}", error.to_string());
+            return None;
+        }
+    };
+    log!(Info, "event=dbus_listening name={}", NAME);
+
+    // zbus answers calls on a thread of its own. This task only holds on to the name until
+    // shutdown, and being a running task keeps the UI loop checking for requests often enough.
+    let inner = connection.inner().clone();
+    runtime.spawn_service_async("dbus", |_, mut cancel| async move {
+        cancel.cancelled().await;
+        let _ = inner.release_name(NAME).await;
+    });
+    Some(Bus {
+        connection,
+        hash: hash(todos, dones),
+    })
+}
+
+impl Bus {
+    // Called after every key and message. Hashing both lists is cheaper than tracking every place
+    // that changes them, and fast enough even for huge lists.
+    pub fn notify(&mut self, todos: &[Item], dones: &[Item]) {
+        let hash = hash(todos, dones);
+        if hash == self.hash {
+            return;
+        }
+        self.hash = hash;
+        if let Err(error) = self
+            .connection
+            .emit_signal(None::<()>, PATH, NAME, "Changed", &())
+        {
+            log!(Warn, "event=dbus_failed error={:?}", error.to_string());
+        }
+    }
+}
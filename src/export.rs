@@ -0,0 +1,108 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use todo_core::activity;
+use todo_core::csv;
+use todo_core::ical;
+use todo_core::markdown;
+use todo_core::merge;
+use todo_core::org;
+use todo_core::storage::{self, Format, Lists};
+
+use crate::clock;
+use crate::log::log;
+
+// What `cli-todo export` writes the lists as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Export {
+    Markdown,
+    Org,
+    Csv,
+    Ics,
+}
+
+fn load(file_path: &str, format: Format) -> io::Result<Lists> {
+    match format.storage().load(file_path, &mut |_, _| {}) {
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Lists::default()),
+        result => result,
+    }
+}
+
+// `cli-todo export`, to stdout.
+pub fn run(file_path: &str, format: Format, export: Export) -> io::Result<()> {
+    let lists = load(file_path, format)?;
+    let out = match export {
+        Export::Markdown => markdown::export(&lists),
+        Export::Org => org::export(&lists),
+        Export::Csv => {
+            let activity = activity::Log::load();
+            csv::export(&lists, &activity, clock::offset(clock::now()))
+        }
+        Export::Ics => ical::export(&lists, clock::now()),
+    };
+    print!("{}", out);
+    Ok(())
+}
+
+// `cli-todo import <path>` adds the items in `path` to the end of the lists they belong in, going
+// by its extension. Items already in the list they'd go to are left out, so importing the same file
+// twice doesn't add everything twice. Returns how many were added and how many were already there.
+// Whatever couldn't be taken over as it was is warned about on stderr.
+pub fn import(file_path: &str, format: Format, path: &str) -> io::Result<(usize, usize)> {
+    let source = fs::read_to_string(path).map_err(|error| {
+        io::Error::new(
+            error.kind(),
+            format!("{}: ERROR: could not read: {}", path, error),
+        )
+    })?;
+    let extension = Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    let theirs = match extension.as_deref() {
+        Some("md" | "markdown") => markdown::import(&source),
+        Some("org") => {
+            let (lists, warnings) = org::import(&source);
+            for (line, warning) in warnings {
+                eprintln!("{}:{}: WARNING: {}", path, line, warning);
+            }
+            lists
+        }
+        _ => {
+            return Err(io::Error::other(format!(
+                "{}: ERROR: don't know how to import that, expected a .md or .org file",
+                path
+            )))
+        }
+    };
+    let _lock = storage::lock(file_path)?;
+    let mut lists = load(file_path, format)?;
+    let (mut added, mut skipped) = (0, 0);
+    for (ours, theirs) in [
+        (&mut lists.todos, theirs.todos),
+        (&mut lists.dones, theirs.dones),
+        (&mut lists.somedays, theirs.somedays),
+    ] {
+        for item in theirs {
+            if ours.contains(&item) {
+                skipped += 1;
+            } else {
+                ours.push(item);
+                added += 1;
+            }
+        }
+    }
+    if added > 0 {
+        format.storage().save(&lists, file_path)?;
+        merge::record(file_path, &lists, clock::now())?;
+    }
+    log!(
+        Info,
+        "event=imported file={:?} from={:?} added={} skipped={}",
+        file_path,
+        path,
+        added,
+        skipped
+    );
+    Ok((added, skipped))
+}
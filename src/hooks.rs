@@ -0,0 +1,218 @@
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use todo_core::item::Item;
+use todo_core::json::Value;
+use todo_core::status::Status;
+
+use crate::log::log;
+use crate::runtime::{Cancel, Message, Runtime};
+
+pub const NAMES: [&str; 6] = [
+    "on-add",
+    "on-done",
+    "on-delete",
+    "on-save",
+    "on-pomodoro",
+    "on-reminder",
+];
+
+// Shell commands from the [hooks] section of the config, run when something happens to an item.
+// The item comes in as JSON on stdin, so one script can post to a chat, append to a journal or do
+// whatever else without cli-todo knowing about any of it.
+//
+// Webhooks from [webhooks] get the same JSON POSTed to them, with the name of the hook as `event`
+// and a line of text for chat services that want one. Every URL for a hook gets it, not only the
+// last.
+#[derive(Default)]
+pub struct Hooks {
+    commands: Vec<(String, String)>,
+    webhooks: Vec<(String, String)>,
+}
+
+pub fn item(item: &Item, status: Status) -> Value {
+    Value::object([
+        ("title", item.to_string().as_str().into()),
+        ("status", status.name().into()),
+    ])
+}
+
+// What a webhook gets, the hook's payload with the event and a line of text added, under both
+// `text` (Slack, Mattermost) and `content` (Discord).
+pub fn webhook_payload(name: &str, payload: &Value) -> Value {
+    let verb = match name {
+        "on-add" => "Added",
+        "on-done" => "Done",
+        "on-delete" => "Deleted",
+        "on-reminder" => "Reminder",
+        _ => "",
+    };
+    let title = payload.get("title").and_then(Value::as_str);
+    let text = match (verb, title) {
+        ("", _) | (_, None) => name.to_string(),
+        (verb, Some(title)) => format!("{}: {}", verb, title),
+    };
+    let mut fields = vec![
+        ("event".to_string(), name.into()),
+        ("text".to_string(), text.as_str().into()),
+        ("content".to_string(), text.as_str().into()),
+    ];
+    if let Value::Object(rest) = payload {
+        fields.extend(rest.iter().cloned());
+    }
+    Value::Object(fields)
+}
+
+// POSTs the payload to `url` with curl, which tries again a few times when the other end is down or
+// busy.
+fn post(url: &str, input: String) -> Result<Child, String> {
+    let child = Command::new("curl")
+        .args(["--silent", "--fail", "--max-time", "30"])
+        .args(["--retry", "3", "--retry-connrefused"])
+        .args(["--header", "Content-Type: application/json"])
+        .args(["--data-binary", "@-"])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = write!(stdin, "{}", input);
+            }
+            Ok(child)
+        }
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+pub fn spawn(command: &str, input: String) -> Result<Child, String> {
+    #[cfg(unix)]
+    let mut shell = Command::new("sh");
+    #[cfg(unix)]
+    shell.arg("-c");
+    #[cfg(windows)]
+    let mut shell = Command::new("cmd");
+    #[cfg(windows)]
+    shell.arg("/C");
+
+    // The terminal belongs to the UI, a hook printing into it would garble the screen.
+    let child = shell
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // A hook that doesn't care about its input may well exit before reading it.
+                let _ = writeln!(stdin, "{}", input);
+            }
+            Ok(child)
+        }
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+impl Hooks {
+    pub fn add(&mut self, name: &str, command: &str) {
+        self.commands.push((name.to_string(), command.to_string()));
+    }
+
+    pub fn add_webhook(&mut self, name: &str, url: &str) {
+        self.webhooks.push((name.to_string(), url.to_string()));
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.command(name).is_some() || self.urls(name).next().is_some()
+    }
+
+    fn urls<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.webhooks
+            .iter()
+            .filter(move |(hook, _)| hook == name)
+            .map(|(_, url)| url.as_str())
+    }
+
+    fn command(&self, name: &str) -> Option<&str> {
+        self.commands
+            .iter()
+            .rev()
+            .find(|(hook, _)| hook == name)
+            .map(|(_, command)| command.as_str())
+    }
+
+    // Starts the hook on the runtime, slow hooks must not hold up the UI. The UI only hears back
+    // when the hook fails.
+    pub fn run(&self, runtime: &mut Runtime, name: &str, payload: Value) {
+        for url in self.urls(name) {
+            log!(Debug, "event=webhook name={} url={:?}", name, url);
+            let input = webhook_payload(name, &payload).to_string();
+            let (name, url) = (name.to_string(), url.to_string());
+            runtime.spawn(&name.clone(), move |sender, cancel| {
+                let result = post(&url, input).and_then(|child| wait(child, cancel));
+                if let Err(error) = result {
+                    log!(Warn, "event=webhook_failed name={} error={:?}", name, error);
+                    let name = format!("{} webhook", name);
+                    let _ = sender.send(Message::HookFailed { name, error });
+                }
+            });
+        }
+        let Some(command) = self.command(name) else {
+            return;
+        };
+        log!(Debug, "event=hook name={} command={:?}", name, command);
+        let (name, command) = (name.to_string(), command.to_string());
+        runtime.spawn(&name.clone(), move |sender, cancel| {
+            let result = spawn(&command, payload.to_string()).and_then(|child| wait(child, cancel));
+            if let Err(error) = result {
+                log!(Warn, "event=hook_failed name={} error={:?}", name, error);
+                let _ = sender.send(Message::HookFailed { name, error });
+            }
+        });
+    }
+
+    // For hooks that run right before exiting, there's no UI left to report to.
+    pub fn run_and_wait(&self, name: &str, payload: Value) {
+        for url in self.urls(name) {
+            log!(Debug, "event=webhook name={} url={:?}", name, url);
+            let result = post(url, webhook_payload(name, &payload).to_string())
+                .and_then(|mut child| child.wait().map_err(|error| error.to_string()));
+            match result {
+                Ok(status) if status.success() => {}
+                Ok(status) => eprintln!("ERROR: webhook {} to {} failed: {}", name, url, status),
+                Err(error) => eprintln!("ERROR: webhook {} to {} failed: {}", name, url, error),
+            }
+        }
+        if let Some(command) = self.command(name) {
+            log!(Debug, "event=hook name={} command={:?}", name, command);
+            let result = spawn(command, payload.to_string())
+                .and_then(|mut child| child.wait().map_err(|error| error.to_string()));
+            match result {
+                Ok(status) if status.success() => {}
+                Ok(status) => eprintln!("ERROR: hook {} failed: {}", name, status),
+                Err(error) => eprintln!("ERROR: hook {} failed: {}", name, error),
+            }
+        }
+    }
+}
+
+// Waits for a hook or webhook to finish, killing it when the app is on its way out.
+pub fn wait(mut child: Child, cancel: &Cancel) -> Result<(), String> {
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(status.to_string()),
+            Ok(None) if cancel.is_cancelled() => {
+                let _ = child.kill();
+                return Err("killed on exit".to_string());
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(error) => return Err(error.to_string()),
+        }
+    }
+}
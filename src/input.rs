@@ -0,0 +1,32 @@
+// What the user pressed, independent of the backend that read it. Backends decode their raw key
+// codes into this and everything that reacts to keys matches on it. Never cast a raw key code to a
+// character: curses codes for special keys are above 255 and wrap around onto regular characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent {
+    Char(char),
+    Ctrl(char),
+    Alt(char),
+    // Function keys, F(1) is F1.
+    F(u8),
+    Enter,
+    Tab,
+    BackTab,
+    Escape,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    CtrlLeft,
+    CtrlRight,
+    AltLeft,
+    AltRight,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    // Not a key press as such, but the screen has to be redrawn all the same.
+    Resize,
+    Unknown,
+}
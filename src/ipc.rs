@@ -0,0 +1,142 @@
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use todo_core::json::Value;
+use todo_core::paths::socket_path;
+
+use crate::log::log;
+use crate::runtime::{Cancel, Message, Runtime};
+
+// How long a request waits for the UI to get to it. The UI loop checks for messages a few times a
+// second, so this only runs out when it is stuck.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Listens on a Unix socket for JSON-RPC requests from todo-ctl and the like, one request per line.
+// Requests are handed to the UI loop which applies them to the live lists, so they show up right
+// away and the next save doesn't overwrite them.
+pub fn start(runtime: &mut Runtime) {
+    let Some(path) = socket_path() else {
+        return;
+    };
+    // Someone else answering on the socket means another session is running. A socket nobody
+    // answers on was left behind by a session that crashed.
+    if UnixStream::connect(&path).is_ok() {
+        log!(
+            Warn,
+            "event=ipc_skipped reason=\"another session owns {}\"",
+            path.display()
+        );
+        return;
+    }
+    let _ = fs::remove_file(&path);
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(error) => {
+            log!(Warn, "event=ipc_failed error={:?}", error.to_string());
+            return;
+        }
+    };
+    // Accepting without blocking, so the task can notice it's time to quit.
+    if listener.set_nonblocking(true).is_err() {
+        return;
+    }
+    log!(
+        Info,
+        "event=ipc_listening path={:?}",
+        path.display().to_string()
+    );
+
+    runtime.spawn_service("ipc", move |sender, cancel| {
+        while !cancel.is_cancelled() {
+            match listener.accept() {
+                Ok((stream, _)) => serve(stream, sender, cancel),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Err(error) => {
+                    log!(Warn, "event=ipc_failed error={:?}", error.to_string());
+                    break;
+                }
+            }
+        }
+        let _ = fs::remove_file(&path);
+    });
+}
+
+fn response(id: Value, result: Result<Value, String>) -> Value {
+    match result {
+        Ok(result) => Value::object([("jsonrpc", "2.0".into()), ("id", id), ("result", result)]),
+        Err(message) => Value::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id),
+            (
+                "error",
+                Value::object([
+                    ("code", Value::Number(-32000.0)),
+                    ("message", message.as_str().into()),
+                ]),
+            ),
+        ]),
+    }
+}
+
+fn serve(stream: UnixStream, sender: &Sender<Message>, cancel: &Cancel) {
+    if stream.set_nonblocking(false).is_err()
+        || stream
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .is_err()
+    {
+        return;
+    }
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while !cancel.is_cancelled() {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => return,
+            Ok(_) => {}
+            Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                continue
+            }
+            Err(_) => return,
+        }
+        let request = match Value::parse(&line) {
+            Ok(request) => request,
+            Err(error) => {
+                let _ = writeln!(writer, "{}", response(Value::Null, Err(error)));
+                continue;
+            }
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let result = match request.get("method").and_then(Value::as_str) {
+            Some(method) => {
+                let (reply, replies) = mpsc::channel();
+                let message = Message::Request {
+                    method: method.to_string(),
+                    params: request.get("params").cloned().unwrap_or(Value::Null),
+                    reply,
+                };
+                if sender.send(message).is_err() {
+                    return;
+                }
+                replies
+                    .recv_timeout(REPLY_TIMEOUT)
+                    .unwrap_or_else(|_| Err("cli-todo did not answer in time".to_string()))
+            }
+            None => Err("request has no method".to_string()),
+        };
+        if writeln!(writer, "{}", response(id, result)).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use todo_core::item::Item;
+use todo_core::json::Value;
+use todo_core::merge;
+use todo_core::status::Status;
+use todo_core::storage::{self, Format, Lists};
+use todo_core::tags;
+
+use crate::clock;
+use crate::log::log;
+
+// `cli-todo jira` mirrors the Jira issues a JQL search finds, the ones assigned to you unless
+// [jira] says otherwise, into TODO as `summary #KEY-123`, and moves the issues of items that were
+// marked DONE since to done over there. It talks to the REST API through curl.
+pub struct Jira {
+    // Like https://example.atlassian.net.
+    pub url: String,
+    pub user: String,
+    // An API token, or the password on servers without those. JIRA_API_TOKEN works too.
+    pub token: String,
+    pub jql: String,
+    // The name of the transition that finishes an issue.
+    pub done: String,
+}
+
+impl Default for Jira {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            user: String::new(),
+            token: String::new(),
+            jql: "assignee = currentUser() AND statusCategory != Done".to_string(),
+            done: "Done".to_string(),
+        }
+    }
+}
+
+// Issue keys are a project key and a number, like PROJ-123.
+fn is_key(tag: &str) -> bool {
+    let Some((project, number)) = tag.rsplit_once('-') else {
+        return false;
+    };
+    project.starts_with(|x: char| x.is_ascii_uppercase())
+        && project
+            .chars()
+            .all(|x| x.is_ascii_uppercase() || x.is_ascii_digit() || x == '_')
+        && !number.is_empty()
+        && number.chars().all(|x| x.is_ascii_digit())
+}
+
+// The issue an item mirrors, from its `#KEY-123` tag.
+pub fn key(item: &str) -> Option<&str> {
+    tags::of(item).find(|tag| is_key(tag))
+}
+
+// Where the issue of `item` is in the browser.
+pub fn link(item: &str, url: &str) -> Option<String> {
+    key(item).map(|key| format!("{}/browse/{}", url.trim_end_matches('/'), key))
+}
+
+// Adds the open `issues`, as (key, summary), that no item mirrors yet to the end of TODO, and
+// returns how many it added along with the keys of the issues whose items are DONE now.
+pub fn mirror(lists: &mut Lists, issues: &[(String, String)]) -> (usize, Vec<String>) {
+    let mut known = HashMap::new();
+    for (status, list) in [
+        (Status::Todo, &lists.todos),
+        (Status::Done, &lists.dones),
+        (Status::Someday, &lists.somedays),
+    ] {
+        for item in list {
+            if let Some(key) = key(item) {
+                known.entry(key.to_string()).or_insert(status);
+            }
+        }
+    }
+    let mut added = 0;
+    let mut finished = Vec::new();
+    for (key, summary) in issues {
+        match known.get(key) {
+            None => {
+                lists
+                    .todos
+                    .push(Item::parse(&format!("{} #{}", summary, key)));
+                added += 1;
+            }
+            Some(Status::Done) => finished.push(key.clone()),
+            Some(Status::Todo | Status::Someday) => {}
+        }
+    }
+    (added, finished)
+}
+
+// Runs curl with `args`, handing it the credentials on stdin so they don't show up in `ps`.
+fn curl(jira: &Jira, args: &[&str]) -> io::Result<Value> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--config", "-"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let user = format!("{}:{}", jira.user, jira.token)
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"");
+        writeln!(stdin, "user = \"{}\"", user)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{}: ERROR: {}",
+            jira.url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let body = String::from_utf8_lossy(&output.stdout);
+    if body.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    Value::parse(&body).map_err(|error| io::Error::other(format!("{}: ERROR: {}", jira.url, error)))
+}
+
+fn search(jira: &Jira) -> io::Result<Vec<(String, String)>> {
+    let jql = format!("jql={}", jira.jql);
+    let url = format!("{}/rest/api/2/search", jira.url);
+    let found = curl(
+        jira,
+        &[
+            "--get",
+            "--data-urlencode",
+            &jql,
+            "--data-urlencode",
+            "fields=summary",
+            "--data-urlencode",
+            "maxResults=100",
+            &url,
+        ],
+    )?;
+    let Some(Value::Array(issues)) = found.get("issues") else {
+        return Ok(Vec::new());
+    };
+    Ok(issues
+        .iter()
+        .filter_map(|issue| {
+            let key = issue.get("key")?.as_str()?;
+            let summary = issue.get("fields")?.get("summary")?.as_str()?;
+            Some((key.to_string(), summary.to_string()))
+        })
+        .collect())
+}
+
+fn finish(jira: &Jira, key: &str) -> io::Result<()> {
+    let url = format!("{}/rest/api/2/issue/{}/transitions", jira.url, key);
+    let transitions = curl(jira, &[&url])?;
+    let id = match transitions.get("transitions") {
+        Some(Value::Array(transitions)) => transitions.iter().find_map(|transition| {
+            let name = transition.get("name")?.as_str()?;
+            name.eq_ignore_ascii_case(&jira.done)
+                .then(|| transition.get("id")?.as_str())
+                .flatten()
+        }),
+        _ => None,
+    };
+    let Some(id) = id else {
+        return Err(io::Error::other(format!(
+            "{}: ERROR: no `{}` transition, set `done` in [jira]",
+            key, jira.done
+        )));
+    };
+    let body = Value::object([("transition", Value::object([("id", id.into())]))]).to_string();
+    curl(
+        jira,
+        &[
+            "--header",
+            "Content-Type: application/json",
+            "--data",
+            &body,
+            &url,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn run(file_path: &str, format: Format, jira: Option<Jira>) -> io::Result<()> {
+    let mut jira = jira.unwrap_or_default();
+    if let Ok(token) = env::var("JIRA_API_TOKEN") {
+        jira.token = token;
+    }
+    if jira.url.is_empty() || jira.user.is_empty() || jira.token.is_empty() {
+        return Err(io::Error::other(
+            "ERROR: set url, user and token in [jira] to sync with Jira",
+        ));
+    }
+    let issues = search(&jira)?;
+    let _lock = storage::lock(file_path)?;
+    let mut lists = match format.storage().load(file_path, &mut |_, _| {}) {
+        Err(error) if error.kind() == ErrorKind::NotFound => Lists::default(),
+        result => result?,
+    };
+    let (added, finished) = mirror(&mut lists, &issues);
+    if added > 0 {
+        format.storage().save(&lists, file_path)?;
+        merge::record(file_path, &lists, clock::now())?;
+    }
+    let mut closed = 0;
+    for key in &finished {
+        match finish(&jira, key) {
+            Ok(()) => closed += 1,
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+    log!(
+        Info,
+        "event=jira issues={} added={} closed={}",
+        issues.len(),
+        added,
+        closed
+    );
+    println!(
+        "{} issues, {} new in TODO, {} marked done in Jira",
+        issues.len(),
+        added,
+        closed
+    );
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use crate::ctrlc;
+
+enum Message<T> {
+    Progress { done: u64, total: u64 },
+    Finished(T),
+}
+
+// Handed to the closure running on the background thread so it can tell the UI how far along it
+// is. Reporting is fire-and-forget: if the UI already went away there is nobody left to care.
+pub struct Reporter<T> {
+    sender: Sender<Message<T>>,
+}
+
+impl<T> Reporter<T> {
+    pub fn progress(&self, done: u64, total: u64) {
+        let _ = self.sender.send(Message::Progress { done, total });
+    }
+}
+
+// A long operation (loading a big file, syncing, importing) running on its own thread. The UI
+// loop is expected to call Job::poll() once per frame and keep rendering in the meantime.
+pub struct Job<T> {
+    pub name: String,
+    pub done: u64,
+    pub total: u64,
+    receiver: Receiver<Message<T>>,
+}
+
+impl<T: Send + 'static> Job<T> {
+    pub fn spawn<F>(name: &str, f: F) -> Self
+    where
+        F: FnOnce(&Reporter<T>) -> T + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        ctrlc::masked(|| {
+            thread::spawn(move || {
+                let reporter = Reporter { sender };
+                let result = f(&reporter);
+                let _ = reporter.sender.send(Message::Finished(result));
+            })
+        });
+        Self {
+            name: name.to_string(),
+            done: 0,
+            total: 0,
+            receiver,
+        }
+    }
+
+    pub fn poll(&mut self) -> Option<T> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(Message::Progress { done, total }) => {
+                    self.done = done;
+                    self.total = total;
+                }
+                Ok(Message::Finished(result)) => return Some(result),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => {
+                    panic!("Job `{}` died without reporting a result", self.name)
+                }
+            }
+        }
+    }
+
+    pub fn ratio(&self) -> Option<f32> {
+        if self.total > 0 {
+            Some(self.done as f32 / self.total as f32)
+        } else {
+            None
+        }
+    }
+}
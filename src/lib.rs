@@ -0,0 +1,655 @@
+use crate::ui::Ui;
+use app::App;
+use config::Config;
+use export::Export;
+use input::KeyEvent;
+use job::Job;
+use layout::LayoutKind;
+use log::{log, Level};
+use report::GroupBy;
+use scripting::Scripts;
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+use style::REGULAR;
+use todo_core::activity;
+use todo_core::boards;
+use todo_core::history::History;
+use todo_core::json::Value;
+use todo_core::merge;
+use todo_core::paths;
+use todo_core::pomodoro;
+use todo_core::storage::{self, Format, Lists};
+use vec2::Vec2;
+
+pub mod app;
+pub mod backend;
+mod buffer;
+mod clock;
+pub mod config;
+mod ctrlc;
+mod daemon;
+#[cfg(all(feature = "dbus", target_os = "linux"))]
+mod dbus;
+mod export;
+mod hooks;
+pub mod input;
+#[cfg(all(unix, feature = "ipc"))]
+mod ipc;
+mod jira;
+mod job;
+mod layout;
+mod log;
+mod open;
+mod picker;
+mod registers;
+mod report;
+mod runtime;
+mod scripting;
+#[cfg(feature = "serve")]
+mod serve;
+#[cfg(not(feature = "serve"))]
+mod serve {
+    use todo_core::storage::Format;
+
+    pub fn run(
+        _file_path: &str,
+        _format: Format,
+        _addr: &str,
+        _token: Option<String>,
+    ) -> Result<(), String> {
+        Err("ERROR: this cli-todo was built without the `serve` feature".to_string())
+    }
+}
+mod sound;
+mod stats;
+mod style;
+mod switcher;
+mod sync;
+#[cfg(test)]
+mod tests;
+pub mod ui;
+pub mod vec2;
+
+// How long to sleep waiting for input. Nothing needs redrawing while idle, this only bounds how
+// long it takes to notice Ctrl+C.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+// While something is in progress the spinner needs to keep turning, and background tasks should
+// be heard from without a noticeable delay.
+const SPINNER_TIMEOUT: Duration = Duration::from_millis(100);
+// How long hooks still running at exit get to finish before they are killed.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+// Reads the file on a background thread while a spinner keeps turning. Returns None when the user
+// gave up waiting.
+fn load(ui: &mut Ui, file_path: &str, format: Format) -> Option<(Lists, String)> {
+    let mut job = Job::spawn(&format!("Loading {}", file_path), {
+        let file_path = file_path.to_string();
+        move |reporter| {
+            format.storage().load(&file_path, &mut |done, total| {
+                reporter.progress(done, total)
+            })
+        }
+    });
+    log!(Info, "event=load file={:?}", file_path);
+    loop {
+        match job.poll() {
+            Some(Ok(lists)) => {
+                log!(
+                    Info,
+                    "event=loaded todos={} dones={} somedays={}",
+                    lists.todos.len(),
+                    lists.dones.len(),
+                    lists.somedays.len()
+                );
+                return Some((lists, format!("Loaded file {}", file_path)));
+            }
+            Some(Err(error)) if error.kind() == ErrorKind::NotFound => {
+                log!(Info, "event=loaded new_file=true");
+                return Some((Lists::default(), format!("New file {}", file_path)));
+            }
+            Some(Err(error)) if error.kind() == ErrorKind::InvalidData => {
+                log!(Error, "event=load_failed error={:?}", error.to_string());
+                ui.backend.shutdown();
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+            Some(Err(error)) => {
+                log!(Error, "event=load_failed error={:?}", error.to_string());
+                ui.backend.shutdown();
+                panic!(
+                    "Could not load state from file `{}`: {:?}",
+                    file_path, error
+                );
+            }
+            None => {
+                // Nothing to show or edit until the file is in memory, so just keep the spinner
+                // going and let the user bail out if it takes too long.
+                ui.clear();
+                ui.begin(Vec2::new(0, 0), LayoutKind::Vert);
+                ui.spinner(&job.name, job.ratio(), ui.backend.size().x, REGULAR);
+                ui.end();
+                ui.present();
+                let key = ui.backend.poll_key(SPINNER_TIMEOUT);
+                #[cfg(unix)]
+                if ctrlc::poll_suspend() || key == Some(KeyEvent::Ctrl('z')) {
+                    suspend(ui);
+                }
+                if ctrlc::poll() || matches!(key, Some(KeyEvent::Char('q') | KeyEvent::Ctrl('c'))) {
+                    log!(Info, "event=load_cancelled");
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+// Ctrl+Z: gives the terminal back to the shell while we are stopped and takes it back once we are
+// continued.
+#[cfg(unix)]
+fn suspend(ui: &mut Ui) {
+    log!(Info, "event=suspend");
+    ui.backend.shutdown();
+    ctrlc::stop();
+    ui.resume();
+    log!(Info, "event=resume");
+}
+
+// Restores the terminal before the panic message is printed, otherwise it ends up somewhere in the
+// alternate screen in raw mode and is gone along with it.
+fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        // A background thread panicking doesn't take the UI down with it.
+        if thread::current().name() == Some("main") {
+            backend::restore();
+        }
+        log!(Error, "event=panic info={:?}", info.to_string());
+        default_hook(info);
+    }));
+}
+
+// After a panic the lists may be halfway through a change, so they go next to the file rather
+// than over it, for the user to compare.
+fn recover(app: &App, file_path: &str, format: Format) -> ! {
+    let recovery = format!("{}.recovery", file_path);
+    let lists = Lists {
+        todos: app.todos.clone(),
+        dones: app.dones.clone(),
+        somedays: app.somedays.clone(),
+    };
+    match format.storage().save(&lists, &recovery) {
+        Ok(()) => {
+            log!(Info, "event=recovered file={:?}", recovery);
+            eprintln!(
+                "Saved the lists as they were to {}, check them against {} before using them",
+                recovery, file_path
+            );
+        }
+        Err(error) => {
+            log!(Error, "event=recover_failed error={:?}", error.to_string());
+            eprintln!("ERROR: could not save {}: {}", recovery, error);
+        }
+    }
+    process::exit(101);
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: cli-todo [--format <lines|todotxt|json|sqlite>] [--list <name>] [--log <error|warn|info|debug|trace>]"
+    );
+    eprintln!("       cli-todo serve [--addr <host:port>] [--token <token>] [--log <level>]");
+    eprintln!("       cli-todo daemon [status] [--log <level>]");
+    eprintln!("       cli-todo stats [--heatmap [--months <n>]]");
+    eprintln!(
+        "       cli-todo report [--week | --since <date> | --range <from>..<to>] [--markdown]"
+    );
+    eprintln!("                       [--group-by <tag|list|priority>] [--html]");
+    eprintln!("       cli-todo summary [--format <plain|markdown>]");
+    eprintln!("       cli-todo merge <other-file>");
+    eprintln!("       cli-todo sync [--remote <user@host:path>] [--review]");
+    eprintln!("       cli-todo jira");
+    eprintln!("       cli-todo export <--markdown | --org | --csv | --ics>");
+    eprintln!("       cli-todo import <file.md | file.org>");
+    process::exit(1);
+}
+
+// The whole program is a library so the benchmarks in benches/ can get at the App, main.rs only
+// calls this.
+pub fn run() {
+    let mut args = env::args().skip(1).peekable();
+    let serving = args.next_if(|arg| arg == "serve").is_some();
+    let daemon = !serving && args.next_if(|arg| arg == "daemon").is_some();
+    let status = daemon && args.next_if(|arg| arg == "status").is_some();
+    let stats = !serving && !daemon && args.next_if(|arg| arg == "stats").is_some();
+    let report = !serving && !daemon && !stats && args.next_if(|arg| arg == "report").is_some();
+    let summary =
+        !serving && !daemon && !stats && !report && args.next_if(|arg| arg == "summary").is_some();
+    let other = match args.next_if(|arg| arg == "merge") {
+        Some(_) if !serving && !daemon && !stats && !report && !summary => {
+            Some(args.next().unwrap_or_else(|| usage()))
+        }
+        _ => None,
+    };
+    let syncing = !serving
+        && !daemon
+        && !stats
+        && !report
+        && !summary
+        && other.is_none()
+        && args.next_if(|arg| arg == "sync").is_some();
+    let mut remote = None;
+    let mut review = false;
+    let jira = !serving
+        && !daemon
+        && !stats
+        && !report
+        && !summary
+        && other.is_none()
+        && !syncing
+        && args.next_if(|arg| arg == "jira").is_some();
+    let taken =
+        serving || daemon || stats || report || summary || other.is_some() || syncing || jira;
+    let exporting = !taken && args.next_if(|arg| arg == "export").is_some();
+    let import = match args.next_if(|arg| arg == "import") {
+        Some(_) if !taken && !exporting => Some(args.next().unwrap_or_else(|| usage())),
+        _ => None,
+    };
+    let mut export = None;
+    let mut since = None;
+    let mut range = None;
+    let mut group_by = GroupBy::Tag;
+    let mut markdown = false;
+    let mut html = false;
+    let mut heatmap = None;
+    let mut format = None;
+    let mut list = boards::HERE.to_string();
+    let mut addr = "127.0.0.1:7878".to_string();
+    // The environment keeps the token out of `ps`.
+    let mut token = env::var("CLI_TODO_TOKEN").ok().filter(|x| !x.is_empty());
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" if serving => addr = args.next().unwrap_or_else(|| usage()),
+            "--token" if serving => token = Some(args.next().unwrap_or_else(|| usage())),
+            // Half a year unless --months says otherwise.
+            "--heatmap" if stats => heatmap = Some(6),
+            "--months" if heatmap.is_some() => {
+                let months = args.next().and_then(|x| x.parse().ok());
+                heatmap = Some(months.unwrap_or_else(|| usage()));
+            }
+            // The last 7 days, which is what it does anyway.
+            "--week" if report => (since, range) = (None, None),
+            "--since" if report => since = Some(args.next().unwrap_or_else(|| usage())),
+            "--range" if report => range = Some(args.next().unwrap_or_else(|| usage())),
+            "--group-by" if report => {
+                let name = args.next().unwrap_or_else(|| usage());
+                group_by = GroupBy::parse(&name).unwrap_or_else(|| usage());
+            }
+            "--markdown" if report => markdown = true,
+            "--html" if report => html = true,
+            "--markdown" if exporting => export = Some(Export::Markdown),
+            "--org" if exporting => export = Some(Export::Org),
+            "--csv" if exporting => export = Some(Export::Csv),
+            "--ics" if exporting => export = Some(Export::Ics),
+            "--remote" if syncing => remote = Some(args.next().unwrap_or_else(|| usage())),
+            "--review" if syncing => review = true,
+            "--format" if summary => {
+                markdown = match args.next().as_deref() {
+                    Some("markdown") => true,
+                    Some("plain") => false,
+                    _ => usage(),
+                }
+            }
+            "--format" => {
+                let name = args.next().unwrap_or_else(|| usage());
+                format = Some(Format::parse(&name).unwrap_or_else(|error| {
+                    eprintln!("ERROR: {}", error);
+                    process::exit(1);
+                }));
+            }
+            "--list" => {
+                list = args.next().unwrap_or_else(|| usage());
+                if list != boards::HERE {
+                    if let Err(error) = boards::check(&list) {
+                        eprintln!("ERROR: {}", error);
+                        process::exit(1);
+                    }
+                }
+            }
+            "--log" => {
+                let level = args
+                    .next()
+                    .and_then(|x| Level::parse(&x))
+                    .unwrap_or_else(|| usage());
+                if let Err(error) = log::init(level) {
+                    eprintln!("{}", error);
+                    process::exit(1);
+                }
+            }
+            _ => usage(),
+        }
+    }
+
+    if stats {
+        let config = Config::load().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        });
+        stats::run(
+            heatmap,
+            config.unicode.unwrap_or_else(backend::unicode_locale),
+        );
+        return;
+    }
+
+    let format = format.unwrap_or_else(|| {
+        Config::load()
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(1);
+            })
+            .format
+    });
+    // Named lists are under the data dir, the one in the current directory is where it always was.
+    let lists_dir = paths::data_dir().map(|dir| dir.join("lists"));
+    let file_path = match &lists_dir {
+        Some(dir) => boards::path(dir, &list, format),
+        None if list == boards::HERE => format.file_name().into(),
+        None => {
+            eprintln!("ERROR: no data directory to keep named lists in, set XDG_DATA_HOME or HOME");
+            process::exit(1);
+        }
+    };
+    if list != boards::HERE {
+        if let Some(Err(error)) = file_path.parent().map(fs::create_dir_all) {
+            eprintln!(
+                "ERROR: could not create the directory of list {}: {}",
+                list, error
+            );
+            process::exit(1);
+        }
+    }
+    let file_path = file_path.to_string_lossy().into_owned();
+
+    if report {
+        let (since, range) = (since.as_deref(), range.as_deref());
+        let result = if html {
+            report::run_html(&file_path, format, since, range)
+        } else {
+            report::run(&file_path, format, since, range, markdown, group_by)
+        };
+        if let Err(error) = result {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
+
+    ctrlc::init();
+
+    if summary {
+        if let Err(error) = report::run_summary(&file_path, format, markdown) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(other) = other {
+        match sync::merge(&file_path, format, &other) {
+            Ok(changed) => println!(
+                "Merged {} into {}, {} changes taken in",
+                other, file_path, changed
+            ),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if syncing {
+        let remote = remote.or_else(|| {
+            Config::load()
+                .unwrap_or_else(|error| {
+                    eprintln!("{}", error);
+                    process::exit(1);
+                })
+                .remote
+        });
+        let Some(remote) = remote else {
+            eprintln!("ERROR: nowhere to sync to, give --remote or set `remote` in [sync]");
+            process::exit(1);
+        };
+        if review {
+            match sync::review(&file_path, &remote) {
+                Ok(Some(copy)) => println!(
+                    "Fetched {} to {}, cli-todo goes through it when it starts, sync again after",
+                    remote, copy
+                ),
+                Ok(None) => println!("Nothing at {} yet, sync without --review", remote),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    process::exit(1);
+                }
+            }
+            return;
+        }
+        match sync::run(&file_path, format, &remote) {
+            Ok(changed) => println!(
+                "Synced {} with {}, {} changes taken in",
+                file_path, remote, changed
+            ),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if jira {
+        let config = Config::load().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        });
+        if let Err(error) = jira::run(&file_path, format, config.jira) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if exporting {
+        let export = export.unwrap_or_else(|| usage());
+        if let Err(error) = export::run(&file_path, format, export) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(path) = import {
+        match export::import(&file_path, format, &path) {
+            Ok((added, skipped)) => println!(
+                "Imported {} items from {} into {}, {} were there already",
+                added, path, file_path, skipped
+            ),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if serving {
+        if let Err(error) = serve::run(&file_path, format, &addr, token) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // Exits with 1 when no daemon is running, for scripts.
+    if status {
+        match daemon::status() {
+            Ok(running) => process::exit(if running { 0 } else { 1 }),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        }
+    }
+
+    if daemon {
+        let config = Config::load().map(|config| Config { format, ..config });
+        if let Err(error) = config.and_then(|config| daemon::run(&file_path, config)) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // Held until we exit, so `cli-todo serve` can't change the file under our feet.
+    let lock = storage::lock(&file_path).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+
+    let config = Config::load().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+    let config = Config { format, ..config };
+
+    let scripts = Scripts::load().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+
+    install_panic_hook();
+    let mut ui = Ui::new(backend::init());
+    if let Some(unicode) = config.unicode {
+        ui.unicode = unicode;
+    }
+    ui.select_with(config.selection);
+    for (tag, color) in &config.tag_colors {
+        ui.palette.color_tag(tag, *color);
+    }
+
+    let Some((lists, notification)) = load(&mut ui, &file_path, format) else {
+        // Interrupted before the file finished loading, saving now would only wipe it.
+        ui.backend.shutdown();
+        return;
+    };
+
+    let mut app = App::new(lists.todos, lists.dones, config, History::load());
+    app.somedays = lists.somedays;
+    app.config_path = paths::config_path();
+    app.scripts = scripts;
+    app.pomodoros = pomodoro::Log::load();
+    app.activity = activity::Log::load();
+    app.notification = notification;
+    app.file_path = file_path.clone();
+    app.list = list;
+    app.lock = Some(lock);
+    app.lists_dir = lists_dir;
+    app.archive_done(&file_path);
+    app.settle();
+    match merge::conflicts(&file_path) {
+        Ok(conflicts) if !conflicts.is_empty() => app.open_conflicts(&file_path, conflicts),
+        Ok(_) => {}
+        Err(error) => log!(Warn, "event=conflicts_failed error={:?}", error.to_string()),
+    }
+    #[cfg(all(unix, feature = "ipc"))]
+    ipc::start(&mut app.runtime);
+    #[cfg(all(feature = "dbus", target_os = "linux"))]
+    let mut bus = dbus::start(&mut app.runtime, &app.todos, &app.dones);
+
+    // A bug in there shouldn't cost the edits made so far. The panic hook already gave the
+    // terminal back, what's left is getting the lists to disk.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        // Nothing changes on the screen unless a key comes in, so only draw after one did and sleep
+        // in between.
+        let mut redraw = true;
+        while !app.quit && !ctrlc::poll() {
+            #[cfg(unix)]
+            if std::mem::take(&mut app.suspend) | ctrlc::poll_suspend() {
+                suspend(&mut ui);
+                redraw = true;
+            } else if ctrlc::poll_resume() {
+                // Stopped and continued by someone else, the terminal may not be how we left it.
+                ui.resume();
+                redraw = true;
+            }
+            if std::mem::take(&mut app.bell) {
+                ui.backend.bell();
+            }
+            app.size = ui.backend.size();
+            if redraw {
+                ui.clear();
+                app.view(&mut ui);
+                ui.present();
+            }
+            let timeout = if app.runtime.busy() {
+                SPINNER_TIMEOUT
+            } else {
+                IDLE_TIMEOUT
+            };
+            let timeout = app
+                .timer_timeout()
+                .map_or(timeout, |timer| timer.min(timeout));
+            redraw = match ui.backend.poll_key(timeout) {
+                Some(key) => {
+                    app.handle_event(key);
+                    true
+                }
+                None => false,
+            };
+            while let Some(message) = app.runtime.poll() {
+                app.handle_message(message);
+                redraw = true;
+            }
+            redraw |= app.tick(Instant::now());
+            #[cfg(all(feature = "dbus", target_os = "linux"))]
+            if let (true, Some(bus)) = (redraw, &mut bus) {
+                bus.notify(&app.todos, &app.dones);
+            }
+        }
+    }));
+    if result.is_err() {
+        recover(&app, &app.file_path, format);
+    }
+
+    ui.backend.shutdown();
+
+    // :list may have switched to other lists since.
+    let file_path = app.file_path.clone();
+    match app.save() {
+        Ok(()) => {
+            // After SIGHUP there is no terminal left to print to, which println!() would panic on.
+            let _ = writeln!(io::stdout(), "Saved state to {}", file_path);
+        }
+        Err(error) => {
+            log!(Error, "event=save_failed error={:?}", error.to_string());
+            eprintln!("ERROR: could not save state to {}: {}", file_path, error);
+            process::exit(1);
+        }
+    }
+
+    app.hooks.run_and_wait(
+        "on-save",
+        Value::object([
+            ("file", file_path.as_str().into()),
+            ("todos", app.todos[..].into()),
+            ("dones", app.dones[..].into()),
+        ]),
+    );
+    app.runtime.shutdown(SHUTDOWN_GRACE);
+}
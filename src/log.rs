@@ -0,0 +1,97 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use todo_core::paths::data_dir;
+
+// Logging is off unless asked for with `--log <level>`, and then goes to a file since the terminal
+// belongs to the UI. Every line is a timestamp, the level and `key=value` fields, so it can be
+// grepped and attached to a bug report as is.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "error" => Some(Self::Error),
+            "warn" => Some(Self::Warn),
+            "info" => Some(Self::Info),
+            "debug" => Some(Self::Debug),
+            "trace" => Some(Self::Trace),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+            Self::Trace => "TRACE",
+        }
+    }
+}
+
+// 0 means logging is off.
+static LEVEL: AtomicU8 = AtomicU8::new(0);
+static FILE: Mutex<Option<File>> = Mutex::new(None);
+
+pub fn path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("log"))
+}
+
+pub fn init(level: Level) -> Result<PathBuf, String> {
+    let path = path().ok_or("ERROR: could not figure out where to put the log")?;
+    let file = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| OpenOptions::new().create(true).append(true).open(&path))
+        .map_err(|error| format!("{}: ERROR: could not open log: {}", path.display(), error))?;
+    *FILE.lock().unwrap_or_else(|error| error.into_inner()) = Some(file);
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    Ok(path)
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+// Use the log! macro instead, it doesn't format anything when the level is off.
+pub fn write(level: Level, args: fmt::Arguments) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut file = FILE.lock().unwrap_or_else(|error| error.into_inner());
+    if let Some(file) = file.as_mut() {
+        // Nowhere left to report a failing log to.
+        let _ = writeln!(
+            file,
+            "{}.{:03} {:5} {}",
+            timestamp.as_secs(),
+            timestamp.subsec_millis(),
+            level.name(),
+            args
+        );
+    }
+}
+
+// log!(Debug, "event=save file={}", file_path)
+macro_rules! log {
+    ($level:ident, $($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Level::$level) {
+            $crate::log::write($crate::log::Level::$level, format_args!($($arg)*));
+        }
+    };
+}
+pub(crate) use log;
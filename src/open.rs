@@ -0,0 +1,35 @@
+use std::env;
+use std::io;
+use std::process::{Command, Stdio};
+use std::thread;
+
+// Opens a URL or a file with whatever the desktop opens those with, without waiting for it.
+pub fn open(target: &str) -> io::Result<()> {
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(windows)]
+    let mut command = Command::new("cmd");
+    #[cfg(windows)]
+    command.args(["/C", "start", ""]);
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    // The terminal belongs to the UI, the opener printing into it would garble the screen.
+    let mut child = command
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Someone has to reap it.
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+// `~/specs/login.pdf` in the home directory, the opener wouldn't know what `~` means.
+pub fn expand(path: &str) -> String {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home.trim_end_matches('/'), rest),
+        _ => path.to_string(),
+    }
+}
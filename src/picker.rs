@@ -0,0 +1,98 @@
+use std::cmp;
+
+use todo_core::fuzzy;
+use todo_core::status::Status;
+
+use crate::config::Header;
+use crate::layout::LayoutKind;
+use crate::style::{REGULAR, SELECTION};
+use crate::ui::{EditState, Ui};
+use crate::vec2::Vec2;
+
+// The Ctrl+P finder: a box over the lists with the items that fuzzy::score() says match what's
+// typed into it, best first, for getting to an item in a long list without scrolling to it.
+#[derive(Default)]
+pub struct Picker {
+    pub query: String,
+    // Which of the matches is picked.
+    pub curr: usize,
+}
+
+impl Picker {
+    // The `items` that match, best first and in the order they came in between equally good ones.
+    pub fn matches<'a>(
+        &self,
+        items: impl IntoIterator<Item = (Status, usize, &'a str)>,
+    ) -> Vec<(Status, usize, &'a str)> {
+        let mut found: Vec<_> = items
+            .into_iter()
+            .filter_map(|(panel, index, item)| {
+                let score = fuzzy::score(&self.query, item)?;
+                Some((score, (panel, index, item)))
+            })
+            .collect();
+        found.sort_by_key(|(score, _)| cmp::Reverse(*score));
+        found.into_iter().map(|(_, found)| found).collect()
+    }
+
+    // Draws the box over whatever is on the screen of `size`, `found` being what matches() gave
+    // among `total` items. Every row is padded out to the full width so nothing shows through.
+    pub fn view(
+        &self,
+        ui: &mut Ui,
+        edit: &EditState,
+        found: &[(Status, usize, &str)],
+        total: usize,
+        header: &Header,
+        size: Vec2,
+    ) {
+        let width = cmp::max(size.x - 4, 8);
+        let inner = (width - 2) as usize;
+        let page = cmp::max(size.y - 5, 1) as usize;
+        let scroll = (self.curr + 1).saturating_sub(page);
+        let (side, fill, top, bottom) = if ui.unicode {
+            ("│", "─", ("┌", "┐"), ("└", "┘"))
+        } else {
+            ("|", "-", ("+", "+"), ("+", "+"))
+        };
+        let rule = |(left, right): (&str, &str), label: &str| {
+            let rest = inner.saturating_sub(label.chars().count() + 1);
+            format!("{}{}{}{}{}", left, fill, label, fill.repeat(rest), right)
+        };
+        let titles = [Status::Todo, Status::Done, Status::Someday].map(|panel| header.title(panel));
+        let mark_width = titles.iter().map(|title| title.chars().count()).max();
+        let mark_width = mark_width.unwrap_or(0) + 1;
+
+        ui.begin(Vec2::new((size.x - width) / 2, 1), LayoutKind::Vert);
+        {
+            ui.label_fixed_width(&rule(top, " Find "), width, REGULAR);
+            ui.begin_layout(LayoutKind::Horz);
+            {
+                // Padded, the field only draws as far as the text goes.
+                let query = format!("{:<w$}", self.query, w = inner - 2);
+                ui.label_fixed_width(&format!("{}> ", side), 3, REGULAR);
+                ui.edit_field(&query, edit, width - 4, &[]);
+                ui.label_fixed_width(side, 1, REGULAR);
+            }
+            ui.end_layout();
+            for row in scroll..scroll + page {
+                ui.begin_layout(LayoutKind::Horz);
+                ui.label_fixed_width(side, 1, REGULAR);
+                match found.get(row) {
+                    Some((panel, _, item)) => {
+                        let mark = format!("{:<mark_width$}", header.title(*panel));
+                        let text = format!("{:<inner$}", item);
+                        let style = if row == self.curr { SELECTION } else { REGULAR };
+                        ui.list_item(&mark, &text, inner as i32, style);
+                    }
+                    None => ui.label_fixed_width(&" ".repeat(inner), inner as i32, REGULAR),
+                }
+                ui.label_fixed_width(side, 1, REGULAR);
+                ui.end_layout();
+            }
+            let count = format!(" {}/{} ", found.len(), total);
+            ui.label_fixed_width(&rule(bottom, &count), width, REGULAR);
+        }
+        ui.end();
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use todo_core::item::Item;
+
+// Vim-style registers for cut and yanked items. Every cut/yank lands in the unnamed register, and
+// additionally in a named one when it was prefixed with `"x`. Using the uppercase name appends to
+// the register instead of replacing it, which is how several groups of items get staged together.
+#[derive(Default)]
+pub struct Registers {
+    unnamed: Vec<Item>,
+    named: HashMap<char, Vec<Item>>,
+}
+
+impl Registers {
+    pub fn is_valid_name(name: char) -> bool {
+        name.is_ascii_alphabetic()
+    }
+
+    pub fn store(&mut self, name: Option<char>, items: Vec<Item>) {
+        match name {
+            Some(name) if name.is_ascii_uppercase() => {
+                self.named
+                    .entry(name.to_ascii_lowercase())
+                    .or_default()
+                    .extend(items.iter().cloned());
+            }
+            Some(name) => {
+                self.named.insert(name, items.clone());
+            }
+            None => {}
+        }
+        self.unnamed = items;
+    }
+
+    pub fn get(&self, name: Option<char>) -> &[Item] {
+        match name {
+            Some(name) => self
+                .named
+                .get(&name.to_ascii_lowercase())
+                .map(Vec::as_slice)
+                .unwrap_or(&[]),
+            None => &self.unnamed,
+        }
+    }
+}
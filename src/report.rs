@@ -0,0 +1,453 @@
+use std::io::ErrorKind;
+
+use todo_core::activity::{self, Entry, Event};
+use todo_core::date::{self, parse_span, DateTime};
+use todo_core::item::Item;
+use todo_core::matrix;
+use todo_core::schedule;
+use todo_core::storage::{Format, Lists};
+use todo_core::tags;
+
+use crate::clock;
+
+const DAY: i64 = 24 * 60 * 60;
+
+// What `--group-by` puts the items of a report under.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    Tag,
+    // The list the item is in now.
+    List,
+    // Where the item is in the Eisenhower matrix at the end of the range.
+    Priority,
+}
+
+impl GroupBy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "tag" => Some(GroupBy::Tag),
+            "list" => Some(GroupBy::List),
+            "priority" => Some(GroupBy::Priority),
+            _ => None,
+        }
+    }
+
+    // The groups `title` is in, none for the one that goes last.
+    fn groups(self, title: &str, lists: &Lists, at: DateTime) -> Vec<String> {
+        match self {
+            GroupBy::Tag => tags::of(title).map(|tag| format!("#{}", tag)).collect(),
+            GroupBy::List => [
+                ("TODO", &lists.todos),
+                ("DONE", &lists.dones),
+                ("SOMEDAY", &lists.somedays),
+            ]
+            .into_iter()
+            .find(|(_, list)| list.iter().any(|item| item.title == title))
+            .map(|(name, _)| name.to_string())
+            .into_iter()
+            .collect(),
+            GroupBy::Priority => {
+                // Due dates are on the items, the log only has titles.
+                let item = [&lists.todos, &lists.dones, &lists.somedays]
+                    .into_iter()
+                    .flatten()
+                    .find(|item| item.title == title)
+                    .cloned()
+                    .unwrap_or_else(|| Item::parse(title));
+                vec![matrix::quadrant(&item, at).name().to_string()]
+            }
+        }
+    }
+
+    fn rest(self) -> &'static str {
+        match self {
+            GroupBy::Tag => "Untagged",
+            GroupBy::List => "Archived or deleted",
+            GroupBy::Priority => "",
+        }
+    }
+}
+
+// Titles of the items that had `event` happen to them in the range, grouped in the order the groups
+// first come up. An item in several groups, like one with several tags, is in each of them, one in
+// none goes last. Every title is there once even when it happened to it more than once.
+fn grouped<'a>(
+    entries: &'a [Entry],
+    event: Event,
+    since: i64,
+    until: i64,
+    group_by: GroupBy,
+    lists: &Lists,
+    at: DateTime,
+) -> Vec<(String, Vec<&'a str>)> {
+    let mut groups: Vec<(String, Vec<&str>)> = Vec::new();
+    let mut rest = Vec::new();
+    for entry in entries {
+        if entry.event != event || entry.time < since || entry.time >= until {
+            continue;
+        }
+        let title = entry.title.as_str();
+        let names = group_by.groups(title, lists, at);
+        if names.is_empty() && !rest.contains(&title) {
+            rest.push(title);
+        }
+        for name in names {
+            let position = match groups.iter().position(|(other, _)| *other == name) {
+                Some(position) => position,
+                None => {
+                    groups.push((name, Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            if !groups[position].1.contains(&title) {
+                groups[position].1.push(title);
+            }
+        }
+    }
+    if !rest.is_empty() {
+        groups.push((group_by.rest().to_string(), rest));
+    }
+    groups
+}
+
+// What got done and what was added from `from` up to `to`, both local midnights, as plain text or
+// Markdown. `lists` are only needed for grouping by list.
+pub fn render(
+    entries: &[Entry],
+    from: DateTime,
+    to: DateTime,
+    offset: i64,
+    markdown: bool,
+    group_by: GroupBy,
+    lists: &Lists,
+) -> String {
+    let (since, until) = (from.to_unix(offset), to.to_unix(offset));
+    let last = to.add_days(-1).to_item();
+    let mut out = if markdown {
+        format!("# Report {} to {}\n", from.to_item(), last)
+    } else {
+        format!("Report {} to {}\n", from.to_item(), last)
+    };
+    for (title, event, mark) in [
+        ("Completed", Event::Done, "[x] "),
+        ("Added", Event::Added, "[ ] "),
+    ] {
+        let groups = grouped(entries, event, since, until, group_by, lists, to);
+        let mut titles: Vec<&str> = groups
+            .iter()
+            .flat_map(|(_, titles)| titles)
+            .copied()
+            .collect();
+        titles.sort_unstable();
+        titles.dedup();
+        if markdown {
+            out += &format!("\n## {} ({})\n", title, titles.len());
+        } else {
+            out += &format!("\n{} ({})\n", title, titles.len());
+        }
+        for (name, titles) in groups {
+            if markdown {
+                out += &format!("\n### {} ({})\n\n", name, titles.len());
+            } else {
+                out += &format!("  {} ({})\n", name, titles.len());
+            }
+            for title in titles {
+                if markdown {
+                    out += &format!("- {}{}\n", mark, title);
+                } else {
+                    out += &format!("    - {}\n", title);
+                }
+            }
+        }
+    }
+    out
+}
+
+// Reads a day for --since and --range, as its midnight.
+fn parse_day(text: &str, now: DateTime) -> Result<DateTime, String> {
+    date::parse_when(text, now)
+        .map(|day| DateTime {
+            hour: 0,
+            minute: 0,
+            ..day
+        })
+        .ok_or_else(|| format!("ERROR: `{}` is not a day, try 2026-06-01", text))
+}
+
+// The days a report is about, as the first one and the one after the last. `range` is either
+// `<from>..<to>`, both ends included and the end today when it's left out, or how far back from
+// today like `2w`.
+fn parse_range(
+    range: &str,
+    today: DateTime,
+    now: DateTime,
+) -> Result<(DateTime, DateTime), String> {
+    if let Some((from, to)) = range.split_once("..") {
+        let from = parse_day(from, now)?;
+        let to = match to {
+            "" => today,
+            to => parse_day(to, now)?,
+        };
+        if to < from {
+            return Err(format!("ERROR: `{}` ends before it starts", range));
+        }
+        return Ok((from, to.add_days(1)));
+    }
+    match parse_span(range) {
+        Some(span) if span >= DAY => Ok((today.add_days(1 - span / DAY), today.add_days(1))),
+        _ => Err(format!(
+            "ERROR: `{}` is not a range, try 2026-10-01..2026-10-14 or 2w",
+            range
+        )),
+    }
+}
+
+// The days a report is about at `now`: the last 7 up to today unless `since` or `range` say
+// otherwise.
+fn days(
+    since: Option<&str>,
+    range: Option<&str>,
+    now: DateTime,
+) -> Result<(DateTime, DateTime), String> {
+    let today = DateTime {
+        hour: 0,
+        minute: 0,
+        ..now
+    };
+    match (since, range) {
+        (_, Some(range)) => parse_range(range, today, now),
+        (Some(text), None) => {
+            let from = parse_day(text, now)?;
+            if from > now {
+                return Err(format!(
+                    "ERROR: `{}` is not a day in the past, try 2026-06-01",
+                    text
+                ));
+            }
+            Ok((from, today.add_days(1)))
+        }
+        (None, None) => Ok((today.add_days(-6), today.add_days(1))),
+    }
+}
+
+fn load(file_path: &str, format: Format) -> Result<Lists, String> {
+    match format.storage().load(file_path, &mut |_, _| {}) {
+        Ok(lists) => Ok(lists),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Lists::default()),
+        Err(error) => Err(format!("{}: ERROR: could not read: {}", file_path, error)),
+    }
+}
+
+// `cli-todo report`.
+pub fn run(
+    file_path: &str,
+    format: Format,
+    since: Option<&str>,
+    range: Option<&str>,
+    markdown: bool,
+    group_by: GroupBy,
+) -> Result<(), String> {
+    let (from, to) = days(since, range, clock::local())?;
+    let lists = match group_by {
+        GroupBy::List => load(file_path, format)?,
+        _ => Lists::default(),
+    };
+    let activity = activity::Log::load();
+    let offset = clock::offset(clock::now());
+    print!(
+        "{}",
+        render(
+            &activity.entries,
+            from,
+            to,
+            offset,
+            markdown,
+            group_by,
+            &lists
+        )
+    );
+    Ok(())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const STYLE: &str =
+    "body { font-family: sans-serif; max-width: 40em; margin: 2em auto; color: #222; }
+h1 { font-size: 1.5em; }
+h2 { font-size: 1.2em; margin-top: 1.5em; }
+table { border-collapse: collapse; }
+th { text-align: left; font-weight: normal; padding-right: 2em; }
+td { text-align: right; font-weight: bold; }
+progress { width: 100%; }
+.overdue li { color: #b00; }
+.done li { color: #777; text-decoration: line-through; }";
+
+// The lists as they are at `now` as a page of its own, with no links to anything, for people who
+// don't live in a terminal: how many items are open and done, how many got done and added from
+// `from` up to `to`, both local midnights, what's overdue and then TODO and DONE in full.
+pub fn render_html(
+    lists: &Lists,
+    entries: &[Entry],
+    from: DateTime,
+    to: DateTime,
+    offset: i64,
+    now: DateTime,
+) -> String {
+    let (since, until) = (from.to_unix(offset), to.to_unix(offset));
+    let title = format!("Report {} to {}", from.to_item(), to.add_days(-1).to_item());
+    let count = |event: Event| {
+        let mut titles: Vec<&str> = entries
+            .iter()
+            .filter(|entry| entry.event == event && (since..until).contains(&entry.time))
+            .map(|entry| entry.title.as_str())
+            .collect();
+        titles.sort_unstable();
+        titles.dedup();
+        titles.len()
+    };
+    let (open, done) = (lists.todos.len(), lists.dones.len());
+    let percent = (done * 100).checked_div(open + done).unwrap_or(0);
+    let overdue: Vec<&Item> = lists
+        .todos
+        .iter()
+        .filter(|item| item.due_by().is_some_and(|due| due <= now))
+        .collect();
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, STYLE, title
+    );
+    out += "<table>\n";
+    for (name, value) in [
+        ("Open", open),
+        ("Done", done),
+        ("Completed in these days", count(Event::Done)),
+        ("Added in these days", count(Event::Added)),
+    ] {
+        out += &format!("<tr><th>{}</th><td>{}</td></tr>\n", name, value);
+    }
+    out += "</table>\n";
+    out += &format!(
+        "<p><progress value=\"{}\" max=\"{}\"></progress> {}% done</p>\n",
+        done,
+        open + done,
+        percent
+    );
+    for (name, class, items) in [
+        ("Overdue", "overdue", overdue),
+        ("TODO", "todo", lists.todos.iter().collect()),
+        ("DONE", "done", lists.dones.iter().collect()),
+    ] {
+        out += &format!("<h2>{} ({})</h2>\n", name, items.len());
+        if items.is_empty() {
+            continue;
+        }
+        out += &format!("<ul class=\"{}\">\n", class);
+        for item in items {
+            out += &format!("<li>{}</li>\n", escape_html(&item.to_string()));
+        }
+        out += "</ul>\n";
+    }
+    out += "</body>\n</html>\n";
+    out
+}
+
+// `cli-todo report --html`, over the same days as the others.
+pub fn run_html(
+    file_path: &str,
+    format: Format,
+    since: Option<&str>,
+    range: Option<&str>,
+) -> Result<(), String> {
+    let now = clock::local();
+    let (from, to) = days(since, range, now)?;
+    let lists = load(file_path, format)?;
+    let activity = activity::Log::load();
+    let offset = clock::offset(clock::now());
+    print!(
+        "{}",
+        render_html(&lists, &activity.entries, from, to, offset, now)
+    );
+    Ok(())
+}
+
+// The morning digest: TODO items overdue at `now`, the rest of today's, and what got done the day
+// before, as plain text or Markdown.
+pub fn summary(
+    todos: &[Item],
+    entries: &[Entry],
+    now: DateTime,
+    offset: i64,
+    markdown: bool,
+) -> String {
+    let today = DateTime {
+        hour: 0,
+        minute: 0,
+        ..now
+    };
+    let overdue: Vec<&Item> = todos
+        .iter()
+        .filter(|item| item.due_by().is_some_and(|due| due <= now))
+        .collect();
+    let due_today: Vec<&Item> = todos
+        .iter()
+        .filter(|item| schedule::is_today(item, now) && !overdue.contains(item))
+        .collect();
+    let (since, until) = (today.add_days(-1).to_unix(offset), today.to_unix(offset));
+    let mut yesterday: Vec<&str> = Vec::new();
+    for entry in entries {
+        let done = matches!(entry.event, Event::Done | Event::Checked);
+        if done && (since..until).contains(&entry.time) && !yesterday.contains(&&entry.title[..]) {
+            yesterday.push(&entry.title);
+        }
+    }
+    let mut out = if markdown {
+        format!("# Summary for {}\n", today.to_item())
+    } else {
+        format!("Summary for {}\n", today.to_item())
+    };
+    let lines = |items: Vec<&Item>| items.iter().map(ToString::to_string).collect();
+    for (title, items, mark) in [
+        ("Overdue", lines(overdue), "[ ] "),
+        ("Today", lines(due_today), "[ ] "),
+        (
+            "Done yesterday",
+            yesterday
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            "[x] ",
+        ),
+    ] {
+        if markdown {
+            out += &format!("\n## {} ({})\n\n", title, items.len());
+        } else {
+            out += &format!("\n{} ({})\n", title, items.len());
+        }
+        for item in items {
+            if markdown {
+                out += &format!("- {}{}\n", mark, item);
+            } else {
+                out += &format!("  - {}\n", item);
+            }
+        }
+    }
+    out
+}
+
+// `cli-todo summary`, meant to be piped into mail from cron.
+pub fn run_summary(file_path: &str, format: Format, markdown: bool) -> Result<(), String> {
+    let todos = load(file_path, format)?.todos;
+    let activity = activity::Log::load();
+    let offset = clock::offset(clock::now());
+    print!(
+        "{}",
+        summary(&todos, &activity.entries, clock::local(), offset, markdown)
+    );
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::future::Future;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use todo_core::json::Value;
+
+use crate::ctrlc;
+use crate::log::log;
+
+// Everything background tasks have to tell the UI.
+pub enum Message {
+    HookFailed {
+        name: String,
+        error: String,
+    },
+    // Something outside wants the lists changed or looked at, the answer goes back through `reply`.
+    // The Unix control socket and the D-Bus service send these.
+    #[cfg_attr(
+        not(any(all(unix, feature = "ipc"), all(target_os = "linux", feature = "dbus"))),
+        allow(dead_code)
+    )]
+    Request {
+        method: String,
+        params: Value,
+        reply: Sender<Result<Value, String>>,
+    },
+}
+
+// Handed to every task. Long running tasks are expected to check it every now and then and wrap
+// up once it's set, async ones can await cancelled() next to whatever they are waiting for.
+#[derive(Clone)]
+pub struct Cancel(watch::Receiver<bool>);
+
+impl Cancel {
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    #[cfg_attr(not(all(feature = "dbus", target_os = "linux")), allow(dead_code))]
+    pub async fn cancelled(&mut self) {
+        // Only fails once the Runtime is gone, which takes everything down with it anyway.
+        let _ = self.0.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+// Runs work that mustn't block the UI (hooks, sounds, the control socket and D-Bus, later syncing,
+// reminders and the like) on a tokio runtime. Results come back as Messages which the main loop
+// picks up with poll() between key presses. Tasks that block, like waiting on a hook, get a thread
+// from tokio's blocking pool, async ones share a couple of worker threads. The runtime is only
+// started with the first task, plenty of sessions never have one.
+pub struct Runtime {
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+    tokio: Option<tokio::runtime::Runtime>,
+    cancel: watch::Sender<bool>,
+    tasks: Vec<(String, JoinHandle<()>)>,
+    // Services run until told to stop, like the control socket, and are stopped first thing on
+    // shutdown instead of being waited for.
+    services: watch::Sender<bool>,
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            tokio: None,
+            cancel: watch::Sender::new(false),
+            tasks: Vec::new(),
+            services: watch::Sender::new(false),
+        }
+    }
+}
+
+impl Runtime {
+    pub fn spawn<F>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce(&Sender<Message>, &Cancel) + Send + 'static,
+    {
+        let cancel = Cancel(self.cancel.subscribe());
+        self.start(name, cancel, f);
+    }
+
+    #[cfg_attr(not(all(unix, feature = "ipc")), allow(dead_code))]
+    pub fn spawn_service<F>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce(&Sender<Message>, &Cancel) + Send + 'static,
+    {
+        let cancel = Cancel(self.services.subscribe());
+        self.start(name, cancel, f);
+    }
+
+    // Like spawn_service(), for services that spend their time awaiting instead of blocking.
+    #[cfg_attr(not(all(feature = "dbus", target_os = "linux")), allow(dead_code))]
+    pub fn spawn_service_async<F, T>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce(Sender<Message>, Cancel) -> T,
+        T: Future<Output = ()> + Send + 'static,
+    {
+        log!(Debug, "event=task_spawned name={:?}", name);
+        let task = f(self.sender.clone(), Cancel(self.services.subscribe()));
+        let handle = self.tokio().spawn(task);
+        self.tasks.push((name.to_string(), handle));
+    }
+
+    // For services that take requests on threads they don't own, like the D-Bus one.
+    #[cfg_attr(not(all(feature = "dbus", target_os = "linux")), allow(dead_code))]
+    pub fn sender(&self) -> Sender<Message> {
+        self.sender.clone()
+    }
+
+    // Threads made with the signals blocked keep them blocked, so Ctrl+C and friends always land on
+    // the UI thread. The blocking pool grows from whoever spawns onto it, hence the mask there too.
+    fn tokio(&mut self) -> &tokio::runtime::Runtime {
+        self.tokio.get_or_insert_with(|| {
+            ctrlc::masked(|| {
+                tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(2)
+                    .thread_name("todo-runtime")
+                    .build()
+                    .expect("can't start the task runtime")
+            })
+        })
+    }
+
+    fn start<F>(&mut self, name: &str, cancel: Cancel, f: F)
+    where
+        F: FnOnce(&Sender<Message>, &Cancel) + Send + 'static,
+    {
+        log!(Debug, "event=task_spawned name={:?}", name);
+        let sender = self.sender.clone();
+        let tokio = self.tokio();
+        let handle = ctrlc::masked(|| tokio.spawn_blocking(move || f(&sender, &cancel)));
+        self.tasks.push((name.to_string(), handle));
+    }
+
+    pub fn poll(&mut self) -> Option<Message> {
+        self.tasks.retain(|(_, handle)| !handle.is_finished());
+        self.receiver.try_recv().ok()
+    }
+
+    // Whether anything is still running, the main loop then checks for messages more often.
+    pub fn busy(&self) -> bool {
+        self.tasks.iter().any(|(_, handle)| !handle.is_finished())
+    }
+
+    // Stops the services, gives running tasks up to `grace` to finish on their own, then tells them
+    // to stop too and waits a moment more. Whatever still runs after that is left behind, exiting
+    // takes it down anyway.
+    pub fn shutdown(mut self, grace: Duration) {
+        let wait = |tasks: &mut Vec<(String, JoinHandle<()>)>, timeout: Duration| {
+            let deadline = Instant::now() + timeout;
+            while Instant::now() < deadline {
+                tasks.retain(|(_, handle)| !handle.is_finished());
+                if tasks.is_empty() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        };
+        self.services.send_replace(true);
+        wait(&mut self.tasks, grace);
+        self.cancel.send_replace(true);
+        wait(&mut self.tasks, Duration::from_millis(200));
+        for (name, _) in &self.tasks {
+            log!(Warn, "event=task_abandoned name={:?}", name);
+        }
+        // Dropping the runtime would wait for the blocking tasks still running.
+        if let Some(tokio) = self.tokio.take() {
+            tokio.shutdown_background();
+        }
+    }
+}
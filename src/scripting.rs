@@ -0,0 +1,277 @@
+// User scripts live in $XDG_CONFIG_HOME/cli-todo/init.rhai and may define any of these functions:
+//
+//     fn on_add(title) { ... }       // returns the title to store instead
+//     fn on_complete(title) { ... }  // the item was just marked DONE
+//     fn on_key(key) { ... }         // every key in normal mode, return true to swallow it
+//     fn command_<name>(args) { }    // runs on `:<name> args...`, may return a message
+//
+// on_key and the commands get the lists as `this.todos` and `this.dones`, plus `this.panel`
+// ("todo", "done" or "someday"), and whatever they change in them is what the app ends up with.
+//
+// Without the `scripting` feature there is no engine and every hook does nothing.
+
+#[cfg(feature = "scripting")]
+mod imp {
+    use std::fs;
+    use std::io::ErrorKind;
+
+    use rhai::{Array, CallFnOptions, Dynamic, Engine, FuncArgs, Map, AST};
+    use todo_core::item::Item;
+    use todo_core::paths::config_dir;
+    use todo_core::status::Status;
+
+    use crate::input::KeyEvent;
+
+    // Enough for any reasonable hook, while an accidental endless loop only freezes the UI for a
+    // moment instead of forever.
+    const MAX_OPERATIONS: u64 = 1_000_000;
+
+    pub struct Scripts {
+        engine: Engine,
+        ast: AST,
+    }
+
+    fn key_name(key: KeyEvent) -> String {
+        match key {
+            KeyEvent::Char(c) => c.to_string(),
+            KeyEvent::Ctrl(c) => format!("ctrl-{}", c),
+            KeyEvent::Alt(c) => format!("alt-{}", c),
+            KeyEvent::F(n) => format!("f{}", n),
+            key => format!("{:?}", key).to_lowercase(),
+        }
+    }
+
+    // Scripts get the items as the lines they're saved as, due dates and all.
+    fn to_array(items: &[Item]) -> Array {
+        items.iter().map(|item| item.to_string().into()).collect()
+    }
+
+    fn from_array(value: Option<&Dynamic>) -> Result<Vec<Item>, String> {
+        let items = value
+            .cloned()
+            .ok_or("Script removed a list")?
+            .into_array()
+            .map_err(|_| "Script replaced a list with something that isn't one")?;
+        items
+            .into_iter()
+            .map(|item| {
+                item.into_string()
+                    .map(|line| Item::parse(&line))
+                    .map_err(|_| "Script put something that isn't a string into a list".to_string())
+            })
+            .collect()
+    }
+
+    impl Default for Scripts {
+        fn default() -> Self {
+            let mut engine = Engine::new();
+            engine.set_max_operations(MAX_OPERATIONS);
+            Self {
+                engine,
+                ast: AST::empty(),
+            }
+        }
+    }
+
+    impl Scripts {
+        pub fn load() -> Result<Self, String> {
+            let mut scripts = Self::default();
+            let Some(path) = config_dir().map(|dir| dir.join("init.rhai")) else {
+                return Ok(scripts);
+            };
+            let source = match fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(error) if error.kind() == ErrorKind::NotFound => String::new(),
+                Err(error) => {
+                    return Err(format!(
+                        "{}: ERROR: could not read script: {}",
+                        path.display(),
+                        error
+                    ))
+                }
+            };
+            scripts.ast = scripts
+                .engine
+                .compile(&source)
+                .map_err(|error| format!("{}: ERROR: {}", path.display(), error))?;
+            // Top level statements run once, so scripts can set things up.
+            scripts
+                .engine
+                .run_ast(&scripts.ast)
+                .map_err(|error| format!("{}: ERROR: {}", path.display(), error))?;
+            Ok(scripts)
+        }
+
+        fn has(&self, name: &str) -> bool {
+            self.ast
+                .iter_functions()
+                .any(|function| function.name == name)
+        }
+
+        // Calls `name` with the lists bound to `this` and takes back whatever the script did to
+        // them.
+        fn call_with_lists(
+            &self,
+            name: &str,
+            args: impl FuncArgs,
+            todos: &mut Vec<Item>,
+            dones: &mut Vec<Item>,
+            panel: Status,
+        ) -> Result<Dynamic, String> {
+            let mut state = Map::new();
+            state.insert("todos".into(), to_array(todos).into());
+            state.insert("dones".into(), to_array(dones).into());
+            state.insert("panel".into(), panel.name().into());
+            let mut this = Dynamic::from_map(state);
+
+            let options = CallFnOptions::new()
+                .eval_ast(false)
+                .bind_this_ptr(&mut this);
+            let result = self
+                .engine
+                .call_fn_with_options::<Dynamic>(
+                    options,
+                    &mut rhai::Scope::new(),
+                    &self.ast,
+                    name,
+                    args,
+                )
+                .map_err(|error| format!("{}: {}", name, error))?;
+
+            let state = this
+                .try_cast::<Map>()
+                .ok_or("Script replaced `this` with something that isn't a map")?;
+            *todos = from_array(state.get("todos"))?;
+            *dones = from_array(state.get("dones"))?;
+            Ok(result)
+        }
+
+        pub fn on_add(&self, item: &mut Item) -> Result<(), String> {
+            if !self.has("on_add") {
+                return Ok(());
+            }
+            let options = CallFnOptions::new().eval_ast(false);
+            let line = self
+                .engine
+                .call_fn_with_options::<String>(
+                    options,
+                    &mut rhai::Scope::new(),
+                    &self.ast,
+                    "on_add",
+                    (item.to_string(),),
+                )
+                .map_err(|error| format!("on_add: {}", error))?;
+            *item = Item::parse(&line);
+            Ok(())
+        }
+
+        pub fn on_complete(&self, item: &Item) -> Result<(), String> {
+            if !self.has("on_complete") {
+                return Ok(());
+            }
+            let options = CallFnOptions::new().eval_ast(false);
+            self.engine
+                .call_fn_with_options::<Dynamic>(
+                    options,
+                    &mut rhai::Scope::new(),
+                    &self.ast,
+                    "on_complete",
+                    (item.to_string(),),
+                )
+                .map(|_| ())
+                .map_err(|error| format!("on_complete: {}", error))
+        }
+
+        pub fn handles_keys(&self) -> bool {
+            self.has("on_key")
+        }
+
+        pub fn on_key(
+            &self,
+            key: KeyEvent,
+            todos: &mut Vec<Item>,
+            dones: &mut Vec<Item>,
+            panel: Status,
+        ) -> Result<bool, String> {
+            if !self.has("on_key") {
+                return Ok(false);
+            }
+            let result = self.call_with_lists("on_key", (key_name(key),), todos, dones, panel)?;
+            Ok(result.as_bool().unwrap_or(false))
+        }
+
+        pub fn command(
+            &self,
+            source: &str,
+            todos: &mut Vec<Item>,
+            dones: &mut Vec<Item>,
+            panel: Status,
+        ) -> Option<Result<String, String>> {
+            let mut words = source.split_whitespace();
+            let name = format!("command_{}", words.next()?);
+            if !self.has(&name) {
+                return None;
+            }
+            let args: Array = words.map(|word| word.to_string().into()).collect();
+            let result = self.call_with_lists(&name, (args,), todos, dones, panel);
+            Some(result.map(|message| {
+                if message.is_unit() {
+                    String::new()
+                } else {
+                    message.to_string()
+                }
+            }))
+        }
+    }
+}
+
+#[cfg(not(feature = "scripting"))]
+mod imp {
+    use todo_core::item::Item;
+    use todo_core::status::Status;
+
+    use crate::input::KeyEvent;
+
+    #[derive(Default)]
+    pub struct Scripts {}
+
+    impl Scripts {
+        pub fn load() -> Result<Self, String> {
+            Ok(Self {})
+        }
+
+        pub fn on_add(&self, _item: &mut Item) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn on_complete(&self, _item: &Item) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn handles_keys(&self) -> bool {
+            false
+        }
+
+        pub fn on_key(
+            &self,
+            _key: KeyEvent,
+            _todos: &mut Vec<Item>,
+            _dones: &mut Vec<Item>,
+            _panel: Status,
+        ) -> Result<bool, String> {
+            Ok(false)
+        }
+
+        pub fn command(
+            &self,
+            _source: &str,
+            _todos: &mut Vec<Item>,
+            _dones: &mut Vec<Item>,
+            _panel: Status,
+        ) -> Option<Result<String, String>> {
+            None
+        }
+    }
+}
+
+pub use imp::Scripts;
@@ -0,0 +1,433 @@
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use todo_core::item::Item;
+use todo_core::json::Value;
+use todo_core::list;
+use todo_core::merge;
+use todo_core::storage::{self, Format, Lists};
+
+use crate::clock;
+use crate::ctrlc;
+use crate::log::log;
+
+// Nobody types a title this long, anything bigger is a mistake or someone poking at the server.
+const MAX_BODY: usize = 64 * 1024;
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+// A client that stops sending halfway through would otherwise hold up everyone after it, requests
+// are handled one at a time.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Either way a response is a status and something to put in the body, errors get wrapped into
+// {"error": "..."}.
+type Response = Result<(u16, Value), (u16, String)>;
+
+// `cli-todo serve`: the same lists over HTTP, for phone shortcuts, tiny web pages and whatever else
+// speaks HTTP better than it speaks terminal. Every change is written to the file right away under
+// the storage lock, so the server and the UI can't save over each other.
+pub fn run(
+    file_path: &str,
+    format: Format,
+    addr: &str,
+    token: Option<String>,
+) -> Result<(), String> {
+    let listener = TcpListener::bind(addr)
+        .map_err(|error| format!("ERROR: could not listen on {}: {}", addr, error))?;
+    let local = listener
+        .local_addr()
+        .map_err(|error| format!("ERROR: {}", error))?;
+    // Anyone on the network could otherwise empty the list.
+    if token.is_none() && !local.ip().is_loopback() {
+        return Err(format!(
+            "ERROR: refusing to serve on {} without a token, pass --token or set CLI_TODO_TOKEN",
+            local
+        ));
+    }
+    // Accepting without blocking, so Ctrl+C gets noticed.
+    listener
+        .set_nonblocking(true)
+        .map_err(|error| format!("ERROR: {}", error))?;
+    log!(Info, "event=serve addr={} file={:?}", local, file_path);
+    println!("Serving {} on http://{}", file_path, local);
+
+    while !ctrlc::poll() {
+        // Nothing on the terminal to give back, just stop.
+        #[cfg(unix)]
+        if ctrlc::poll_suspend() {
+            ctrlc::stop();
+        }
+        match listener.accept() {
+            Ok((stream, _)) => handle(stream, file_path, format, token.as_deref()),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => wait(&listener),
+            Err(error) => log!(Warn, "event=accept_failed error={:?}", error.to_string()),
+        }
+    }
+    Ok(())
+}
+
+// Sleeps until a connection or a signal comes in.
+#[cfg(unix)]
+fn wait(listener: &TcpListener) {
+    use std::os::fd::AsRawFd;
+
+    let mut fds = vec![libc::pollfd {
+        fd: listener.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    }];
+    if let Some(fd) = ctrlc::fd() {
+        fds.push(libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        });
+    }
+    unsafe {
+        libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1);
+    }
+}
+
+#[cfg(not(unix))]
+fn wait(_listener: &TcpListener) {
+    std::thread::sleep(Duration::from_millis(50));
+}
+
+fn handle(mut stream: TcpStream, file_path: &str, format: Format, token: Option<&str>) {
+    if stream.set_nonblocking(false).is_err()
+        || stream.set_read_timeout(Some(READ_TIMEOUT)).is_err()
+    {
+        return;
+    }
+    let (method, path, response) = match read_request(&stream) {
+        Ok(request) => {
+            let response = if token.is_none() && request.header("Origin").is_some() {
+                // Without a token any web page open in the browser could otherwise change the
+                // lists on a loopback server, browsers send Origin with everything cross-site.
+                Err((
+                    403,
+                    "requests from web pages need a token, serve with --token".to_string(),
+                ))
+            } else if request.method == "OPTIONS" {
+                // The CORS preflight a browser sends before the real request, it carries no
+                // token.
+                Ok((204, Value::Null))
+            } else if !authorized(&request, token) {
+                Err((401, "missing or wrong token".to_string()))
+            } else {
+                route(&request, file_path, format)
+            };
+            (request.method, request.path, response)
+        }
+        Err(response) => (String::new(), String::new(), Err(response)),
+    };
+    let status = match &response {
+        Ok((status, _)) | Err((status, _)) => *status,
+    };
+    log!(
+        Info,
+        "event=http method={} path={:?} status={}",
+        method,
+        path,
+        status
+    );
+    let _ = write_response(&mut stream, response, token.is_some());
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String, (u16, String)> {
+    let mut line = String::new();
+    reader
+        .take(MAX_LINE)
+        .read_line(&mut line)
+        .map_err(|error| (400, error.to_string()))?;
+    if !line.ends_with('\n') {
+        return Err((400, "request line or header too long".to_string()));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, (u16, String)> {
+    let mut reader = BufReader::new(stream);
+    let line = read_line(&mut reader)?;
+    let mut parts = line.split(' ');
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err((400, "malformed request line".to_string()));
+    };
+    let path = target.split('?').next().unwrap_or("").to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err((431, "too many headers".to_string()));
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or((400, "malformed header".to_string()))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+    let mut request = Request {
+        method: method.to_string(),
+        path,
+        headers,
+        body: String::new(),
+    };
+
+    let length = match request.header("Content-Length") {
+        Some(length) => length
+            .parse::<usize>()
+            .map_err(|_| (400, "invalid Content-Length".to_string()))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err((413, "body too large".to_string()));
+    }
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|error| (400, error.to_string()))?;
+    request.body = String::from_utf8(body).map_err(|_| (400, "body is not UTF-8".to_string()))?;
+    Ok(request)
+}
+
+// Compares every byte whatever the first difference, so the time it takes gives nothing away
+// about the token.
+fn authorized(request: &Request, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let given = request
+        .header("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or("");
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+// Web pages only get to talk to the server when it has a token, `cors` says so.
+fn write_response(stream: &mut TcpStream, response: Response, cors: bool) -> io::Result<()> {
+    let (status, body) = match response {
+        Ok((204, _)) => (204, String::new()),
+        Ok((status, body)) => (status, body.to_string()),
+        Err((status, message)) => (
+            status,
+            Value::object([("error", message.as_str().into())]).to_string(),
+        ),
+    };
+    let cors = if cors {
+        "Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, PUT, DELETE\r\n\
+         Access-Control-Allow-Headers: Authorization, Content-Type\r\n"
+    } else {
+        ""
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         {}\
+         Connection: close\r\n\
+         \r\n\
+         {}",
+        status,
+        reason(status),
+        body.len(),
+        cors,
+        body
+    )?;
+    stream.flush()
+}
+
+fn item(index: usize, item: &Item) -> Value {
+    Value::object([
+        ("index", index.into()),
+        ("title", item.to_string().as_str().into()),
+    ])
+}
+
+fn title(body: &str) -> Result<String, (u16, String)> {
+    let body = Value::parse(body).map_err(|error| (400, error))?;
+    let title = body
+        .get("title")
+        .and_then(Value::as_str)
+        .ok_or((400, "missing `title`".to_string()))?;
+    if title.is_empty() || title.contains(['\n', '\r']) {
+        return Err((400, "title must be a single non-empty line".to_string()));
+    }
+    Ok(title.to_string())
+}
+
+fn load(file_path: &str, format: Format) -> Result<Lists, (u16, String)> {
+    match format.storage().load(file_path, &mut |_, _| {}) {
+        Ok(lists) => Ok(lists),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(Lists::default()),
+        Err(error) => Err((500, error.to_string())),
+    }
+}
+
+// Loads the lists, lets `f` change them and saves them back, all while holding the lock. `f` gets
+// the list the path is about first and the other one second, which is where `done` and `undo`
+// move items to.
+fn modify(
+    file_path: &str,
+    format: Format,
+    name: &str,
+    f: impl FnOnce(&mut Vec<Item>, &mut Vec<Item>) -> Response,
+) -> Response {
+    let _lock = storage::lock(file_path).map_err(|error| match error.kind() {
+        ErrorKind::WouldBlock => (409, error.to_string()),
+        _ => (500, error.to_string()),
+    })?;
+    // SOMEDAY isn't served, it only has to survive the round trip.
+    let Lists {
+        mut todos,
+        mut dones,
+        somedays,
+    } = load(file_path, format)?;
+    let response = match name {
+        "todos" => f(&mut todos, &mut dones)?,
+        _ => f(&mut dones, &mut todos)?,
+    };
+    let lists = Lists {
+        todos,
+        dones,
+        somedays,
+    };
+    format
+        .storage()
+        .save(&lists, file_path)
+        .map_err(|error| (500, error.to_string()))?;
+    log!(
+        Info,
+        "event=saved file={:?} todos={} dones={}",
+        file_path,
+        lists.todos.len(),
+        lists.dones.len()
+    );
+    // The save already happened, a state that's behind only makes a later merge less exact.
+    if let Err(error) = merge::record(file_path, &lists, clock::now()) {
+        log!(Warn, "event=state_failed error={:?}", error.to_string());
+    }
+    Ok(response)
+}
+
+fn route(request: &Request, file_path: &str, format: Format) -> Response {
+    let segments: Vec<&str> = request
+        .path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let (name, index, action) = match segments[..] {
+        [name] => (name, None, None),
+        [name, index] => (name, Some(index), None),
+        [name, index, action] => (name, Some(index), Some(action)),
+        _ => return Err((404, "not found".to_string())),
+    };
+    if name != "todos" && name != "dones" {
+        return Err((404, "not found".to_string()));
+    }
+    let index = match index.map(str::parse::<usize>) {
+        Some(Ok(index)) => Some(index),
+        Some(Err(_)) => return Err((404, "not found".to_string())),
+        None => None,
+    };
+    let missing = || (404, format!("no item {} in {}", index.unwrap_or(0), name));
+    let method = request.method.as_str();
+
+    match (method, index, action) {
+        ("GET", None, None) => {
+            let lists = load(file_path, format)?;
+            let items = if name == "todos" {
+                lists.todos
+            } else {
+                lists.dones
+            };
+            Ok((200, items[..].into()))
+        }
+        ("GET", Some(index), None) => {
+            let lists = load(file_path, format)?;
+            let items = if name == "todos" {
+                lists.todos
+            } else {
+                lists.dones
+            };
+            Ok((200, item(index, items.get(index).ok_or_else(missing)?)))
+        }
+        ("POST", None, None) => {
+            let title = title(&request.body)?;
+            modify(file_path, format, name, |items, _| {
+                items.push(Item::parse(&title));
+                Ok((201, item(items.len() - 1, &items[items.len() - 1])))
+            })
+        }
+        ("PUT", Some(index), None) => {
+            let title = title(&request.body)?;
+            modify(file_path, format, name, |items, _| {
+                *items.get_mut(index).ok_or_else(missing)? = Item::parse(&title);
+                Ok((200, item(index, &items[index])))
+            })
+        }
+        ("DELETE", Some(index), None) => modify(file_path, format, name, |items, _| {
+            let mut curr = index;
+            list::delete(items, &mut curr).ok_or_else(missing)?;
+            Ok((204, Value::Null))
+        }),
+        ("POST", Some(index), Some(action))
+            if (name, action) == ("todos", "done") || (name, action) == ("dones", "undo") =>
+        {
+            modify(file_path, format, name, |items, other| {
+                if index >= items.len() {
+                    return Err(missing());
+                }
+                let mut curr = index;
+                list::transfer(other, items, &mut curr);
+                Ok((200, item(other.len() - 1, &other[other.len() - 1])))
+            })
+        }
+        (_, _, None) => Err((405, format!("{} is not allowed here", method))),
+        _ => Err((404, "not found".to_string())),
+    }
+}
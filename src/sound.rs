@@ -0,0 +1,55 @@
+use crate::hooks;
+use crate::log::log;
+use crate::runtime::{Message, Runtime};
+
+pub const EVENTS: [&str; 3] = ["done", "pomodoro", "overdue"];
+
+// What to play when something happens, from the [sounds] section of the config. Nothing plays
+// unless it says so.
+pub enum Sound {
+    // The terminal's bell, which is a beep or a flash depending on the terminal.
+    Bell,
+    // A shell command, like `paplay ~/sounds/done.oga`.
+    Command(String),
+}
+
+#[derive(Default)]
+pub struct Sounds {
+    sounds: Vec<(String, Sound)>,
+}
+
+impl Sounds {
+    // `bell`, a shell command, or `off` for nothing.
+    pub fn set(&mut self, event: &str, value: &str) {
+        self.sounds.retain(|(other, _)| other != event);
+        let sound = match value {
+            "off" | "no" | "" => return,
+            "bell" => Sound::Bell,
+            command => Sound::Command(command.to_string()),
+        };
+        self.sounds.push((event.to_string(), sound));
+    }
+
+    // Starts the command for `event` on the runtime, or returns true when the bell should ring.
+    // Ringing it is up to whoever has the terminal.
+    pub fn play(&self, runtime: &mut Runtime, event: &str) -> bool {
+        let Some((_, sound)) = self.sounds.iter().find(|(other, _)| other == event) else {
+            return false;
+        };
+        let command = match sound {
+            Sound::Bell => return true,
+            Sound::Command(command) => command.clone(),
+        };
+        log!(Debug, "event=sound name={} command={:?}", event, command);
+        let name = format!("{} sound", event);
+        runtime.spawn(&name.clone(), move |sender, cancel| {
+            let result =
+                hooks::spawn(&command, String::new()).and_then(|child| hooks::wait(child, cancel));
+            if let Err(error) = result {
+                log!(Warn, "event=sound_failed name={:?} error={:?}", name, error);
+                let _ = sender.send(Message::HookFailed { name, error });
+            }
+        });
+        false
+    }
+}
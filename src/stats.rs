@@ -0,0 +1,119 @@
+use todo_core::activity::{self, Event};
+use todo_core::date::{format_span, DateTime};
+
+use crate::clock;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const LEVELS_UNICODE: [char; 5] = ['·', '░', '▒', '▓', '█'];
+const LEVELS_ASCII: [char; 5] = ['.', ':', 'o', 'O', '#'];
+
+// A GitHub style calendar of the items done over the last `weeks` weeks, this one included: a
+// column per week with Monday on top, darker the more got done that day. Month names go on the
+// first row, a legend on the last.
+pub fn heatmap(
+    activity: &activity::Log,
+    today: DateTime,
+    weeks: usize,
+    unicode: bool,
+) -> Vec<String> {
+    let levels = if unicode {
+        LEVELS_UNICODE
+    } else {
+        LEVELS_ASCII
+    };
+    let first = today.add_days(-(today.weekday() as i64) - 7 * (weeks as i64 - 1));
+    let offset = clock::offset(clock::now());
+    let counts = activity.daily(Event::Done, first.to_unix(offset), weeks * 7);
+    let max = counts.iter().copied().max().unwrap_or(0);
+    let level = |count: usize| {
+        if count == 0 {
+            0
+        } else {
+            // The busiest day gets the darkest, one item the lightest.
+            1 + (count - 1) * 3 / (max - 1).max(1)
+        }
+    };
+
+    let mut months = " ".repeat(4);
+    for week in 0..weeks {
+        let monday = first.add_days(week as i64 * 7);
+        let column = 4 + week * 2;
+        // Above the week the month starts in, unless the name before is still in the way.
+        if monday.day <= 7 && months.len() <= column {
+            months += &" ".repeat(column - months.len());
+            months += MONTHS[monday.month as usize - 1];
+        }
+    }
+    let mut rows = vec![months.trim_end().to_string()];
+    for weekday in 0..7 {
+        let mut row = match weekday {
+            0 => "Mon ",
+            2 => "Wed ",
+            4 => "Fri ",
+            _ => "    ",
+        }
+        .to_string();
+        for week in 0..weeks {
+            let day = week * 7 + weekday;
+            if first.add_days(day as i64) > today {
+                break;
+            }
+            row.push(levels[level(counts[day])]);
+            row.push(' ');
+        }
+        rows.push(row.trim_end().to_string());
+    }
+    let total: usize = counts.iter().sum();
+    let legend: Vec<String> = levels.iter().map(char::to_string).collect();
+    rows.push(format!(
+        "    Less {} More   {} done in {} weeks",
+        legend.join(" "),
+        total,
+        weeks
+    ));
+    rows
+}
+
+// `cli-todo stats`: what the stats screen shows, for the terminal and scripts.
+pub fn run(heatmap_months: Option<u32>, unicode: bool) {
+    let activity = activity::Log::load();
+    let today = DateTime {
+        hour: 0,
+        minute: 0,
+        ..clock::local()
+    };
+    match heatmap_months {
+        Some(months) => {
+            // Close enough, a month is four and a bit weeks.
+            let weeks = (months as usize * 30).div_ceil(7).max(1);
+            for row in heatmap(&activity, today, weeks, unicode) {
+                println!("{}", row);
+            }
+        }
+        None => {
+            let offset = clock::offset(clock::now());
+            let counts = activity.daily(Event::Done, today.add_days(-29).to_unix(offset), 30);
+            let week: usize = counts[30 - 7..].iter().sum();
+            let month: usize = counts.iter().sum();
+            println!(
+                "Done today: {}, in the last 7 days: {}, in the last 30 days: {}",
+                counts[29], week, month
+            );
+            let streak = activity.streak(clock::now(), offset);
+            println!(
+                "Streak: {} days in a row, {} at best",
+                streak.current, streak.best
+            );
+            match activity.completion_time() {
+                Some((time, count)) => println!(
+                    "Average time to complete: {} over {} items",
+                    format_span(time),
+                    count
+                ),
+                None => println!("Nothing completed since the stats started"),
+            }
+        }
+    }
+}
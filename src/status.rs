@@ -1,14 +0,0 @@
-#[derive(Debug, PartialEq)]
-pub enum Status {
-    Todo,
-    Done,
-}
-
-impl Status {
-    pub fn toggle(&self) -> Self {
-        match self {
-            Status::Todo => Status::Done,
-            Status::Done => Status::Todo,
-        }
-    }
-}
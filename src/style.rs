@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+pub const REGULAR: &str = "regular";
+pub const SELECTION: &str = "selection";
+pub const CURSOR: &str = "cursor";
+pub const OVERDUE: &str = "overdue";
+pub const SOON: &str = "soon";
+pub const GAUGE: &str = "gauge";
+pub const GHOST: &str = "ghost";
+pub const DEFERRED: &str = "deferred";
+pub const STALE: &str = "stale";
+// `pri:A` to `pri:C` in the lists.
+pub const PRIORITIES: [&str; 3] = ["priority-a", "priority-b", "priority-c"];
+
+// The eight colors every terminal worth supporting has. The built-in palette doesn't need all of
+// them but there is no reason the backends shouldn't know how to draw them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    pub const ALL: [Color; 8] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Color::Black => "black",
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Blue => "blue",
+            Color::Magenta => "magenta",
+            Color::Cyan => "cyan",
+            Color::White => "white",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|color| color.name() == name)
+    }
+}
+
+// What tags are drawn in unless the config picks one, the colors that stand out from the black
+// background and from each other.
+const TAG_COLORS: [Color; 6] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+];
+
+// How the current item stands out: in reverse colors, with a marker in front of it and in bold for
+// monochrome terminals and eyes the colors don't work for, or both.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Selection {
+    #[default]
+    Reverse,
+    Marker,
+    Both,
+}
+
+impl Selection {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "reverse" => Some(Selection::Reverse),
+            "marker" => Some(Selection::Marker),
+            "both" => Some(Selection::Both),
+            _ => None,
+        }
+    }
+}
+
+pub const BOLD: u8 = 1 << 0;
+pub const DIM: u8 = 1 << 1;
+pub const UNDERLINE: u8 = 1 << 2;
+pub const REVERSE: u8 = 1 << 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub attrs: u8,
+}
+
+impl Style {
+    pub const fn new(fg: Color, bg: Color) -> Self {
+        Self { fg, bg, attrs: 0 }
+    }
+
+    pub const fn with_attrs(self, attrs: u8) -> Self {
+        Self {
+            attrs: self.attrs | attrs,
+            ..self
+        }
+    }
+}
+
+// Widgets ask for styles by their meaning ("selection", "overdue") and the palette tells them what
+// that looks like. How a style ends up on the screen is up to the backend.
+pub struct Palette {
+    styles: HashMap<String, Style>,
+    // Tags with a color of their own from the config, without the `#`.
+    tags: HashMap<String, Color>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        use Color::*;
+        let mut palette = Self {
+            styles: HashMap::new(),
+            tags: HashMap::new(),
+        };
+        palette.define(REGULAR, Style::new(White, Black));
+        palette.define(SELECTION, Style::new(Black, White));
+        palette.define(CURSOR, Style::new(Black, White));
+        palette.define(GHOST, Style::new(White, Black).with_attrs(DIM));
+        palette.define(DEFERRED, Style::new(White, Black).with_attrs(DIM));
+        palette.define(STALE, Style::new(Yellow, Black));
+        palette.define(GAUGE, Style::new(Green, Black));
+        palette.define(OVERDUE, Style::new(Red, Black).with_attrs(BOLD));
+        palette.define(SOON, Style::new(Yellow, Black).with_attrs(BOLD));
+        palette.define(PRIORITIES[0], Style::new(Red, Black).with_attrs(BOLD));
+        palette.define(PRIORITIES[1], Style::new(Yellow, Black).with_attrs(BOLD));
+        palette.define(PRIORITIES[2], Style::new(Cyan, Black).with_attrs(BOLD));
+        palette
+    }
+}
+
+impl Palette {
+    pub fn define(&mut self, name: &str, style: Style) {
+        self.styles.insert(name.to_string(), style);
+    }
+
+    pub fn style(&self, name: &str) -> Style {
+        self.styles
+            .get(name)
+            .or_else(|| self.styles.get(REGULAR))
+            .copied()
+            .expect("The regular style is always defined")
+    }
+
+    pub fn color_tag(&mut self, tag: &str, color: Color) {
+        self.tags.insert(tag.to_string(), color);
+    }
+
+    // `base` in the color of `tag`. Tags nobody picked a color for get one from their name, the
+    // same one every time and on every machine, so a project looks the same wherever it shows up.
+    pub fn tag(&self, tag: &str, base: Style) -> Style {
+        let fg = self.tags.get(tag).copied().unwrap_or_else(|| {
+            // FNV-1a, std's hasher is free to change between releases.
+            let hash = tag.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+                (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+            });
+            TAG_COLORS[hash as usize % TAG_COLORS.len()]
+        });
+        Style { fg, ..base }
+    }
+}
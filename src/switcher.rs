@@ -0,0 +1,68 @@
+use std::cmp;
+use std::path::Path;
+
+use todo_core::boards;
+
+use crate::layout::LayoutKind;
+use crate::style::{REGULAR, SELECTION};
+use crate::ui::Ui;
+use crate::vec2::Vec2;
+
+// The list switcher, `b` or `:list`: the lists in the current directory and the named ones, for
+// picking which to work on.
+pub struct Switcher {
+    // boards::HERE first, then the named lists.
+    pub names: Vec<String>,
+    // Which of them is picked.
+    pub curr: usize,
+}
+
+impl Switcher {
+    // The lists there are in `dir`, with the cursor on `current`.
+    pub fn new(dir: Option<&Path>, current: &str) -> Self {
+        let mut names = vec![boards::HERE.to_string()];
+        names.extend(dir.map(boards::names).unwrap_or_default());
+        if !names.iter().any(|name| name == current) {
+            names.push(current.to_string());
+        }
+        let curr = names.iter().position(|name| name == current).unwrap_or(0);
+        Self { names, curr }
+    }
+
+    // Draws the box over whatever is on the screen of `size`, with a `*` on the `current` list.
+    // `here` is what the lists in the current directory are shown as. Every row is padded out to
+    // the full width so nothing shows through.
+    pub fn view(&self, ui: &mut Ui, current: &str, here: &str, size: Vec2) {
+        let width = (size.x - 4).clamp(8, 40);
+        let inner = (width - 2) as usize;
+        let page = cmp::max(size.y - 4, 1) as usize;
+        let scroll = (self.curr + 1).saturating_sub(page);
+        let (side, fill, top, bottom) = if ui.unicode {
+            ("│", "─", ("┌", "┐"), ("└", "┘"))
+        } else {
+            ("|", "-", ("+", "+"), ("+", "+"))
+        };
+        let rule = |(left, right): (&str, &str), label: &str| {
+            let rest = inner.saturating_sub(label.chars().count() + 1);
+            format!("{}{}{}{}{}", left, fill, label, fill.repeat(rest), right)
+        };
+
+        ui.begin(Vec2::new((size.x - width) / 2, 1), LayoutKind::Vert);
+        {
+            ui.label_fixed_width(&rule(top, " Lists "), width, REGULAR);
+            for (row, name) in self.names.iter().enumerate().skip(scroll).take(page) {
+                let mark = if name == current { "* " } else { "  " };
+                let shown = if name == boards::HERE { here } else { name };
+                let text = format!("{}{:<w$}", mark, shown, w = inner - 2);
+                let style = if row == self.curr { SELECTION } else { REGULAR };
+                ui.begin_layout(LayoutKind::Horz);
+                ui.label_fixed_width(side, 1, REGULAR);
+                ui.label_fixed_width(&text, inner as i32, style);
+                ui.label_fixed_width(side, 1, REGULAR);
+                ui.end_layout();
+            }
+            ui.label_fixed_width(&rule(bottom, " :list <name> adds one "), width, REGULAR);
+        }
+        ui.end();
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::process::{self, Command};
+
+use todo_core::merge::{self, State};
+use todo_core::storage::{self, Format, Lists};
+
+use crate::clock;
+use crate::log::log;
+
+// How many titles are in a different list, or none, after than before.
+fn changes(before: &Lists, after: &Lists) -> usize {
+    let lists = |lists: &Lists| {
+        let mut statuses = HashMap::new();
+        for (index, list) in [&lists.todos, &lists.dones, &lists.somedays]
+            .into_iter()
+            .enumerate()
+        {
+            for item in list {
+                statuses.entry(item.clone()).or_insert(index);
+            }
+        }
+        statuses
+    };
+    let (before, after) = (lists(before), lists(after));
+    before
+        .iter()
+        .filter(|(title, index)| after.get(*title) != Some(index))
+        .count()
+        + after
+            .keys()
+            .filter(|title| !before.contains_key(*title))
+            .count()
+}
+
+// Merges `theirs`, the lists from `other`, with the state at `other_state` into `file_path` and
+// saves the result, which it returns along with the number of changes taken in. The caller holds
+// the lock.
+fn take_in(
+    file_path: &str,
+    format: Format,
+    other: &str,
+    theirs: Lists,
+    other_state: &str,
+) -> io::Result<(Lists, State, usize)> {
+    let storage = format.storage();
+    let ours = match storage.load(file_path, &mut |_, _| {}) {
+        Err(error) if error.kind() == ErrorKind::NotFound => Lists::default(),
+        result => result?,
+    };
+    let state_path = merge::state_path(file_path);
+    let our_state = State::load(&state_path)?;
+    let their_state = State::load(other_state)?;
+    let (merged, state) = merge::merge(&ours, &our_state, &theirs, &their_state, clock::now());
+    let changed = changes(&ours, &merged);
+    storage.save(&merged, file_path)?;
+    state.save(&state_path)?;
+    log!(
+        Info,
+        "event=merged file={:?} other={:?} changed={}",
+        file_path,
+        other,
+        changed
+    );
+    Ok((merged, state, changed))
+}
+
+// Takes in the changes from `other`, a copy of the lists that went its own way, like a sync
+// conflict file. Its state is read from next to it if it has one, without it its items count as
+// changed just now.
+pub fn merge(file_path: &str, format: Format, other: &str) -> io::Result<usize> {
+    let _lock = storage::lock(file_path)?;
+    let theirs = format.storage().load(other, &mut |_, _| {})?;
+    take_in(file_path, format, other, theirs, &merge::state_path(other))
+        .map(|(_, _, changed)| changed)
+}
+
+// Copies `from` to `to` with scp, either of them on the other machine. Returns false when `from`
+// isn't there, which the first sync to a new place is like.
+fn scp(from: &str, to: &str) -> io::Result<bool> {
+    let output = Command::new("scp").arg("-q").arg(from).arg(to).output()?;
+    if output.status.success() {
+        return Ok(true);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("No such file") {
+        return Ok(false);
+    }
+    Err(io::Error::other(format!(
+        "{}: ERROR: could not copy it to {}: {}",
+        from,
+        to,
+        stderr.trim()
+    )))
+}
+
+// Shares the lists with another machine over SSH. `remote` is anything scp takes, like
+// `me@host:notes/TODO`. The copy over there and its state are fetched, merged into ours like
+// `cli-todo merge` does and the result is sent back, so both end up the same. Nothing is locked on
+// the other end, a cli-todo open over there saves over what this sends when it quits.
+pub fn run(file_path: &str, format: Format, remote: &str) -> io::Result<usize> {
+    let _lock = storage::lock(file_path)?;
+    let dir = env::temp_dir().join(format!("cli-todo-sync-{}", process::id()));
+    fs::create_dir_all(&dir)?;
+    let local = dir.join("TODO").to_string_lossy().into_owned();
+    let local_state = merge::state_path(&local);
+    let remote_state = merge::state_path(remote);
+    let result = (|| {
+        // Nothing over there yet is empty lists, whatever an empty file would be in the format.
+        let theirs = if scp(remote, &local)? {
+            format.storage().load(&local, &mut |_, _| {})?
+        } else {
+            Lists::default()
+        };
+        scp(&remote_state, &local_state)?;
+        let (merged, state, changed) = take_in(file_path, format, &local, theirs, &local_state)?;
+        format.storage().save(&merged, &local)?;
+        state.save(&local_state)?;
+        scp(&local, remote)?;
+        scp(&local_state, &remote_state)?;
+        log!(
+            Info,
+            "event=synced file={:?} remote={:?} changed={}",
+            file_path,
+            remote,
+            changed
+        );
+        Ok(changed)
+    })();
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+// Fetches the copy on the other machine as a conflict copy next to `file_path` instead of merging
+// it, so the next cli-todo to start goes through what the two disagree about with the user. Returns
+// where it went, or None when there's nothing over there yet.
+pub fn review(file_path: &str, remote: &str) -> io::Result<Option<String>> {
+    let now = clock::local();
+    let copy = format!(
+        "{}.sync-conflict-{:04}{:02}{:02}-{:02}{:02}-ssh",
+        file_path, now.year, now.month, now.day, now.hour, now.minute
+    );
+    if !scp(remote, &copy)? {
+        return Ok(None);
+    }
+    scp(&merge::state_path(remote), &merge::state_path(&copy))?;
+    log!(
+        Info,
+        "event=fetched file={:?} remote={:?} copy={:?}",
+        file_path,
+        remote,
+        copy
+    );
+    Ok(Some(copy))
+}
@@ -0,0 +1,1614 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::app::App;
+use crate::backend::test_backend::{self, keys, Screen, TestBackend};
+use crate::clock;
+use crate::config::Config;
+use crate::export;
+use crate::hooks;
+use crate::input::KeyEvent;
+use crate::jira::{self, Jira};
+use crate::layout::LayoutKind;
+use crate::report::{self, GroupBy};
+use crate::runtime::Message;
+use crate::style::{Color, Palette, Style};
+use crate::sync;
+use crate::ui::Ui;
+use crate::vec2::Vec2;
+use todo_core::activity::{self, Entry, Event};
+use todo_core::csv;
+use todo_core::date::DateTime;
+use todo_core::history::History;
+use todo_core::ical;
+use todo_core::item::Item;
+use todo_core::json;
+use todo_core::merge;
+use todo_core::org;
+use todo_core::status::Status;
+use todo_core::storage::{self, Format, Lists};
+use todo_core::todotxt;
+
+// Runs the app on the test backend until the script of keys runs out, drawing after every key the
+// same way main() does.
+fn run(todos: &[&str], dones: &[&str], size: Vec2, script: &str) -> (App, Screen) {
+    run_with(Config::default(), todos, dones, size, script)
+}
+
+fn run_with(
+    config: Config,
+    todos: &[&str],
+    dones: &[&str],
+    size: Vec2,
+    script: &str,
+) -> (App, Screen) {
+    let (backend, screen) = TestBackend::new(size, keys(script));
+    let mut ui = Ui::new(Box::new(backend));
+    if let Some(unicode) = config.unicode {
+        ui.unicode = unicode;
+    }
+    ui.select_with(config.selection);
+    let mut app = App::new(
+        todos.iter().map(|x| Item::from(*x)).collect(),
+        dones.iter().map(|x| Item::from(*x)).collect(),
+        config,
+        History::default(),
+    );
+    app.settle();
+    loop {
+        app.size = ui.backend.size();
+        ui.clear();
+        app.view(&mut ui);
+        ui.present();
+        match ui.backend.poll_key(crate::IDLE_TIMEOUT) {
+            Some(key) => app.handle_event(key),
+            None => break,
+        }
+    }
+    (app, screen)
+}
+
+// Saves the app's lists the way main() does on exit and reads the file back.
+fn saved(app: &App, name: &str) -> String {
+    let file_path = env::temp_dir().join(format!("cli-todo-{}-{}", process::id(), name));
+    let file_path = file_path.to_str().unwrap();
+    storage::save(&app.todos, &app.dones, &app.somedays, file_path).unwrap();
+    let source = fs::read_to_string(file_path).unwrap();
+    fs::remove_file(file_path).unwrap();
+    source
+}
+
+// Draws the app as it is on a fresh screen, for apps changed after run().
+fn draw(app: &App) -> String {
+    let (backend, screen) = TestBackend::new(SIZE, keys(""));
+    let mut ui = Ui::new(Box::new(backend));
+    app.view(&mut ui);
+    ui.present();
+    test_backend::text(&screen)
+}
+
+// Compares the screen against src/snapshots/<name>.snap. A missing snapshot is written out for
+// review, and running the tests with UPDATE_SNAPSHOTS=1 rewrites the ones that changed on purpose.
+fn assert_snapshot(name: &str, screen: &Screen) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/snapshots")
+        .join(format!("{}.snap", name));
+    let actual = test_backend::text(screen) + "\n";
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() || !path.exists() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap();
+    if actual != expected {
+        panic!(
+            "Snapshot {} does not match the screen, rerun with UPDATE_SNAPSHOTS=1 if the change is intended\n\
+             --- expected\n{}--- actual\n{}",
+            path.display(),
+            expected,
+            actual
+        );
+    }
+}
+
+const SIZE: Vec2 = Vec2 { x: 60, y: 10 };
+
+#[test]
+fn insert_item() {
+    let (app, screen) = run(&[], &[], SIZE, "ifoo\n");
+    assert_eq!(saved(&app, "insert_item"), "TODO: foo\n");
+    assert!(test_backend::text(&screen).contains("- [ ] foo"));
+}
+
+#[test]
+fn cancel_insert() {
+    let (app, _) = run(&["a"], &[], SIZE, "ifoo\x1b");
+    assert_eq!(saved(&app, "cancel_insert"), "TODO: a\n");
+}
+
+#[test]
+fn mark_done_and_back() {
+    let (app, screen) = run(&["a", "b"], &["c"], SIZE, "j\n");
+    assert_eq!(saved(&app, "mark_done"), "TODO: a\nDONE: c\nDONE: b\n");
+    assert!(test_backend::text(&screen).contains("DONE!"));
+
+    let (app, _) = run(&["a"], &["c"], SIZE, "\t\n");
+    assert_eq!(saved(&app, "mark_undone"), "TODO: a\nTODO: c\n");
+}
+
+#[test]
+fn cut_and_paste_across_panels() {
+    let (app, _) = run(&["a", "b"], &["c"], SIZE, "dd\tp");
+    assert_eq!(saved(&app, "cut_paste"), "TODO: b\nDONE: c\nDONE: a\n");
+}
+
+#[test]
+fn rename_item() {
+    let (app, _) = run(&["a"], &[], SIZE, "Abc\n");
+    assert_eq!(saved(&app, "rename_item"), "TODO: abc\n");
+}
+
+#[test]
+fn edit_after_non_ascii_first_character() {
+    let (app, _) = run(&["été"], &[], SIZE, "aüx\n");
+    assert_eq!(saved(&app, "edit_non_ascii"), "TODO: éüxté\n");
+}
+
+// The due date is edited along with the rest of the line and read out of it again after.
+#[test]
+fn edit_item_with_due_date() {
+    let (app, _) = run(&["call mom due:2026-10-20"], &[], SIZE, "A #family\n");
+    assert_eq!(app.todos[0].title, "call mom #family");
+    assert_eq!(app.todos[0].due, DateTime::parse("2026-10-20"));
+    assert_eq!(
+        saved(&app, "edit_due"),
+        "TODO: call mom #family due:2026-10-20\n"
+    );
+}
+
+#[cfg(feature = "ncurses")]
+#[test]
+fn ncurses_reads_non_ascii_keys() {
+    use crate::backend::ncurses_backend::decode_key;
+    use ncurses::WchResult;
+
+    for c in ['é', 'ï', '漢', '🦀'] {
+        assert_eq!(decode_key(WchResult::Char(c as u32)), KeyEvent::Char(c));
+    }
+    assert_eq!(decode_key(WchResult::Char(10)), KeyEvent::Enter);
+    assert_eq!(decode_key(WchResult::Char(1)), KeyEvent::Ctrl('a'));
+    assert_eq!(decode_key(WchResult::Char(0x9b)), KeyEvent::Unknown);
+}
+
+#[cfg(all(feature = "crossterm", not(feature = "ncurses")))]
+#[test]
+fn crossterm_reads_non_ascii_keys() {
+    use crate::backend::crossterm_backend::decode_key;
+    use crossterm::event::{KeyCode, KeyEvent as Key, KeyModifiers};
+
+    for c in ['é', 'ï', '漢', '🦀'] {
+        let key = Key::new(KeyCode::Char(c), KeyModifiers::NONE);
+        assert_eq!(decode_key(key), KeyEvent::Char(c));
+        let key = Key::new(KeyCode::Char(c), KeyModifiers::ALT);
+        assert_eq!(decode_key(key), KeyEvent::Alt(c));
+    }
+}
+
+#[test]
+fn bars_line_up_with_non_ascii_labels() {
+    let (backend, screen) = TestBackend::new(Vec2::new(20, 3), keys(""));
+    let mut ui = Ui::new(Box::new(backend));
+    ui.unicode = false;
+    ui.begin(Vec2::new(0, 0), LayoutKind::Vert);
+    ui.gauge("café 1/2", 0.5, 20, "regular");
+    ui.bar_chart(&[("é", 2), ("ab", 1)], 20, "regular");
+    ui.end();
+    ui.present();
+    let text = test_backend::text(&screen);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "[#####----] café 1/2");
+    assert_eq!(lines[1], "é  ############### 2");
+    assert_eq!(lines[2], "ab ########        1");
+}
+
+#[test]
+fn ctrl_z_undoes_typing() {
+    let (mut app, _) = run(&["a"], &[], SIZE, "Abc");
+    app.handle_event(KeyEvent::Ctrl('z'));
+    assert!(!app.suspend);
+    app.handle_event(KeyEvent::Enter);
+    assert_eq!(saved(&app, "ctrl_z_undoes_typing"), "TODO: a\n");
+    app.handle_event(KeyEvent::Ctrl('z'));
+    assert!(app.suspend);
+}
+
+#[test]
+fn command_needs_confirmation() {
+    let (app, _) = run(&["a", "b"], &[], SIZE, ":done\nn");
+    assert_eq!(app.notification, "Cancelled");
+    assert_eq!(app.dones.len(), 0);
+
+    let (app, _) = run(&["a", "b"], &[], SIZE, ":done\ny");
+    assert_eq!(saved(&app, "command"), "DONE: a\nDONE: b\n");
+}
+
+#[test]
+fn quit() {
+    let (app, _) = run(&["a"], &[], SIZE, "q");
+    assert!(app.quit);
+}
+
+#[test]
+fn pomodoro_cycle() {
+    let (mut app, _) = run(&["a"], &[], SIZE, "t");
+    let start = Instant::now();
+    assert!(app.tick(start + Duration::from_secs(25 * 60)));
+    assert_eq!(app.notification, "Pomodoro #1 on a done, take a break");
+    assert_eq!(app.pomodoros.count("a"), 1);
+    app.tick(start + Duration::from_secs(30 * 60));
+    assert_eq!(app.notification, "Break is over, back to a");
+
+    app.handle_event(KeyEvent::Char('t'));
+    assert!(!app.tick(start + Duration::from_secs(60 * 60)));
+    assert_eq!(app.pomodoros.count("a"), 1);
+}
+
+#[test]
+fn defer_and_bring_back() {
+    let (app, screen) = run(&["a", "b"], &[], SIZE, "z2999-01-04T09:30\n");
+    assert_eq!(
+        saved(&app, "defer"),
+        "TODO: b\nTODO: a defer:2999-01-04T09:30\n"
+    );
+    assert!(test_backend::text(&screen).contains("Deferred until 2999-01-04 09:30"));
+
+    let (app, _) = run(&["a defer:2999-01-04"], &[], SIZE, "z\n");
+    assert_eq!(saved(&app, "undefer"), "TODO: a\n");
+
+    let (mut app, _) = run(&["a defer:2000-01-01", "b"], &[], SIZE, "");
+    app.now = app.now.add_days(-1);
+    assert!(app.tick(Instant::now()));
+    assert_eq!(app.notification, "a is back");
+}
+
+#[test]
+fn today_view() {
+    let (app, screen) = run(&["a due:2000-01-01", "b", "c"], &[], SIZE, "j*Tjix\n");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("TODO (3/4 today)"));
+    assert!(!text.contains("- [ ] c"));
+    let today = app.now.to_item();
+    let today = &today[..10];
+    assert_eq!(
+        saved(&app, "today"),
+        format!(
+            "TODO: a due:2000-01-01\nTODO: x sched:{0}\nTODO: b sched:{0}\nTODO: c\n",
+            today
+        )
+    );
+}
+
+#[test]
+fn context_filter() {
+    let (app, screen) = run(
+        &["call @phone", "shop @errands", "mail @phone"],
+        &[],
+        SIZE,
+        "cc",
+    );
+    let text = test_backend::text(&screen);
+    assert!(text.contains("0/3 done  @phone"));
+    assert!(text.contains("TODO (2/3 @phone)"));
+    assert!(!text.contains("shop"));
+    // Landed on the next item in the context.
+    assert_eq!(app.todo_curr, 2);
+
+    let (app, _) = run(&["call @phone", "shop @errands"], &[], SIZE, "ccGifax\nc");
+    assert!(app.filter.is_none());
+    assert_eq!(
+        saved(&app, "context"),
+        "TODO: fax @phone\nTODO: call @phone\nTODO: shop @errands\n"
+    );
+}
+
+#[test]
+fn saved_and_typed_filters() {
+    let config = Config::parse(
+        "[filters]\nwork = tag:#work AND status:todo\nsoon = due<=+7d OR #urgent\n",
+        "config",
+    )
+    .unwrap();
+    let todos = ["report #work", "groceries", "deploy #work #urgent"];
+    let (app, screen) = run_with(config, &todos, &["old #work"], SIZE, "v");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("1/4 done  work"));
+    assert!(text.contains("TODO (2/3 work)"));
+    assert!(!text.contains("groceries"));
+    assert!(!text.contains("old #work"));
+    assert_eq!(app.notification, "Only work, v for the next filter");
+
+    let (app, screen) = run(
+        &["a due:2000-01-01", "b due:2999-01-01", "c"],
+        &[],
+        SIZE,
+        ":filter save late due<today\n",
+    );
+    assert!(!test_backend::text(&screen).contains("b due:"));
+    assert_eq!(app.notification, "Saved filter late until you quit");
+
+    let (app, _) = run(&["a"], &[], SIZE, ":filter due<=whenever\n");
+    assert_eq!(app.notification, "Can't tell when `whenever` is");
+    assert!(app.filter.is_none());
+}
+
+#[test]
+fn sorted_panels() {
+    let config = || Config::parse("[sort]\ntodo = due, title desc\n", "config").unwrap();
+    let todos = ["c", "b due:2026-10-20", "a due:2026-10-18", "d"];
+    let (app, _) = run_with(config(), &todos, &[], SIZE, "ie due:2026-10-19\nK");
+    assert_eq!(
+        app.notification,
+        "Sorted by due, title desc, o switches to putting items in order by hand"
+    );
+    assert_eq!(app.todos[app.todo_curr], "e due:2026-10-19");
+    assert_eq!(
+        saved(&app, "sorted"),
+        "TODO: a due:2026-10-18\nTODO: e due:2026-10-19\nTODO: b due:2026-10-20\nTODO: d\nTODO: c\n"
+    );
+
+    let (app, _) = run_with(config(), &todos, &[], SIZE, "oGK");
+    assert_eq!(app.todos[2], "c");
+}
+
+#[test]
+fn checklist_steps() {
+    let item = "release; [x] bump; [ ] tag it; [ ] announce";
+    let (_, screen) = run(&[item], &[], SIZE, "");
+    assert!(test_backend::text(&screen).contains("- [ ] release 1/3"));
+
+    let (app, screen) = run(&[item], &[], SIZE, "xj jj ");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("- [x] tag it"));
+    assert!(text.contains("release 3/3"));
+    assert_eq!(
+        app.notification,
+        "All 3 steps done, Enter on the item finishes it"
+    );
+    assert_eq!(
+        saved(&app, "checklist"),
+        "TODO: release; [x] bump; [x] tag it; [x] announce\n"
+    );
+}
+
+#[test]
+fn attachments() {
+    let (app, screen) = run(
+        &["read the spec; [ ] comment"],
+        &[],
+        SIZE,
+        ":attach ~/specs/login flow.pdf\nx",
+    );
+    assert_eq!(
+        saved(&app, "attach"),
+        "TODO: read the spec attach:~/specs/login%20flow.pdf; [ ] comment\n"
+    );
+    assert!(test_backend::text(&screen).contains("- attach: ~/specs/login flow.pdf"));
+
+    let (app, _) = run(&["nothing"], &[], SIZE, "x");
+    assert!(app.notification.starts_with("Nothing to show"));
+}
+
+#[test]
+fn search_with_regexes() {
+    let todos = ["call bob", "report 12", "water plants", "report draft"];
+    let (app, _) = run(&todos, &[], SIZE, "/report\n");
+    assert_eq!(app.todo_curr, 1);
+    let (app, _) = run(&todos, &[], SIZE, "/\\vreport \\d+$\n");
+    assert_eq!(app.todo_curr, 1);
+    let (app, _) = run(&todos, &[], SIZE, "/\\v^(water|report) [a-z]+$\n");
+    assert_eq!(app.todo_curr, 2);
+    let (app, _) = run(&todos, &[], SIZE, "/\\vre(port\n");
+    assert_eq!(app.todo_curr, 0);
+    assert_eq!(app.notification, "Missing `)` in the pattern `re(port`");
+    let (app, _) = run(&todos, &[], SIZE, "/nope\n");
+    assert_eq!(app.notification, "Pattern not found: nope");
+}
+
+#[test]
+fn search_next_and_previous() {
+    let todos = ["call bob", "report 12", "water plants", "report draft"];
+    let dones = ["old report", "taxes"];
+    let (app, _) = run(&todos, &dones, SIZE, "/report\nn");
+    assert_eq!((app.panel, app.todo_curr), (Status::Todo, 3));
+    let (app, _) = run(&todos, &dones, SIZE, "/report\nnn");
+    assert_eq!((app.panel, app.done_curr), (Status::Done, 0));
+    let (app, _) = run(&todos, &dones, SIZE, "/report\nnnn");
+    assert_eq!((app.panel, app.todo_curr), (Status::Todo, 1));
+    assert_eq!(app.notification, "Search went round to the top");
+    let (app, _) = run(&todos, &dones, SIZE, "/report\nN");
+    assert_eq!((app.panel, app.done_curr), (Status::Done, 0));
+    assert_eq!(app.notification, "Search went round to the bottom");
+    let (app, _) = run(&todos, &dones, SIZE, "n");
+    assert_eq!(app.notification, "No search to repeat, / searches first");
+}
+
+#[test]
+fn fuzzy_finder() {
+    let todos = ["return the drill", "water plants", "release notes"];
+    let (mut app, _) = run(&todos, &["renew passport"], SIZE, "");
+    app.handle_event(KeyEvent::Ctrl('p'));
+    for key in keys("rn") {
+        app.handle_event(key);
+    }
+    let (backend, screen) = TestBackend::new(SIZE, keys(""));
+    let mut ui = Ui::new(Box::new(backend));
+    app.view(&mut ui);
+    ui.present();
+    assert_snapshot("fuzzy_finder", &screen);
+
+    app.handle_event(KeyEvent::Down);
+    app.handle_event(KeyEvent::Enter);
+    assert_eq!((app.panel, app.done_curr), (Status::Done, 0));
+    app.handle_event(KeyEvent::Ctrl('p'));
+    for key in keys("xyz\n") {
+        app.handle_event(key);
+    }
+    assert_eq!(app.panel, Status::Done);
+    assert!(!draw(&app).contains("Find"));
+}
+
+#[test]
+fn named_lists() {
+    let dir = env::temp_dir().join(format!("cli-todo-{}-lists", process::id()));
+    let work = todo_core::boards::path(&dir, "work", Format::Lines);
+    fs::create_dir_all(work.parent().unwrap()).unwrap();
+    let (mut app, _) = run(&["deploy"], &[], SIZE, "");
+    app.file_path = work.to_string_lossy().into_owned();
+    app.list = "work".to_string();
+    app.lists_dir = Some(dir.clone());
+    let feed = |app: &mut App, script: &str| {
+        for key in keys(script) {
+            app.handle_event(key);
+        }
+    };
+
+    feed(&mut app, ":list home\n");
+    assert_eq!(app.notification, "Switched to list home");
+    assert!(app.todos.is_empty());
+    assert_eq!(fs::read_to_string(&work).unwrap(), "TODO: deploy\n");
+    feed(&mut app, "iwater plants\n");
+    feed(&mut app, "u");
+    assert!(app.todos.is_empty());
+    feed(&mut app, "u");
+    assert_eq!(app.notification, "Nothing to undo");
+    feed(&mut app, "iwater plants\n");
+
+    // The switcher has the lists in the current directory first, then the named ones.
+    feed(&mut app, "b");
+    assert_snapshot("named_lists", &{
+        let (backend, screen) = TestBackend::new(SIZE, keys(""));
+        let mut ui = Ui::new(Box::new(backend));
+        app.view(&mut ui);
+        ui.present();
+        screen
+    });
+    feed(&mut app, "j\n");
+    assert_eq!(app.list, "work");
+    assert_eq!(app.todos, ["deploy"]);
+    let home = todo_core::boards::path(&dir, "home", Format::Lines);
+    assert_eq!(fs::read_to_string(home).unwrap(), "TODO: water plants\n");
+
+    feed(&mut app, ":list no/pe\n");
+    assert_eq!(
+        app.notification,
+        "`no/pe` can't be a list name, use letters, digits, - and _"
+    );
+    drop(app);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn jump_by_first_character() {
+    let todos = ["apples", "bread", "butter", "Beans", "cheese"];
+    let (app, _) = run(&todos, &[], SIZE, "fb");
+    assert_eq!(app.todo_curr, 1);
+    let (app, _) = run(&todos, &[], SIZE, "fb;;");
+    assert_eq!(app.todo_curr, 3);
+    let (app, _) = run(&todos, &[], SIZE, "fb;;;");
+    assert_eq!(app.todo_curr, 1);
+    let (app, _) = run(&todos, &[], SIZE, "GFb;");
+    assert_eq!(app.todo_curr, 2);
+    let (app, _) = run(&todos, &[], SIZE, "fb;,");
+    assert_eq!(app.todo_curr, 1);
+    let (app, _) = run(&todos, &[], SIZE, "fz");
+    assert_eq!(app.notification, "No item starting with z");
+    let (app, _) = run(&todos, &[], SIZE, ";");
+    assert_eq!(app.notification, "No jump to repeat, f<char> jumps first");
+}
+
+#[test]
+fn search_smart_case() {
+    let todos = ["call Bob", "email bob", "Bob's party"];
+    let (app, _) = run(&todos, &[], SIZE, "/bob\n");
+    assert_eq!(app.todo_curr, 1);
+    let (app, _) = run(&todos, &[], SIZE, "/Bob\n");
+    assert_eq!(app.todo_curr, 2);
+    let (app, _) = run(&todos, &[], SIZE, "/\\v^BOB\n");
+    assert_eq!(app.notification, "Pattern not found: \\v^BOB");
+    let (app, _) = run(&todos, &[], SIZE, "/\\v\\Wbob\n");
+    assert_eq!(app.todo_curr, 1);
+
+    let sensitive = || Config::parse("[search]\ncase = sensitive\n", "config").unwrap();
+    let (app, _) = run_with(sensitive(), &todos, &[], SIZE, "/\\v^b\n");
+    assert_eq!(app.notification, "Pattern not found: \\v^b");
+    let ignore = Config::parse("[search]\ncase = ignore\n", "config").unwrap();
+    let (app, _) = run_with(ignore, &todos, &[], SIZE, "/\\v^BOB\n");
+    assert_eq!(app.todo_curr, 2);
+}
+
+#[test]
+fn cycle_through_tags() {
+    let todos = ["a #work", "b #home", "c"];
+    let (app, screen) = run(&todos, &["d #work"], SIZE, "]t");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("TODO (1/3 #home)"));
+    assert!(text.contains("b #home") && !text.contains("a #work"));
+    assert_eq!(
+        app.notification,
+        "Only #home, ]t for the next tag and [t the one before"
+    );
+
+    let (_, screen) = run(&todos, &["d #work"], SIZE, "]t]t");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("a #work") && text.contains("d #work") && !text.contains("b #home"));
+
+    let (app, _) = run(&todos, &[], SIZE, "]t]t]t");
+    assert_eq!(app.notification, "All tags");
+    let (app, _) = run(&todos, &[], SIZE, "[t");
+    assert!(app.notification.starts_with("Only #work"));
+
+    let (app, _) = run(&todos, &[], SIZE, "]tie\n");
+    assert_eq!(app.todos[1], "e #home");
+}
+
+#[test]
+fn cycle_through_projects() {
+    let todos = ["dig +garden @home", "call +1 @phone", "prune +garden", "c"];
+    let (app, screen) = run(&todos, &["weed +garden"], SIZE, "]p");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("prune +garden") && text.contains("weed +garden"));
+    assert!(!text.contains("call +1"));
+    assert!(text.contains("TODO (2/4 +garden)"));
+    assert_eq!(
+        app.notification,
+        "Only +garden, ]p for the next project and [p the one before"
+    );
+
+    let (app, _) = run(&todos, &[], SIZE, "]p]p");
+    assert_eq!(app.notification, "All projects");
+    let (app, _) = run(&["a"], &[], SIZE, "]p");
+    assert_eq!(app.notification, "No +projects anywhere");
+    let (app, _) = run(&todos, &[], SIZE, "]pie\n");
+    assert_eq!(app.todos[0], "e +garden");
+}
+
+#[test]
+fn todotxt_format() {
+    let config = Config::parse("[storage]\nformat = todotxt\n", "config").unwrap();
+    assert_eq!(config.format, Format::TodoTxt);
+    // With sqlite at the end when it's built in.
+    let error = Config::parse("[storage]\nformat = xml\n", "config")
+        .err()
+        .unwrap();
+    assert!(error.starts_with(
+        "config:2: ERROR: unknown format `xml`, expected one of lines, todotxt, json"
+    ));
+
+    let line = "(A) 2026-10-01 call mom +family @phone due:2026-10-20";
+    let (status, item) = todotxt::parse_line(line);
+    assert_eq!(status, Status::Todo);
+    assert_eq!(
+        item,
+        "call mom +family @phone due:2026-10-20 pri:A created:2026-10-01"
+    );
+    assert_eq!(todotxt::format_line(status, &item), line);
+    let line = "x 2026-10-14 2026-10-01 file taxes pri:B";
+    let (status, item) = todotxt::parse_line(line);
+    assert_eq!(status, Status::Done);
+    assert_eq!(
+        item,
+        "file taxes pri:B created:2026-10-01 completed:2026-10-14"
+    );
+    assert_eq!(todotxt::format_line(status, &item), line);
+    assert_eq!(
+        todotxt::parse_line("learn the cello @someday"),
+        (Status::Someday, "learn the cello".to_string())
+    );
+}
+
+#[test]
+fn json_format() {
+    let dir = env::temp_dir().join(format!("cli-todo-{}-json", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file_path = dir.join("todo.json");
+    let file_path = file_path.to_str().unwrap();
+    let storage = Format::Json.storage();
+    fs::write(dir.join("TODO"), "TODO: water plants\nDONE: call bob\n").unwrap();
+    let mut lists = storage.load(file_path, &mut |_, _| {}).unwrap();
+    assert_eq!(lists.todos, ["water plants"]);
+    assert_eq!(lists.dones, ["call bob"]);
+    storage.save(&lists, file_path).unwrap();
+    let saved = fs::read_to_string(file_path).unwrap();
+    assert!(saved.starts_with("{\"version\":1,\"items\":[\n{\"id\":1,\"title\":\"water plants\""));
+
+    // The ids stay with the items, the new one gets the next.
+    lists.todos.insert(0, "feed cat".into());
+    storage.save(&lists, file_path).unwrap();
+    let value = json::Value::parse(&fs::read_to_string(file_path).unwrap()).unwrap();
+    let Some(json::Value::Array(items)) = value.get("items") else {
+        panic!("no items in {}", value);
+    };
+    let ids: Vec<_> = items
+        .iter()
+        .map(|item| item.get("id").unwrap().to_string())
+        .collect();
+    assert_eq!(ids, ["3", "1", "2"]);
+
+    fs::write(file_path, "{\"version\":2,\"items\":[]}").unwrap();
+    let error = storage.load(file_path, &mut |_, _| {}).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "{}: ERROR: version 2 is from a later cli-todo, this one reads up to 1",
+            file_path
+        )
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn import_markdown() {
+    let dir = env::temp_dir();
+    let file_path = dir.join(format!("cli-todo-{}-import", process::id()));
+    let file_path = file_path.to_str().unwrap();
+    let notes = dir.join(format!("cli-todo-{}-notes.md", process::id()));
+    let notes = notes.to_str().unwrap();
+    fs::write(file_path, "TODO: water plants\n").unwrap();
+    fs::write(
+        notes,
+        "# Week\n\nSome text.\n\n- [ ] water plants\n- [X] call bob\n  * [ ] nested\n\n\
+         ## SOMEDAY\n\n- [ ] learn the cello\n- plain bullet\n",
+    )
+    .unwrap();
+    assert_eq!(
+        export::import(file_path, Format::Lines, notes).unwrap(),
+        (3, 1)
+    );
+    assert_eq!(
+        fs::read_to_string(file_path).unwrap(),
+        "TODO: water plants\nTODO: nested\nDONE: call bob\nSOMEDAY: learn the cello\n"
+    );
+    assert_eq!(
+        export::import(file_path, Format::Lines, notes).unwrap(),
+        (0, 4)
+    );
+    let error = export::import(file_path, Format::Lines, file_path).unwrap_err();
+    assert!(error.to_string().contains("expected a .md or .org file"));
+    for path in [
+        file_path.to_string(),
+        notes.to_string(),
+        merge::state_path(file_path),
+        storage::lock_path(file_path),
+    ] {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[test]
+fn org_mode() {
+    let lists = Lists {
+        todos: vec!["call mom due:2026-10-20".into()],
+        dones: vec!["file taxes".into()],
+        somedays: vec!["learn the cello".into()],
+    };
+    let out = org::export(&lists);
+    assert_eq!(
+        out,
+        "#+TODO: TODO SOMEDAY | DONE\n\n* TODO call mom due:2026-10-20\n  \
+         DEADLINE: <2026-10-20 Tue>\n* DONE file taxes\n* SOMEDAY learn the cello\n"
+    );
+    assert_eq!(org::import(&out), (lists, Vec::new()));
+
+    let source = "#+TODO: TODO WAIT(w) | DONE CANCELLED\n\
+                  * Work\n\
+                  ** WAIT reply to bob :work:mail:\n\
+                  \x20  DEADLINE: <2026-10-21 Wed 14:30>\n\
+                  * CANCELLED move house\n\
+                  \x20  We stayed after all.\n\
+                  * Notes\n";
+    let (lists, warnings) = org::import(source);
+    assert_eq!(
+        lists.todos,
+        ["reply to bob #work #mail due:2026-10-21T14:30"]
+    );
+    assert_eq!(lists.dones, ["move house"]);
+    assert_eq!(
+        warnings,
+        [
+            (
+                3,
+                "`reply to bob #work #mail` is under another heading, imported as an item of its \
+                 own"
+                .to_string()
+            ),
+            (6, "the text under `move house` is left out".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn csv_export() {
+    let lists = Lists {
+        todos: vec![
+            "call mom, then dad #family due:2026-10-20".into(),
+            "say \"hi\" created:2026-10-01".into(),
+        ],
+        dones: vec!["file taxes #money #home".into()],
+        somedays: Vec::new(),
+    };
+    let time = DateTime::parse("2026-10-14T09:30").unwrap().to_unix(0);
+    let activity = activity::Log::new(vec![Entry {
+        time,
+        event: Event::Done,
+        title: "file taxes #money #home".to_string(),
+    }]);
+    assert_eq!(
+        csv::export(&lists, &activity, 0),
+        "id,title,status,created,completed,due,tags\n\
+         1,\"call mom, then dad #family due:2026-10-20\",todo,,,2026-10-20,family\n\
+         2,\"say \"\"hi\"\" created:2026-10-01\",todo,2026-10-01,,,\n\
+         3,file taxes #money #home,done,,2026-10-14T09:30,,money home\n"
+    );
+}
+
+#[test]
+fn ical_export() {
+    let lists = Lists {
+        todos: vec![
+            "call mom, then dad #family pri:A due:2026-10-20".into(),
+            "no due date".into(),
+        ],
+        dones: vec!["file taxes due:2026-10-14T09:30".into()],
+        somedays: Vec::new(),
+    };
+    let now = DateTime::parse("2026-10-15T08:00").unwrap().to_unix(0);
+    let out = ical::export(&lists, now);
+    assert!(out.ends_with("END:VCALENDAR\r\n"));
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(
+        lines[3..13],
+        [
+            "BEGIN:VTODO",
+            "UID:a7808e5755730841@cli-todo",
+            "DTSTAMP:20261015T080000Z",
+            "SUMMARY:call mom\\, then dad #family",
+            "DUE;VALUE=DATE:20261020",
+            "STATUS:NEEDS-ACTION",
+            "PRIORITY:1",
+            "CATEGORIES:family",
+            "END:VTODO",
+            "BEGIN:VTODO",
+        ]
+    );
+    assert!(lines.contains(&"DUE:20261014T093000"));
+    assert!(lines.contains(&"STATUS:COMPLETED"));
+    assert!(!out.contains("no due date"));
+}
+
+#[test]
+fn bell_on_done() {
+    let (app, _) = run(&["a"], &[], SIZE, "\n");
+    assert!(!app.bell);
+
+    let config = Config::parse("[sounds]\ndone = bell\noverdue = off\n", "config").unwrap();
+    let (app, _) = run_with(config, &["a"], &[], SIZE, "\n");
+    assert!(app.bell);
+
+    assert_eq!(
+        Config::parse("[sounds]\nsave = bell\n", "config").err(),
+        Some(
+            "config:2: ERROR: unknown event `save`, expected one of done, pomodoro, overdue"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn tag_colors() {
+    let base = Style::new(Color::White, Color::Black);
+    let palette = Palette::default();
+    let work = palette.tag("work", base);
+    assert_eq!(work, palette.tag("work", base));
+    assert_eq!(work.bg, Color::Black);
+    assert!(![Color::Black, Color::White].contains(&work.fg));
+
+    let config = Config::parse("[theme]\ntag:work = blue\n", "config").unwrap();
+    let mut palette = Palette::default();
+    for (tag, color) in &config.tag_colors {
+        palette.color_tag(tag, *color);
+    }
+    assert_eq!(palette.tag("work", base).fg, Color::Blue);
+    assert_eq!(
+        Config::parse("[theme]\ntag:work = pink\n", "config").err(),
+        Some(
+            "config:2: ERROR: unknown color `pink`, expected one of black, red, green, yellow, \
+             blue, magenta, cyan, white"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn custom_panel_titles() {
+    let config = || {
+        let source = "[header]\ntodo = Backlog\ndone = Shipped\nalign = right\nnotification = no\n";
+        Config::parse(source, "config").unwrap()
+    };
+    let (_, screen) = run_with(config(), &["a"], &["b"], SIZE, "");
+    let text = test_backend::text(&screen);
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].contains("1/2 done"));
+    assert_eq!(
+        lines[1].trim_end(),
+        format!("{:>30}{:>30}", "Backlog (1)", "Shipped (1)")
+    );
+    assert_eq!(lines[2].trim_end(), "- [ ] a                       - [x] b");
+
+    // Notifications take the place of the status bar while they're up.
+    let (app, screen) = run_with(config(), &["a"], &["b"], SIZE, "md");
+    assert_eq!(app.notification, "Moved to Shipped");
+    assert!(test_backend::text(&screen).starts_with("Moved to Shipped"));
+
+    assert_eq!(
+        Config::parse("[header]\nalign = middle\n", "config").err(),
+        Some("config:2: ERROR: expected left, center or right, got `middle`".to_string())
+    );
+}
+
+#[test]
+fn ascii_glyphs() {
+    let todos = (1..=30).map(|x| format!("item {}", x)).collect::<Vec<_>>();
+    let todos = todos.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    let config = Config::parse("[theme]\nglyphs = ascii\n", "config").unwrap();
+    let (_, screen) = run_with(config, &todos, &["done"], SIZE, "");
+    let text = test_backend::text(&screen);
+    assert!(text.is_ascii(), "{}", text);
+    assert!(text.contains("- [ ] item 1                 #- [x] done"));
+    assert_eq!(
+        Config::parse("[theme]\nglyphs = fancy\n", "config").err(),
+        Some("config:2: ERROR: expected auto, unicode or ascii, got `fancy`".to_string())
+    );
+}
+
+#[test]
+fn selection_marker() {
+    let config = |selection| {
+        let source = format!("[theme]\nselection = {}\n", selection);
+        Config::parse(&source, "config").unwrap()
+    };
+    let (_, screen) = run_with(config("marker"), &["a", "b"], &[], SIZE, "j");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("- [ ] a\n▶ [ ] b"), "{}", text);
+    let (_, screen) = run_with(config("reverse"), &["a", "b"], &[], SIZE, "j");
+    assert!(test_backend::text(&screen).contains("- [ ] a\n- [ ] b"));
+    assert_eq!(
+        Config::parse("[theme]\nselection = blink\n", "config").err(),
+        Some("config:2: ERROR: expected reverse, marker or both, got `blink`".to_string())
+    );
+}
+
+#[test]
+fn undo_and_redo() {
+    let (mut app, _) = run(&["a", "b", "c"], &[], SIZE, "jddu");
+    assert_eq!(app.todos, ["a", "b", "c"]);
+    assert_eq!(app.todo_curr, 1);
+    assert_eq!(app.notification, "Undid deleting b, Ctrl+R does it again");
+    app.handle_event(KeyEvent::Ctrl('r'));
+    assert_eq!(app.todos, ["a", "c"]);
+    assert_eq!(app.notification, "Redid deleting b");
+
+    // Adding an item and typing it in is one change, and so is finishing one.
+    let (app, _) = run(&["a"], &[], SIZE, "inew\nu");
+    assert_eq!(app.todos, ["a"]);
+    let (mut app, _) = run(&["a", "b"], &[], SIZE, "j\nu");
+    assert_eq!(app.todos, ["a", "b"]);
+    assert!(app.dones.is_empty());
+    app.handle_event(KeyEvent::Ctrl('r'));
+    assert_eq!(app.dones, ["b"]);
+    assert_eq!(app.notification, "Redid moving b to DONE");
+
+    let (app, _) = run(&["a"], &[], SIZE, "u");
+    assert_eq!(app.notification, "Nothing to undo");
+}
+
+#[test]
+fn undo_after_things_change_by_themselves() {
+    // Deferred items coming back aren't a change to take back, but the defer before still is.
+    let (mut app, _) = run(&["a", "b"], &[], SIZE, "");
+    app.now = app.now.add_days(-3);
+    for key in keys("ztomorrow\n") {
+        app.handle_event(key);
+    }
+    assert_eq!(app.todos.len(), 2);
+    assert!(app.todos[1].starts_with("a defer:"));
+    assert!(app.tick(Instant::now()));
+    assert_eq!(app.todos, ["b", "a"]);
+    app.handle_event(KeyEvent::Char('u'));
+    assert_eq!(app.todos, ["a", "b"]);
+    assert_eq!(
+        app.notification,
+        "Undid the last change, Ctrl+R does it again"
+    );
+    app.handle_event(KeyEvent::Ctrl('r'));
+    assert_eq!(app.todos, ["b", "a"]);
+
+    // Items added from outside can be taken back like any others, and what was before them too.
+    let (mut app, _) = run(&["a", "b"], &[], SIZE, "dd");
+    let (reply, answer) = mpsc::channel();
+    app.handle_message(Message::Request {
+        method: "add".to_string(),
+        params: json::Value::object([("title", "c".into())]),
+        reply,
+    });
+    assert_eq!(answer.recv(), Ok(Ok(json::Value::Null)));
+    assert_eq!(app.todos, ["b", "c"]);
+    app.handle_event(KeyEvent::Char('u'));
+    assert_eq!(app.todos, ["b"]);
+    assert_eq!(app.notification, "Undid adding c, Ctrl+R does it again");
+    app.handle_event(KeyEvent::Char('u'));
+    assert_eq!(app.todos, ["a", "b"]);
+}
+
+#[test]
+fn set_and_clear_due_dates() {
+    let (mut app, _) = run(&["pay rent"], &[], SIZE, "!2026-11-01T09:30\n");
+    assert_eq!(app.todos, ["pay rent due:2026-11-01T09:30"]);
+    assert_eq!(app.notification, "Due 2026-11-01 09:30");
+
+    // It starts out with the date there is, to change or take out.
+    app.handle_event(KeyEvent::Char('!'));
+    for _ in 0.."2026-11-01T09:30".len() {
+        app.handle_event(KeyEvent::Backspace);
+    }
+    app.handle_event(KeyEvent::Enter);
+    assert_eq!(app.todos, ["pay rent"]);
+    assert_eq!(app.notification, "pay rent isn't due any more");
+
+    let (app, _) = run(&["pay rent"], &[], SIZE, "!someday\n");
+    assert_eq!(app.todos, ["pay rent"]);
+    assert!(app.notification.starts_with("Can't tell when `someday` is"));
+}
+
+#[test]
+fn priorities() {
+    let (app, _) = run(&["a", "b pri:B"], &[], SIZE, "++j+");
+    assert_eq!(app.todos, ["a pri:B", "b pri:A"]);
+    assert_eq!(app.notification, "Priority A");
+    let (app, _) = run(&["a pri:A"], &[], SIZE, "+---");
+    assert_eq!(app.todos, ["a"]);
+    assert_eq!(app.notification, "No priority");
+
+    let todos = ["none", "c pri:C", "a pri:A", "b pri:b"];
+    let (mut app, _) = run(&todos, &[], SIZE, ":sort priority\n");
+    assert_eq!(app.todos, ["a pri:A", "b pri:B", "c pri:C", "none"]);
+    assert_eq!(app.notification, "Sorted TODO by priority, u puts it back");
+    app.handle_event(KeyEvent::Char('u'));
+    assert_eq!(app.todos, ["none", "c pri:C", "a pri:A", "b pri:B"]);
+    let (app, _) = run(&todos, &[], SIZE, ":sort urgency\n");
+    assert!(app.notification.starts_with("unknown sort key `urgency`"));
+}
+
+#[test]
+fn park_for_someday_and_bring_back() {
+    let (app, screen) = run(&["a", "b"], &["c"], SIZE, ">\t\t");
+    assert!(test_backend::text(&screen).contains("SOMEDAY"));
+    assert_eq!(saved(&app, "park"), "TODO: b\nDONE: c\nSOMEDAY: a\n");
+
+    let (app, _) = run(&["a", "b"], &[], SIZE, ">\t\t\n");
+    assert_eq!(saved(&app, "promote"), "TODO: b\nTODO: a\n");
+}
+
+#[test]
+fn move_between_lists() {
+    let (app, _) = run(&["a due:2026-10-20", "b #x", "c"], &[], SIZE, "mdmsmt");
+    assert_eq!(app.notification, "Already in TODO");
+    assert_eq!(
+        saved(&app, "move"),
+        "TODO: c\nDONE: a due:2026-10-20\nSOMEDAY: b #x\n"
+    );
+}
+
+#[test]
+fn estimates_add_up_against_capacity() {
+    let (_, screen) = run(&["a ~30m", "b ~2h", "c"], &[], SIZE, "");
+    assert!(test_backend::text(&screen).contains("0/3 done  ~2h 30m"));
+
+    let config = Config::parse("[today]\ncapacity = 1h\n", "config").unwrap();
+    let (_, screen) = run_with(config, &["a ~30m", "b ~2h", "c"], &[], SIZE, "j*T");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("~2h  today ~2h is over the 1h capacity"));
+}
+
+#[test]
+fn dates_typed_in_words() {
+    let (app, _) = run(&["a"], &[], SIZE, "A due:tomorrow defer:in-3-days\n");
+    let tomorrow = app.now.add_days(1).to_item();
+    let later = app.now.add_days(3).to_item();
+    assert_eq!(
+        saved(&app, "words"),
+        format!("TODO: a defer:{} due:{}\n", later, &tomorrow[..10])
+    );
+
+    let (app, _) = run(&["a"], &[], SIZE, "A due:someday\n");
+    assert!(app.notification.starts_with("Can't tell when `someday` is"));
+    assert_eq!(saved(&app, "unknown"), "TODO: a due:someday\n");
+}
+
+#[test]
+fn pinned_items_stay_on_top() {
+    let (app, screen) = run(&["a", "b", "c"], &[], SIZE, "GPgix\nK");
+    assert!(test_backend::text(&screen).contains("^ [ ] c #pinned"));
+    assert_eq!(app.todo_curr, 1);
+    assert_eq!(
+        saved(&app, "pinned"),
+        "TODO: c #pinned\nTODO: x\nTODO: a\nTODO: b\n"
+    );
+}
+
+#[test]
+fn due_countdowns() {
+    let today = clock::local();
+    let day = |days| today.add_days(days).to_item()[..10].to_string();
+    let todos = [
+        format!("a due:{}", day(-1)),
+        format!("b due:{}", day(1)),
+        format!("c due:{}", day(5)),
+        format!("d due:{}", day(0)),
+    ];
+    let todos: Vec<&str> = todos.iter().map(String::as_str).collect();
+    let (_, screen) = run(&todos, &[], Vec2::new(80, 10), "");
+    let text = test_backend::text(&screen);
+    assert!(text.contains(&format!("a due:{} -1d", day(-1))));
+    assert!(text.contains(&format!("b due:{} tomorrow", day(1))));
+    assert!(text.contains(&format!("c due:{} 5d", day(5))));
+    assert!(text.contains(&format!("d due:{} today", day(0))));
+}
+
+#[test]
+fn pick_something() {
+    let (app, _) = run(&["a defer:2999-01-04", "b"], &["c"], SIZE, "\tR");
+    assert_eq!((app.panel, app.todo_curr), (Status::Todo, 1));
+    assert_eq!(app.notification, "How about b? t starts a pomodoro on it");
+}
+
+#[test]
+fn stale_items() {
+    let (mut app, _) = run(&["old", "new"], &[], SIZE, "");
+    let now = clock::now();
+    app.activity = activity::Log::new(vec![
+        Entry {
+            time: now - 40 * activity::DAY,
+            event: Event::Added,
+            title: "old".to_string(),
+        },
+        Entry {
+            time: now - activity::DAY,
+            event: Event::Added,
+            title: "new".to_string(),
+        },
+    ]);
+    app.handle_event(KeyEvent::Char('j'));
+    app.handle_event(KeyEvent::Char('O'));
+    assert_eq!(
+        app.notification,
+        "1 stale items, Enter to finish, z to defer or dd to delete them"
+    );
+    assert_eq!(app.todo_curr, 0);
+
+    // Changing it doesn't make it any less stale, putting it off for later does.
+    for key in keys("+!2999-01-01\nOO") {
+        app.handle_event(key);
+    }
+    assert_eq!(app.todos[0].priority, Some('C'));
+    assert_eq!(app.todos[0].due, DateTime::parse("2999-01-01"));
+    assert_eq!(
+        app.notification,
+        "1 stale items, Enter to finish, z to defer or dd to delete them"
+    );
+    app.now = app.now.add_days(-3);
+    for key in keys("ztomorrow\nOO") {
+        app.handle_event(key);
+    }
+    assert_eq!(
+        app.notification,
+        "0 stale items, Enter to finish, z to defer or dd to delete them"
+    );
+    assert!(app.tick(Instant::now()));
+    assert_eq!(app.todos[1], "old pri:C due:2999-01-01");
+    app.handle_event(KeyEvent::Char('O'));
+    app.handle_event(KeyEvent::Char('O'));
+    assert_eq!(
+        app.notification,
+        "1 stale items, Enter to finish, z to defer or dd to delete them"
+    );
+}
+
+#[test]
+fn item_age() {
+    let config = Config::parse("[list]\nage = yes\n", "config").unwrap();
+    let (mut app, _) = run_with(config, &["old", "new"], &[], SIZE, "");
+    app.activity = activity::Log::new(vec![Entry {
+        time: clock::now() - 12 * activity::DAY - 3 * 3600,
+        event: Event::Added,
+        title: "old".to_string(),
+    }]);
+    let text = draw(&app);
+    assert!(text.contains("- [ ] old (12d)"));
+    assert!(!text.contains("new ("));
+
+    app.handle_event(KeyEvent::Char('W'));
+    assert!(!draw(&app).contains("(12d)"));
+}
+
+#[test]
+fn edited_items_keep_their_age() {
+    let (mut app, _) = run(&["old"], &[], SIZE, "");
+    let added = clock::now() - 12 * activity::DAY;
+    app.activity = activity::Log::new(vec![Entry {
+        time: added,
+        event: Event::Added,
+        title: "old".to_string(),
+    }]);
+    for key in keys("A #work\n+*P") {
+        app.handle_event(key);
+    }
+    let title = app.todos[0].title.clone();
+    let today = app.now.to_item();
+    assert_eq!(title, format!("old #work sched:{} #pinned", &today[..10]));
+    assert_eq!(app.todos[0].priority, Some('C'));
+    assert_eq!(app.activity.opened(&title), Some(added));
+    assert_eq!(app.activity.entries[0].title, title);
+    app.handle_event(KeyEvent::Char('u'));
+    assert_eq!(app.activity.opened(&app.todos[0]), Some(added));
+}
+
+#[test]
+fn habits_are_checked_off_for_the_day() {
+    let (mut app, _) = run(&["stretch #habit", "b"], &[], SIZE, "");
+    let now = clock::now();
+    let checked = |days| Entry {
+        time: now - days * activity::DAY,
+        event: Event::Checked,
+        title: "stretch #habit".to_string(),
+    };
+    app.activity = activity::Log::new(vec![checked(2), checked(1)]);
+    app.handle_event(KeyEvent::Enter);
+    assert_eq!(app.notification, "Done for today, 3 days in a row");
+    assert!(draw(&app).contains("- [ ] stretch #habit □□□□■■■"));
+    app.handle_event(KeyEvent::Enter);
+    assert_eq!(app.notification, "Already done today, see you tomorrow");
+    assert_eq!(saved(&app, "habit"), "TODO: stretch #habit\nTODO: b\n");
+    assert_eq!(app.activity.streak(now, 0).current, 3);
+}
+
+#[test]
+fn old_done_items_go_to_the_archive() {
+    let config = Config::parse("[archive]\nafter = 14d\n", "config").unwrap();
+    let (mut app, _) = run_with(config, &[], &["old", "recent", "unknown"], SIZE, "");
+    let now = clock::now();
+    let done = |title: &str, days| Entry {
+        time: now - days * activity::DAY,
+        event: Event::Done,
+        title: title.to_string(),
+    };
+    app.activity = activity::Log::new(vec![done("old", 20), done("recent", 1)]);
+    let file_path = env::temp_dir().join(format!("cli-todo-{}-archive", process::id()));
+    let file_path = file_path.to_str().unwrap();
+    app.archive_done(file_path);
+    app.archive_done(file_path);
+    let archive = storage::archive_path(file_path);
+    let archived = fs::read_to_string(&archive).unwrap();
+    fs::remove_file(&archive).unwrap();
+    assert_eq!(archived, "DONE: old\n");
+    assert_eq!(app.dones, ["recent", "unknown"]);
+}
+
+#[test]
+fn template_adds_its_items() {
+    let config = Config::parse(
+        "[templates]\nrelease checklist = bump #release; ; tag #release\n",
+        "config",
+    )
+    .unwrap();
+    let (app, _) = run_with(config, &["a"], &[], SIZE, ":template release checklist\n");
+    assert_eq!(app.notification, "Added 2 items from release checklist");
+    assert_eq!(
+        saved(&app, "template"),
+        "TODO: a\nTODO: bump #release\nTODO: tag #release\n"
+    );
+
+    let (app, _) = run(&[], &[], SIZE, ":template nope\n");
+    assert_eq!(
+        app.notification,
+        "No template nope, there are none in the config"
+    );
+}
+
+#[test]
+fn matrix_moves_items_between_quadrants() {
+    let (app, screen) = run(
+        &["a #important", "b due:2000-01-01", "c"],
+        &[],
+        SIZE,
+        "e\t1\t4",
+    );
+    let text = test_backend::text(&screen);
+    assert!(text.contains("1 Do first (1)"));
+    assert!(text.contains("4 Eliminate (1)"));
+    assert_eq!(app.notification, "b is due soon, that makes it urgent");
+    assert_eq!(
+        saved(&app, "matrix"),
+        "TODO: a #important #urgent\nTODO: b due:2000-01-01\nTODO: c\n"
+    );
+}
+
+#[test]
+fn report_groups_by_tag() {
+    let from = DateTime::parse("2026-10-12").unwrap();
+    let at = |day: i64| from.add_days(day).to_unix(0) + 3600;
+    let entry = |day, event, title: &str| Entry {
+        time: at(day),
+        event,
+        title: title.to_string(),
+    };
+    let entries = [
+        entry(-1, Event::Done, "too early #work"),
+        entry(0, Event::Added, "deploy #work #ops"),
+        entry(1, Event::Done, "deploy #work #ops"),
+        entry(2, Event::Done, "water plants"),
+        entry(7, Event::Done, "too late"),
+    ];
+    let lists = Lists::default();
+    let render = |markdown, group_by| {
+        report::render(
+            &entries,
+            from,
+            from.add_days(7),
+            0,
+            markdown,
+            group_by,
+            &lists,
+        )
+    };
+    assert_eq!(
+        render(false, GroupBy::Tag),
+        "Report 2026-10-12 to 2026-10-18\n\
+         \nCompleted (2)\n  #work (1)\n    - deploy #work #ops\n  #ops (1)\n    - deploy #work #ops\n  Untagged (1)\n    - water plants\n\
+         \nAdded (1)\n  #work (1)\n    - deploy #work #ops\n  #ops (1)\n    - deploy #work #ops\n"
+    );
+    assert!(render(true, GroupBy::Tag).starts_with(
+        "# Report 2026-10-12 to 2026-10-18\n\n## Completed (2)\n\n### #work (1)\n\n- [x] deploy"
+    ));
+}
+
+#[test]
+fn report_groups_by_list_and_priority() {
+    let from = DateTime::parse("2026-10-12").unwrap();
+    let entry = |title: &str| Entry {
+        time: from.to_unix(0) + 3600,
+        event: Event::Done,
+        title: title.to_string(),
+    };
+    let entries = [
+        entry("deploy #important"),
+        entry("water plants"),
+        entry("call mom #urgent"),
+    ];
+    let lists = Lists {
+        dones: vec!["deploy #important".into(), "call mom #urgent".into()],
+        ..Lists::default()
+    };
+    let completed = |group_by| {
+        let text = report::render(&entries, from, from.add_days(7), 0, false, group_by, &lists);
+        text.lines()
+            .skip(3)
+            .take_while(|line| !line.is_empty())
+            .map(|line| format!("{}\n", line))
+            .collect::<String>()
+    };
+    assert_eq!(
+        completed(GroupBy::List),
+        "  DONE (2)\n    - deploy #important\n    - call mom #urgent\n\
+         \x20 Archived or deleted (1)\n    - water plants\n"
+    );
+    assert_eq!(
+        completed(GroupBy::Priority),
+        "  Schedule (1)\n    - deploy #important\n  Eliminate (1)\n    - water plants\n\
+         \x20 Delegate (1)\n    - call mom #urgent\n"
+    );
+}
+
+#[test]
+fn html_report() {
+    let now = DateTime::parse("2026-10-15T08:00").unwrap();
+    let from = DateTime::parse("2026-10-09").unwrap();
+    let lists = Lists {
+        todos: vec!["late <b> & co due:2026-10-14".into(), "later".into()],
+        dones: vec!["ship it".into()],
+        ..Lists::default()
+    };
+    let entries = [Entry {
+        time: DateTime::parse("2026-10-14T17:00").unwrap().to_unix(0),
+        event: Event::Done,
+        title: "ship it".to_string(),
+    }];
+    let html = report::render_html(&lists, &entries, from, from.add_days(7), 0, now);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h1>Report 2026-10-09 to 2026-10-15</h1>"));
+    assert!(html.contains("<tr><th>Completed in these days</th><td>1</td></tr>"));
+    assert!(html.contains("<progress value=\"1\" max=\"3\"></progress> 33% done"));
+    assert!(html.contains(
+        "<h2>Overdue (1)</h2>\n<ul class=\"overdue\">\n<li>late &lt;b&gt; &amp; co due:2026-10-14</li>"
+    ));
+    assert!(html.contains("<h2>DONE (1)</h2>\n<ul class=\"done\">\n<li>ship it</li>"));
+}
+
+#[test]
+fn morning_summary() {
+    let now = DateTime::parse("2026-10-15T08:00").unwrap();
+    let todos: Vec<Item> = [
+        "late due:2026-10-14",
+        "meeting due:2026-10-15T07:30",
+        "report due:2026-10-15",
+        "call sched:2026-10-15",
+        "later due:2026-10-20",
+    ]
+    .map(Item::from)
+    .to_vec();
+    let entries = [Entry {
+        time: DateTime::parse("2026-10-14T17:00").unwrap().to_unix(0),
+        event: Event::Done,
+        title: "ship it".to_string(),
+    }];
+    assert_eq!(
+        report::summary(&todos, &entries, now, 0, false),
+        "Summary for 2026-10-15\n\
+         \nOverdue (2)\n  - late due:2026-10-14\n  - meeting due:2026-10-15T07:30\n\
+         \nToday (2)\n  - report due:2026-10-15\n  - call sched:2026-10-15\n\
+         \nDone yesterday (1)\n  - ship it\n"
+    );
+    assert!(report::summary(&todos, &entries, now, 0, true)
+        .ends_with("## Done yesterday (1)\n\n- [x] ship it\n"));
+}
+
+#[test]
+fn conflict_copies_are_merged_in() {
+    let dir = env::temp_dir().join(format!("cli-todo-{}-conflicts", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file_path = dir.join("TODO");
+    let file_path = file_path.to_str().unwrap();
+    let other = format!("{}.sync-conflict-20261015-093012-ABCDEFG", file_path);
+    // The other machine finished `a` and added `c` after the last sync.
+    fs::write(format!("{}.state", file_path), "100 todo a\n100 todo b\n").unwrap();
+    fs::write(&other, "TODO: b\nTODO: c\nDONE: a\n").unwrap();
+    fs::write(
+        format!("{}.state", other),
+        "100 todo b\n200 todo c\n200 done a\n",
+    )
+    .unwrap();
+    let (mut app, _) = run(&["a", "b"], &[], SIZE, "");
+    app.open_conflicts(file_path, merge::conflicts(file_path).unwrap());
+    let screen = draw(&app);
+    assert!(screen.contains("- [ ] b             * [ ] c             * [ ] c"));
+    assert!(screen.contains("                    * [x] a             * [x] a"));
+    app.handle_event(KeyEvent::Enter);
+    assert_eq!(app.todos, ["b", "c"]);
+    assert_eq!(app.dones, ["a"]);
+    assert!(!Path::new(&other).exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn disputed_items_are_settled_one_by_one() {
+    let dir = env::temp_dir().join(format!("cli-todo-{}-disputed", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file_path = dir.join("TODO");
+    let file_path = file_path.to_str().unwrap();
+    let other = format!("{}.sync-conflict-20261015-093012-ABCDEFG", file_path);
+    // They finished `a` after we last saw it, we finished `b` after they did.
+    fs::write(format!("{}.state", file_path), "100 todo a\n300 done b\n").unwrap();
+    fs::write(&other, "TODO: b\nDONE: a\n").unwrap();
+    fs::write(format!("{}.state", other), "200 done a\n100 todo b\n").unwrap();
+    let (mut app, _) = run(&["a"], &["b"], SIZE, "");
+    app.open_conflicts(file_path, merge::conflicts(file_path).unwrap());
+    assert!(draw(&app).starts_with("1/2 disputed: h keeps ours"));
+    // Ours for `a`, and `b` with a better title.
+    for key in keys("he!\n") {
+        app.handle_event(key);
+    }
+    let screen = draw(&app);
+    assert!(screen.contains("- [ ] a             * [ ] b             - [ ] a"));
+    assert!(screen.contains("- [x] b             * [x] a             * [x] b!"));
+    app.handle_event(KeyEvent::Enter);
+    assert_eq!(app.todos, ["a"]);
+    assert_eq!(app.dones, ["b!"]);
+    let state = fs::read_to_string(format!("{}.state", file_path)).unwrap();
+    assert!(state.contains(" deleted b\n"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn first_sync_in_json() {
+    let dir = env::temp_dir().join(format!("cli-todo-{}-first-sync", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file_path = dir.join("todo.json").to_str().unwrap().to_string();
+    let remote = dir.join("remote.json").to_str().unwrap().to_string();
+    let lists = Lists {
+        todos: vec!["a".into()],
+        ..Lists::default()
+    };
+    Format::Json.storage().save(&lists, &file_path).unwrap();
+    // Nothing at `remote` yet, scp takes local paths as well.
+    assert_eq!(sync::run(&file_path, Format::Json, &remote).unwrap(), 0);
+    let synced = Format::Json
+        .storage()
+        .load(&remote, &mut |_, _| {})
+        .unwrap();
+    assert_eq!(synced.todos, ["a"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn webhooks_get_the_event() {
+    let config = Config::parse(
+        "[webhooks]\non-done = https://example.com/a\non-done = https://example.com/b\n",
+        "config",
+    )
+    .unwrap();
+    assert!(config.hooks.has("on-done"));
+    assert!(!config.hooks.has("on-add"));
+    assert_eq!(
+        hooks::webhook_payload(
+            "on-done",
+            &hooks::item(&"water plants".into(), Status::Done)
+        )
+        .to_string(),
+        r#"{"event":"on-done","text":"Done: water plants","content":"Done: water plants","title":"water plants","status":"done"}"#
+    );
+    assert_eq!(
+        Config::parse("[webhooks]\non-done = example.com\n", "config").err(),
+        Some("config:2: ERROR: expected a URL, got `example.com`".to_string())
+    );
+}
+
+#[test]
+fn jira_issues_are_mirrored() {
+    let mut lists = storage::Lists {
+        todos: vec!["fix the login #PROJ-1".into()],
+        dones: vec!["update docs #PROJ-2".into()],
+        ..Default::default()
+    };
+    let issues = [1, 2, 3].map(|number| (format!("PROJ-{}", number), "new".to_string()));
+    let (added, finished) = jira::mirror(&mut lists, &issues);
+    assert_eq!(added, 1);
+    assert_eq!(finished, ["PROJ-2"]);
+    assert_eq!(lists.todos, ["fix the login #PROJ-1", "new #PROJ-3"]);
+
+    let config = Config {
+        jira: Some(Jira {
+            url: "https://example.atlassian.net/".to_string(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (app, _) = run_with(config, &[], &[], SIZE, "");
+    assert_eq!(
+        app.link("see https://example.com/spec #PROJ-1").as_deref(),
+        Some("https://example.atlassian.net/browse/PROJ-1")
+    );
+    assert_eq!(
+        app.link("see https://example.com/spec #release-2")
+            .as_deref(),
+        Some("https://example.com/spec")
+    );
+    assert_eq!(app.link("nothing here"), None);
+}
+
+#[test]
+fn stats_count_what_happened() {
+    let (app, screen) = run(&["a"], &[], SIZE, "ib\n\ns");
+    assert_eq!(app.activity.entries.len(), 2);
+    let text = test_backend::text(&screen);
+    assert!(text.contains("Completed"));
+    assert!(text.contains("Average time to complete: 0m over 1 items"));
+    assert!(text.contains("Backlog: 1 open, +0 over the last 6 days"));
+}
+
+#[test]
+fn points_for_getting_things_done() {
+    let config = Config::parse("[points]\nenabled = yes\nhour = 20\n", "config").unwrap();
+    let (_, screen) = run_with(config, &["a ~30m #important", "b"], &[], SIZE, "\n\ns");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("Points: 40, level 1 (40/100 to level 2)"));
+
+    let config = Config::parse("[points]\ndone = 5\n", "config").unwrap();
+    let (app, _) = run_with(config, &["a ~2h"], &[], SIZE, "\n");
+    assert_eq!(app.notification, "DONE! +25 points");
+}
+
+#[test]
+fn streak_in_the_status_bar() {
+    let (_, screen) = run(&["a", "b"], &[], SIZE, "\n");
+    assert!(test_backend::text(&screen).contains("1/2 done  streak 1 (best 1)"));
+}
+
+#[test]
+fn heatmap_shows_completions() {
+    let (_, screen) = run(&["a"], &[], SIZE, "\ns\t\t");
+    let text = test_backend::text(&screen);
+    assert!(text.contains("Done per day"));
+    assert!(text.contains("Less · ░ ▒ ▓ █ More   1 done in 28 weeks"));
+}
+
+#[test]
+fn snapshot_empty() {
+    let (_, screen) = run(&[], &[], SIZE, "");
+    assert_snapshot("empty", &screen);
+}
+
+#[test]
+fn snapshot_long_list_scrolled() {
+    let todos = (1..=30).map(|x| format!("item {}", x)).collect::<Vec<_>>();
+    let todos = todos.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    let (_, screen) = run(&todos, &["done"], SIZE, "jjjjjjjjjjjj");
+    assert_snapshot("long_list_scrolled", &screen);
+}
+
+#[test]
+fn scrollbar_follows_the_cursor() {
+    let todos = (1..=30).map(|x| format!("item {}", x)).collect::<Vec<_>>();
+    let todos = todos.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+    let thumb = |keys| {
+        let (_, screen) = run(&todos, &["done"], SIZE, keys);
+        let text = test_backend::text(&screen);
+        text.lines()
+            .position(|line| line.starts_with("- [ ]") && line.contains('█'))
+    };
+    assert_eq!(thumb(""), Some(3));
+    assert_eq!(thumb("G"), Some(9));
+
+    // Lists that fit don't get one.
+    let (_, screen) = run(&["a", "b"], &["c"], SIZE, "");
+    assert!(!test_backend::text(&screen).contains('│'));
+}
+
+#[test]
+fn snapshot_edit_mode() {
+    let (_, screen) = run(&["water plants"], &["weekly report"], SIZE, "iweek");
+    assert_snapshot("edit_mode", &screen);
+}
+
+#[test]
+fn snapshot_narrow_terminal() {
+    let (_, screen) = run(
+        &["a rather long item that won't fit", "b"],
+        &["c"],
+        Vec2::new(20, 6),
+        "",
+    );
+    assert_snapshot("narrow_terminal", &screen);
+}
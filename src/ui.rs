@@ -1,17 +1,302 @@
+use crate::backend::Backend;
+use crate::buffer::Buffer;
+use crate::input::KeyEvent;
 use crate::layout::{Layout, LayoutKind};
+use crate::style::{self, Palette, Selection, Style};
 use crate::vec2::Vec2;
-use crate::{HIGHLIGHT_PAIR, REGULAR_PAIR};
+use todo_core::priority;
 
-use ncurses::*;
+use std::cmp;
+use std::mem;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Default)]
 pub struct Ui {
+    pub backend: Box<dyn Backend>,
+    // The frame being drawn and the one currently on the screen.
+    frame: Buffer,
+    previous: Option<Buffer>,
     pub layouts: Vec<Layout>,
-    pub key: Option<i32>,
+    pub palette: Palette,
+    pub unicode: bool,
+    // Whether selected list items get a marker in place of the first character of their mark.
+    marker: bool,
+}
+
+// Everything an edit field has to remember between frames for a single editing session.
+#[derive(Default)]
+pub struct EditState {
+    pub cursor: usize,
+    // What the buffer looked like before the session started, so it can be cancelled.
+    pub original: String,
+    undo: Vec<(String, usize)>,
+    typing: bool,
+}
+
+impl EditState {
+    pub fn new(buffer: &str, cursor: usize) -> Self {
+        Self {
+            cursor,
+            original: buffer.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn snapshot(&mut self, buffer: &str) {
+        self.undo.push((buffer.to_string(), self.cursor));
+        self.typing = false;
+    }
+
+    fn undo(&mut self, buffer: &mut String) {
+        if let Some((previous, cursor)) = self.undo.pop() {
+            *buffer = previous;
+            self.cursor = cursor;
+        }
+        self.typing = false;
+    }
+
+    // Applies `key` to `buffer`. Returns false for keys that mean nothing to an edit field so the
+    // caller can make something else out of them.
+    //
+    // `suggestions` are complete texts the buffer may be heading for, the most likely one last.
+    // Tab or Right at the end of the line accepts the best match.
+    pub fn handle_key(
+        &mut self,
+        buffer: &mut String,
+        key: KeyEvent,
+        suggestions: &[String],
+        abbreviations: &[(String, String)],
+    ) -> bool {
+        if self.cursor > buffer.len() {
+            self.cursor = buffer.len();
+        }
+        let accepting =
+            self.cursor == buffer.len() && matches!(key, KeyEvent::Right | KeyEvent::Tab);
+        match key {
+            _ if accepting && suggestion(buffer, suggestions).is_some() => {
+                self.snapshot(buffer);
+                *buffer = suggestion(buffer, suggestions).unwrap_or_default();
+                self.cursor = buffer.len();
+            }
+            KeyEvent::Char(c) => {
+                // A whole run of typing is undone at once, a word at a time.
+                if !self.typing || c == ' ' {
+                    self.snapshot(buffer);
+                    self.typing = true;
+                }
+                if self.cursor >= buffer.len() {
+                    buffer.push(c);
+                } else {
+                    buffer.insert(self.cursor, c);
+                }
+                self.cursor += c.len_utf8();
+                expand_abbreviation(buffer, &mut self.cursor, abbreviations);
+            }
+            KeyEvent::Ctrl('_' | 'z') => self.undo(buffer),
+            KeyEvent::Left => {
+                self.cursor = previous_char(buffer, self.cursor);
+                self.typing = false;
+            }
+            KeyEvent::Right => {
+                self.cursor = next_char(buffer, self.cursor);
+                self.typing = false;
+            }
+            KeyEvent::Home => {
+                self.cursor = 0;
+                self.typing = false;
+            }
+            KeyEvent::End => {
+                self.cursor = buffer.len();
+                self.typing = false;
+            }
+            KeyEvent::Backspace => {
+                if self.cursor > 0 {
+                    self.snapshot(buffer);
+                    self.cursor = previous_char(buffer, self.cursor);
+                    buffer.remove(self.cursor);
+                }
+            }
+            KeyEvent::Delete => {
+                if self.cursor < buffer.len() {
+                    self.snapshot(buffer);
+                    buffer.remove(self.cursor);
+                }
+            }
+            KeyEvent::CtrlLeft | KeyEvent::AltLeft | KeyEvent::Alt('b') => {
+                self.cursor = word_left(buffer, self.cursor);
+                self.typing = false;
+            }
+            KeyEvent::CtrlRight | KeyEvent::AltRight | KeyEvent::Alt('f') => {
+                self.cursor = word_right(buffer, self.cursor);
+                self.typing = false;
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+// The part of `text` that fits in `width` cells.
+fn clip(text: &str, width: i32) -> &str {
+    let end = text
+        .char_indices()
+        .nth(width.max(0) as usize)
+        .map_or(text.len(), |(index, _)| index);
+    &text[..end]
+}
+
+// The most likely complete text for what's typed so far, if there is one.
+fn suggestion(buffer: &str, suggestions: &[String]) -> Option<String> {
+    suggestions
+        .iter()
+        .rev()
+        .find(|x| !buffer.is_empty() && x.len() > buffer.len() && x.starts_with(buffer))
+        .cloned()
+}
+
+// Expands an abbreviation that was just completed right before the cursor. Only whole words are
+// expanded, typing `x;std` leaves the `;std` alone.
+fn expand_abbreviation(
+    buffer: &mut String,
+    cursor: &mut usize,
+    abbreviations: &[(String, String)],
+) {
+    let typed = &buffer[..*cursor];
+    let found = abbreviations.iter().find(|(abbreviation, _)| {
+        typed
+            .strip_suffix(abbreviation.as_str())
+            .is_some_and(|before| {
+                before.is_empty() || before.ends_with(|c: char| c.is_ascii_whitespace())
+            })
+    });
+    if let Some((abbreviation, expansion)) = found {
+        let start = *cursor - abbreviation.len();
+        buffer.replace_range(start..*cursor, expansion);
+        *cursor = start + expansion.len();
+    }
+}
+
+// The cursor is a byte offset into the buffer, these keep it on character boundaries.
+fn previous_char(buffer: &str, cursor: usize) -> usize {
+    buffer[..cursor]
+        .chars()
+        .next_back()
+        .map_or(0, |c| cursor - c.len_utf8())
+}
+
+fn next_char(buffer: &str, cursor: usize) -> usize {
+    buffer[cursor..]
+        .chars()
+        .next()
+        .map_or(cursor, |c| cursor + c.len_utf8())
+}
+
+fn word_left(buffer: &str, cursor: usize) -> usize {
+    let bytes = buffer.as_bytes();
+    let mut cursor = cursor;
+    while cursor > 0 && bytes[cursor - 1].is_ascii_whitespace() {
+        cursor -= 1;
+    }
+    while cursor > 0 && !bytes[cursor - 1].is_ascii_whitespace() {
+        cursor -= 1;
+    }
+    cursor
+}
+
+fn word_right(buffer: &str, cursor: usize) -> usize {
+    let bytes = buffer.as_bytes();
+    let mut cursor = cursor;
+    while cursor < bytes.len() && !bytes[cursor].is_ascii_whitespace() {
+        cursor += 1;
+    }
+    while cursor < bytes.len() && bytes[cursor].is_ascii_whitespace() {
+        cursor += 1;
+    }
+    cursor
+}
+
+const SPINNER_UNICODE: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_ASCII: [&str; 4] = ["|", "/", "-", "\\"];
+
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+// Renders `ratio` of `width` cells as a bar. With unicode the last cell gets a partial block, so
+// the bar grows smoothly instead of jumping a whole cell at a time.
+fn render_bar(ratio: f32, width: i32, unicode: bool) -> String {
+    let width = width.max(0) as usize;
+    let ratio = ratio.clamp(0.0, 1.0);
+    if unicode {
+        let eighths = (ratio * (width * 8) as f32).round() as usize;
+        let mut bar = "█".repeat(eighths / 8);
+        let partial = eighths % 8;
+        if partial > 0 {
+            bar.push(EIGHTHS[partial]);
+        }
+        bar
+    } else {
+        "#".repeat((ratio * width as f32).round() as usize)
+    }
 }
 
 impl Ui {
-   pub fn begin(&mut self, pos: Vec2, kind: LayoutKind) {
+    pub fn new(backend: Box<dyn Backend>) -> Self {
+        Self {
+            unicode: backend.unicode(),
+            frame: Buffer::new(backend.size(), Palette::default().style(style::REGULAR)),
+            previous: None,
+            backend,
+            layouts: Vec::new(),
+            palette: Palette::default(),
+            marker: false,
+        }
+    }
+
+    pub fn select_with(&mut self, selection: Selection) {
+        use style::Color::*;
+        self.marker = selection != Selection::Reverse;
+        let style = match selection {
+            Selection::Reverse => return,
+            Selection::Marker => Style::new(White, Black),
+            Selection::Both => Style::new(Black, White),
+        };
+        self.palette
+            .define(style::SELECTION, style.with_attrs(style::BOLD));
+    }
+
+    // Starts a new blank frame sized to the terminal.
+    pub fn clear(&mut self) {
+        self.frame = Buffer::new(self.backend.size(), self.palette.style(style::REGULAR));
+    }
+
+    // After the backend took the terminal back, the next frame can't rely on anything being on
+    // the screen.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pub fn resume(&mut self) {
+        self.backend.resume();
+        self.previous = None;
+    }
+
+    // Sends whatever changed since the last frame to the terminal.
+    pub fn present(&mut self) {
+        let blank = self.palette.style(style::REGULAR);
+        let previous = match self.previous.take() {
+            Some(previous) if previous.size == self.frame.size => previous,
+            // First frame or the terminal got resized, nothing on the screen can be trusted.
+            _ => {
+                self.backend.clear();
+                Buffer::new(self.frame.size, blank)
+            }
+        };
+        for (pos, text, style) in self.frame.diff(&previous) {
+            self.backend.draw(pos, &text, style);
+        }
+        self.backend.present();
+        self.previous = Some(mem::replace(
+            &mut self.frame,
+            Buffer::new(Vec2::default(), blank),
+        ));
+    }
+
+    pub fn begin(&mut self, pos: Vec2, kind: LayoutKind) {
         assert!(self.layouts.is_empty());
         self.layouts.push(Layout {
             kind,
@@ -20,7 +305,7 @@ impl Ui {
         })
     }
 
-   pub fn begin_layout(&mut self, kind: LayoutKind) {
+    pub fn begin_layout(&mut self, kind: LayoutKind) {
         let layout = self
             .layouts
             .last()
@@ -33,7 +318,7 @@ impl Ui {
         });
     }
 
-   pub fn end_layout(&mut self) {
+    pub fn end_layout(&mut self) {
         let layout = self
             .layouts
             .pop()
@@ -44,87 +329,231 @@ impl Ui {
             .add_widget(layout.size);
     }
 
-    pub fn label_fixed_width(&mut self, text: &str, width: i32, pair: i16) {
+    pub fn label_fixed_width(&mut self, text: &str, width: i32, style: &str) {
         let layout = self
             .layouts
             .last_mut()
             .expect("Trying to render label outside of any layout");
         let pos = layout.available_pos();
 
-        mv(pos.y, pos.x);
-        attron(COLOR_PAIR(pair));
-        addstr(text);
-        attroff(COLOR_PAIR(pair));
+        self.frame
+            .put_str(pos, clip(text, width), self.palette.style(style));
 
         layout.add_widget(Vec2::new(width, 1));
     }
 
-    pub fn edit_field(&mut self, buffer: &mut String, cursor: &mut usize, width: i32) {
+    // A label made of `mark` followed by `text`, without gluing the two together into a new string
+    // for every row of every frame. On regular rows the `#tags` get their colors, the other styles
+    // say something about the whole row that the tags shouldn't drown out.
+    pub fn list_item(&mut self, mark: &str, text: &str, width: i32, style_name: &str) {
         let layout = self
             .layouts
             .last_mut()
-            .expect("Trying to render edit field outside of any layout");
+            .expect("Trying to render list item outside of any layout");
         let pos = layout.available_pos();
+        let style = self.palette.style(style_name);
+        let marked;
+        let mark = if self.marker && style_name == style::SELECTION {
+            let glyph = if self.unicode { '▶' } else { '>' };
+            marked = format!("{}{}", glyph, mark.chars().skip(1).collect::<String>());
+            &marked
+        } else {
+            mark
+        };
+        let mark = clip(mark, width);
+        let offset = mark.chars().count() as i32;
 
-        if *cursor > buffer.len() {
-            *cursor = buffer.len();
-        }
-
-        if let Some(key) = self.key.take() {
-            match key {
-                32..=126 => {
-                    if *cursor >= buffer.len() {
-                        buffer.push(key as u8 as char);
-                    } else {
-                        buffer.insert(*cursor, key as u8 as char);
-                    }
-                    *cursor += 1;
-                }
-                constants::KEY_LEFT => {
-                    if *cursor > 0 {
-                        *cursor -= 1
-                    }
-                }
-                constants::KEY_RIGHT => {
-                    if *cursor < buffer.len() {
-                        *cursor += 1;
-                    }
-                }
-                constants::KEY_BACKSPACE => {
-                    if *cursor > 0 {
-                        *cursor -= 1;
-                        if *cursor < buffer.len() {
-                            buffer.remove(*cursor);
-                        }
-                    }
-                }
-                constants::KEY_DC => {
-                    if *cursor < buffer.len() {
-                        buffer.remove(*cursor);
-                    }
+        self.frame.put_str(pos, mark, style);
+        self.frame.put_str(
+            pos + Vec2::new(offset, 0),
+            clip(text, width - offset),
+            style,
+        );
+        if style_name == style::REGULAR {
+            let mut at = offset;
+            for word in text.split(' ') {
+                if at >= width {
+                    break;
                 }
-                _ => {
-                    self.key = Some(key);
+                // Contexts and projects get colors like tags do, under their name with the @ or +.
+                let project = word
+                    .strip_prefix('+')
+                    .is_some_and(|x| x.starts_with(char::is_alphabetic));
+                let context = word.len() > 1 && word.starts_with('@');
+                let highlight =
+                    if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+                        Some(self.palette.tag(tag, style))
+                    } else if project || context {
+                        Some(self.palette.tag(word, style))
+                    } else {
+                        priority::parse(word).and_then(|level| {
+                            let index = priority::LEVELS.iter().position(|&x| x == level)?;
+                            Some(self.palette.style(style::PRIORITIES[index]))
+                        })
+                    };
+                if let Some(highlight) = highlight {
+                    self.frame
+                        .put_str(pos + Vec2::new(at, 0), clip(word, width - at), highlight);
                 }
+                at += word.chars().count() as i32 + 1;
             }
         }
 
+        layout.add_widget(Vec2::new(width, 1));
+    }
+
+    // Draws `buffer` with the cursor and the ghost text of the best suggestion. The keys typed into
+    // it go through EditState::handle_key() first.
+    pub fn edit_field(
+        &mut self,
+        buffer: &str,
+        edit: &EditState,
+        width: i32,
+        suggestions: &[String],
+    ) {
+        let layout = self
+            .layouts
+            .last_mut()
+            .expect("Trying to render edit field outside of any layout");
+        let pos = layout.available_pos();
+        let cursor = edit.cursor.min(buffer.len());
+        // Where things go on the screen is in characters rather than bytes.
+        let column = buffer.get(..cursor).unwrap_or(buffer).chars().count();
+        let end = buffer.chars().count();
+
+        let suggestion = suggestion(buffer, suggestions);
+
         // Buffer
         {
-            mv(pos.y, pos.x);
-            attron(COLOR_PAIR(REGULAR_PAIR));
-            addstr(buffer);
-            attroff(COLOR_PAIR(REGULAR_PAIR));
+            self.frame
+                .put_str(pos, clip(buffer, width), self.palette.style(style::REGULAR));
             layout.add_widget(Vec2::new(width, 1));
         }
 
+        // Suggestion
+        if let Some(suggestion) = &suggestion {
+            let x = end as i32;
+            self.frame.put_str(
+                pos + Vec2::new(x, 0),
+                clip(&suggestion[buffer.len()..], width - x),
+                self.palette.style(style::GHOST),
+            );
+        }
+
         // Cursor
-        {
-            mv(pos.y, pos.x + *cursor as i32);
-            attron(COLOR_PAIR(HIGHLIGHT_PAIR));
-            addstr(buffer.get(*cursor..=*cursor).unwrap_or(" "));
-            attroff(COLOR_PAIR(HIGHLIGHT_PAIR));
+        if (column as i32) < width {
+            // At the end of the line the cursor sits on the first character of the suggestion.
+            let under = buffer
+                .get(cursor..)
+                .and_then(|rest| rest.chars().next())
+                .or_else(|| suggestion.as_ref()?.get(buffer.len()..)?.chars().next())
+                .unwrap_or(' ')
+                .to_string();
+            self.frame.put_str(
+                pos + Vec2::new(column as i32, 0),
+                &under,
+                self.palette.style(style::CURSOR),
+            );
+        }
+    }
+
+    pub fn spinner(&mut self, label: &str, ratio: Option<f32>, width: i32, style: &str) {
+        // Derive the frame from the wall clock rather than counting calls, so the spinner turns
+        // at the same speed no matter how often the UI happens to be redrawn.
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or(0);
+        let frames: &[&str] = if self.unicode {
+            &SPINNER_UNICODE
+        } else {
+            &SPINNER_ASCII
+        };
+        let frame = frames[(millis / 100) as usize % frames.len()];
+        let text = match ratio {
+            Some(ratio) => format!("{} {} {:.0}%", frame, label, ratio * 100.0),
+            None => format!("{} {}", frame, label),
+        };
+        self.label_fixed_width(&text, width, style);
+    }
+
+    pub fn gauge(&mut self, label: &str, ratio: f32, width: i32, style: &str) {
+        let layout = self
+            .layouts
+            .last_mut()
+            .expect("Trying to render gauge outside of any layout");
+        let pos = layout.available_pos();
+
+        let label = format!(" {label}");
+        let bar_width = width - label.chars().count() as i32 - 2;
+        let bar = render_bar(ratio, bar_width, self.unicode);
+        let padding = (bar_width.max(0) as usize).saturating_sub(bar.chars().count());
+        let track = if self.unicode { ' ' } else { '-' };
+
+        self.frame.put_str(
+            pos,
+            &format!("[{}{}]{}", bar, track.to_string().repeat(padding), label),
+            self.palette.style(style),
+        );
+
+        layout.add_widget(Vec2::new(width, 1));
+    }
+
+    pub fn bar_chart(&mut self, bars: &[(&str, usize)], width: i32, style: &str) {
+        let label_width = bars
+            .iter()
+            .map(|(label, _)| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let max = bars.iter().map(|(_, value)| *value).max().unwrap_or(0);
+        let value_width = max.to_string().len();
+        let bar_width = width - (label_width + value_width) as i32 - 2;
+
+        for (label, value) in bars {
+            let ratio = if max > 0 {
+                *value as f32 / max as f32
+            } else {
+                0.0
+            };
+            let bar = render_bar(ratio, bar_width, self.unicode);
+            let padding = (bar_width.max(0) as usize).saturating_sub(bar.chars().count());
+            self.label_fixed_width(
+                &format!(
+                    "{:<label_width$} {}{} {:>value_width$}",
+                    label,
+                    bar,
+                    " ".repeat(padding),
+                    value
+                ),
+                width,
+                style,
+            );
+        }
+    }
+
+    // A column `height` rows high with a thumb showing where the `height` rows from `scroll` on are
+    // among all `count` of them.
+    pub fn scrollbar(&mut self, height: usize, count: usize, scroll: usize, style: &str) {
+        let (track, thumb) = if self.unicode {
+            ("│", "█")
+        } else {
+            ("|", "#")
+        };
+        let size = cmp::max(height * height / cmp::max(count, 1), 1);
+        let start = match count.saturating_sub(height) {
+            0 => 0,
+            rest => cmp::min(scroll, rest) * (height - size) / rest,
+        };
+        self.begin_layout(LayoutKind::Vert);
+        for row in 0..height {
+            let cell = if (start..start + size).contains(&row) {
+                thumb
+            } else {
+                track
+            };
+            self.label_fixed_width(cell, 1, style);
         }
+        self.end_layout();
     }
 
     pub fn end(&mut self) {
@@ -1,6 +1,6 @@
 use std::ops::{Add, Mul};
 
-#[derive(Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct Vec2 {
     pub x: i32,
     pub y: i32,
@@ -0,0 +1,401 @@
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::item::Item;
+use crate::paths::data_dir;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    Added,
+    Done,
+    // Moved back from DONE to TODO.
+    Undone,
+    // Deleted from TODO without getting done. Deleting DONE items changes nothing worth counting.
+    Deleted,
+    // A habit done for the day. It stays in TODO for the next one.
+    Checked,
+}
+
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Event::Added => "added",
+            Event::Done => "done",
+            Event::Undone => "undone",
+            Event::Deleted => "deleted",
+            Event::Checked => "checked",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        [
+            Event::Added,
+            Event::Done,
+            Event::Undone,
+            Event::Deleted,
+            Event::Checked,
+        ]
+        .into_iter()
+        .find(|event| event.name() == name)
+    }
+
+    // What it does to the number of open items.
+    fn change(self) -> i64 {
+        match self {
+            Event::Added | Event::Undone => 1,
+            Event::Done | Event::Deleted => -1,
+            Event::Checked => 0,
+        }
+    }
+
+    // Whether it's counted with `other`, habits done for the day count as done.
+    fn counts_as(self, other: Event) -> bool {
+        self == other || (self == Event::Checked && other == Event::Done)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    // Unix time.
+    pub time: i64,
+    pub event: Event,
+    pub title: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Period {
+    pub added: usize,
+    pub done: usize,
+    // Open items at the end of the period.
+    pub open: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Streak {
+    // Days in a row with something done, up to today or yesterday.
+    pub current: usize,
+    pub best: usize,
+    pub done_today: bool,
+}
+
+pub const DAY: i64 = 24 * 60 * 60;
+
+// Everything that happened to items as `<unix time> <event> <title>`, oldest first, which is what
+// the stats are made of. Like the pomodoro log it's only ever appended to, items that get a new
+// title as `<unix time> renamed <length of the old title> <old title> <new title>`.
+#[derive(Default)]
+pub struct Log {
+    path: Option<PathBuf>,
+    pub entries: Vec<Entry>,
+    // When each title was last added or moved back to TODO, and when it was last marked DONE, kept
+    // up to date by load() and add().
+    opened: HashMap<String, i64>,
+    closed: HashMap<String, i64>,
+    // Every time each habit was done, oldest first.
+    checked: HashMap<String, Vec<i64>>,
+    // Which entries are about each title, for renaming it.
+    titles: HashMap<String, Vec<usize>>,
+    // What completion_time() came to, the stats show it every frame. Forgotten when an entry comes
+    // in or a title changes.
+    completion: Cell<Option<Option<(i64, usize)>>>,
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as i64)
+}
+
+// The old and the new title of a `renamed` line. The length comes first since titles may have
+// spaces or anything else in them.
+fn renamed(rest: &str) -> Option<(&str, &str)> {
+    let (length, rest) = rest.split_once(' ')?;
+    let (from, to) = rest.split_at_checked(length.parse().ok()?)?;
+    Some((from, to.strip_prefix(' ')?))
+}
+
+impl Log {
+    pub fn load() -> Self {
+        data_dir().map_or_else(Self::default, |dir| Self::open(dir.join("activity")))
+    }
+
+    // The log kept in the file at `path`.
+    pub fn open(path: PathBuf) -> Self {
+        // A missing or unreadable log only means the stats start from scratch.
+        let source = fs::read_to_string(&path).unwrap_or_default();
+        let mut log = Self::default();
+        for line in source.lines() {
+            let mut parts = line.splitn(3, ' ');
+            let (Some(Ok(time)), Some(event)) = (parts.next().map(str::parse), parts.next()) else {
+                continue;
+            };
+            let rest = parts.next().unwrap_or("");
+            // Logs from before items had their due dates apart have them in the titles.
+            if event == "renamed" {
+                if let Some((from, to)) = renamed(rest) {
+                    let (from, to) = (Item::parse(from).title, Item::parse(to).title);
+                    if from != to {
+                        log.retitle(&from, &to);
+                    }
+                }
+                continue;
+            }
+            if let Some(event) = Event::parse(event) {
+                log.push(Entry {
+                    time,
+                    event,
+                    title: Item::parse(rest).title,
+                });
+            }
+        }
+        Self {
+            path: Some(path),
+            ..log
+        }
+    }
+
+    // A log of `entries` that isn't saved anywhere.
+    pub fn new(entries: Vec<Entry>) -> Self {
+        let mut log = Self::default();
+        for entry in entries {
+            log.push(entry);
+        }
+        log
+    }
+
+    fn push(&mut self, entry: Entry) {
+        self.completion.set(None);
+        let index = self.entries.len();
+        self.titles
+            .entry(entry.title.clone())
+            .or_default()
+            .push(index);
+        self.entries.push(entry);
+        let entry = &self.entries[index];
+        let times = match entry.event {
+            Event::Added | Event::Undone => &mut self.opened,
+            Event::Done => &mut self.closed,
+            Event::Checked => {
+                let times = self.checked.entry(entry.title.clone()).or_default();
+                times.push(entry.time);
+                return;
+            }
+            Event::Deleted => return,
+        };
+        times.insert(entry.title.clone(), entry.time);
+    }
+
+    // Unix time the item with `title` was last added or moved back to TODO, if the log knows.
+    pub fn opened(&self, title: &str) -> Option<i64> {
+        self.opened.get(title).copied()
+    }
+
+    // Same for when it was last marked DONE.
+    pub fn closed(&self, title: &str) -> Option<i64> {
+        self.closed.get(title).copied()
+    }
+
+    // Unix times the habit with `title` was done, oldest first.
+    pub fn checked(&self, title: &str) -> &[i64] {
+        self.checked.get(title).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn add(&mut self, event: Event, title: &str) -> io::Result<()> {
+        let time = now();
+        self.push(Entry {
+            time,
+            event,
+            title: title.to_string(),
+        });
+        self.append(format_args!("{} {} {}", time, event.name(), title))
+    }
+
+    // The item called `from` is called `to` now, like after its due date or priority changed. The
+    // log goes on as if it had always had that title, it isn't a new item just because of that.
+    pub fn rename(&mut self, from: &str, to: &str) -> io::Result<()> {
+        if from == to || !self.titles.contains_key(from) {
+            return Ok(());
+        }
+        self.retitle(from, to);
+        self.append(format_args!(
+            "{} renamed {} {} {}",
+            now(),
+            from.len(),
+            from,
+            to
+        ))
+    }
+
+    fn retitle(&mut self, from: &str, to: &str) {
+        let Some(indices) = self.titles.remove(from) else {
+            return;
+        };
+        self.completion.set(None);
+        for &index in &indices {
+            self.entries[index].title = to.to_string();
+        }
+        let merged = self.titles.entry(to.to_string()).or_default();
+        merged.extend(indices);
+        merged.sort_unstable();
+        // Should there be an item called `to` already, the later times win like they do in push().
+        for times in [&mut self.opened, &mut self.closed] {
+            if let Some(time) = times.remove(from) {
+                let merged = times.entry(to.to_string()).or_insert(time);
+                *merged = time.max(*merged);
+            }
+        }
+        if let Some(mut times) = self.checked.remove(from) {
+            let merged = self.checked.entry(to.to_string()).or_default();
+            merged.append(&mut times);
+            merged.sort_unstable();
+        }
+    }
+
+    fn append(&self, line: fmt::Arguments) -> io::Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    // How many items were open at `time`. The log may well be younger than the list, so this is
+    // worked back from `open_now` rather than added up from the start.
+    pub fn open_at(&self, time: i64, open_now: usize) -> usize {
+        let later: i64 = self
+            .entries
+            .iter()
+            .filter(|entry| entry.time >= time)
+            .map(|entry| entry.event.change())
+            .sum();
+        (open_now as i64 - later).max(0) as usize
+    }
+
+    // What happened between each two neighbouring `bounds` (unix times, ascending). One pass over
+    // the log, the stats ask for this every frame.
+    pub fn periods(&self, bounds: &[i64], open_now: usize) -> Vec<Period> {
+        let mut periods = vec![Period::default(); bounds.len().saturating_sub(1)];
+        // What changed the number of open items from each bound on, the way open_at() adds it up.
+        let mut later = vec![0; bounds.len()];
+        for entry in &self.entries {
+            let passed = bounds.partition_point(|&bound| bound <= entry.time);
+            let Some(last) = passed.checked_sub(1) else {
+                continue;
+            };
+            later[last] += entry.event.change();
+            if let Some(period) = periods.get_mut(last) {
+                if entry.event.counts_as(Event::Added) {
+                    period.added += 1;
+                }
+                if entry.event.counts_as(Event::Done) {
+                    period.done += 1;
+                }
+            }
+        }
+        for index in (1..later.len()).rev() {
+            later[index - 1] += later[index];
+        }
+        for (period, later) in periods.iter_mut().zip(&later[1..]) {
+            period.open = (open_now as i64 - later).max(0) as usize;
+        }
+        periods
+    }
+
+    // How many times `event` happened on each of the `days` days starting at `first` (unix time of
+    // a midnight).
+    pub fn daily(&self, event: Event, first: i64, days: usize) -> Vec<usize> {
+        let mut counts = vec![0; days];
+        for entry in self
+            .entries
+            .iter()
+            .filter(|entry| entry.event.counts_as(event))
+        {
+            let day = (entry.time - first).div_euclid(DAY);
+            if let Some(count) = usize::try_from(day)
+                .ok()
+                .and_then(|day| counts.get_mut(day))
+            {
+                *count += 1;
+            }
+        }
+        counts
+    }
+
+    // Runs of days with at least one item done. `now` is unix time and `offset` how far local time
+    // is ahead of UTC, days go from midnight to midnight. Until today is over nothing done today
+    // doesn't break the current streak yet.
+    pub fn streak(&self, now: i64, offset: i64) -> Streak {
+        let days: BTreeSet<i64> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.event.counts_as(Event::Done))
+            .map(|entry| (entry.time + offset).div_euclid(DAY))
+            .collect();
+        let mut best = 0;
+        let mut run = 0;
+        let mut previous = None;
+        for &day in &days {
+            run = if previous == Some(day - 1) {
+                run + 1
+            } else {
+                1
+            };
+            best = best.max(run);
+            previous = Some(day);
+        }
+        let today = (now + offset).div_euclid(DAY);
+        let done_today = days.contains(&today);
+        let last = if done_today { today } else { today - 1 };
+        let current = (0..)
+            .take_while(|back| days.contains(&(last - back)))
+            .count();
+        Streak {
+            current,
+            best,
+            done_today,
+        }
+    }
+
+    // The average number of seconds from adding an item to getting it done and how many items
+    // that is over. Items are told apart by their titles, and one that comes back from DONE counts
+    // as added again.
+    pub fn completion_time(&self) -> Option<(i64, usize)> {
+        if let Some(completion) = self.completion.get() {
+            return completion;
+        }
+        let mut total = 0;
+        let mut count = 0;
+        // When the item was opened and not closed yet, the earliest first. Going title by title
+        // through the index is much quicker than keeping track of every title at once.
+        let mut open = VecDeque::new();
+        for indices in self.titles.values() {
+            open.clear();
+            for entry in indices.iter().map(|&index| &self.entries[index]) {
+                match entry.event {
+                    Event::Added | Event::Undone => open.push_back(entry.time),
+                    Event::Done | Event::Deleted => {
+                        let Some(added) = open.pop_front() else {
+                            continue;
+                        };
+                        if entry.event == Event::Done {
+                            total += entry.time - added;
+                            count += 1;
+                        }
+                    }
+                    Event::Checked => {}
+                }
+            }
+        }
+        let completion = (count > 0).then(|| (total / count as i64, count));
+        self.completion.set(Some(completion));
+        completion
+    }
+}
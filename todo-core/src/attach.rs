@@ -0,0 +1,23 @@
+// Files and URLs an item refers to, as `attach:~/specs/login.pdf` words anywhere in its title, as
+// many as it likes. Spaces are written %20 to keep each of them one word.
+pub const KEY: &str = "attach";
+
+pub fn of(item: &str) -> Vec<String> {
+    item.split_whitespace()
+        .filter_map(|word| word.strip_prefix(KEY)?.strip_prefix(':'))
+        .filter(|path| !path.is_empty())
+        .map(|path| path.replace("%20", " "))
+        .collect()
+}
+
+// Goes at the end of the title, before any steps, so it doesn't become part of the last one.
+pub fn add(item: &mut String, path: &str) {
+    let end = item.find(';').unwrap_or(item.len());
+    let title = item[..end].trim_end();
+    let word = format!("{}:{}", KEY, path.replace(' ', "%20"));
+    *item = if title.is_empty() {
+        format!("{}{}", word, &item[end..])
+    } else {
+        format!("{} {}{}", title, word, &item[end..])
+    };
+}
@@ -0,0 +1,43 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::storage::Format;
+
+// Named lists, like `work` and `home`, each in a directory of its own under `dir` (the data dir's
+// `lists`) with the file of their format in it, `lists/work/TODO`. The lists in the current
+// directory stay what they always were and have no name, `.` stands for them where one is needed.
+pub const HERE: &str = ".";
+
+// Names end up as directory names, so they're kept to what's safe in one everywhere.
+pub fn check(name: &str) -> Result<(), String> {
+    let valid = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+    if name.is_empty() || !name.chars().all(valid) {
+        return Err(format!(
+            "`{}` can't be a list name, use letters, digits, - and _",
+            name
+        ));
+    }
+    Ok(())
+}
+
+// Where the list called `name` is kept, or the file in the current directory for HERE.
+pub fn path(dir: &Path, name: &str, format: Format) -> PathBuf {
+    match name {
+        HERE => PathBuf::from(format.file_name()),
+        name => dir.join(name).join(format.file_name()),
+    }
+}
+
+// The named lists there are in `dir`, sorted. A missing `dir` just means there are none yet.
+pub fn names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| check(name).is_ok())
+        .collect();
+    names.sort_unstable();
+    names
+}
@@ -0,0 +1,67 @@
+use std::borrow::Cow;
+
+// Steps of an item are the parts of its title after `;` starting with `[ ]` or `[x]`, like
+// `release 1.2; [x] bump the version; [ ] tag it`. They read like the checklists in Markdown, and
+// `S` can still split them out into items of their own.
+fn step(part: &str) -> Option<(bool, &str)> {
+    let part = part.trim_start();
+    if let Some(text) = part.strip_prefix("[ ]") {
+        return Some((false, text.trim()));
+    }
+    part.strip_prefix("[x]")
+        .or_else(|| part.strip_prefix("[X]"))
+        .map(|text| (true, text.trim()))
+}
+
+pub fn steps(item: &str) -> Vec<(bool, &str)> {
+    // Most items have none, this is checked for every row on the screen.
+    if !item.contains(';') {
+        return Vec::new();
+    }
+    item.split(';').skip(1).filter_map(step).collect()
+}
+
+// How many steps are done out of how many there are.
+pub fn progress(item: &str) -> Option<(usize, usize)> {
+    let steps = steps(item);
+    (!steps.is_empty()).then(|| (steps.iter().filter(|(done, _)| *done).count(), steps.len()))
+}
+
+// The title the way the lists show it, the steps folded into how many of them are done.
+pub fn summary(item: &str) -> Cow<'_, str> {
+    let Some((done, total)) = progress(item) else {
+        return Cow::Borrowed(item);
+    };
+    let rest: Vec<&str> = item
+        .split(';')
+        .enumerate()
+        .filter(|(index, part)| *index == 0 || step(part).is_none())
+        .map(|(_, part)| part.trim())
+        .collect();
+    Cow::Owned(format!("{} {}/{}", rest.join("; "), done, total))
+}
+
+// Ticks step `index` off or back on, leaving the rest of the title exactly as it was.
+pub fn toggle(item: &mut String, index: usize) {
+    let mut count = 0;
+    let parts: Vec<String> = item
+        .split(';')
+        .enumerate()
+        .map(|(position, part)| {
+            let Some((done, _)) = step(part).filter(|_| position > 0) else {
+                return part.to_string();
+            };
+            count += 1;
+            if count != index + 1 {
+                return part.to_string();
+            }
+            let (from, to) = if done {
+                (if part.contains("[x]") { "[x]" } else { "[X]" }, "[ ]")
+            } else {
+                ("[ ]", "[x]")
+            };
+            part.replacen(from, to, 1)
+        })
+        .collect();
+    *item = parts.join(";");
+}
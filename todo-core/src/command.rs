@@ -0,0 +1,175 @@
+use crate::boards;
+use crate::item::Item;
+use crate::sort::Order;
+use crate::status::Status;
+use crate::tags;
+
+// Commands typed after `:` in the command line. Everything here touches many items at once, so
+// they are only carried out after the user confirms the prompt.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    AllDone,
+    AllTodo,
+    PurgeDone,
+    Tag(String),
+    // Adds the items of a template from the config. Nothing is lost by it, so it doesn't ask.
+    Template(String),
+    // Shows only the items matching a filter expression or the saved filter of that name, or
+    // everything again when it's empty. Also carried out right away, it's just a view.
+    Filter(String),
+    // Saves a filter expression under a name, and shows it.
+    SaveFilter(String, String),
+    // Attaches a file or URL to the current item, right away since it's only the one.
+    Attach(String),
+    // Sorts the current panel once, right away too, `u` puts it back.
+    Sort(Order),
+    // Saves the lists and opens the named ones, or the ones in the current directory for `.`,
+    // right away as well, nothing is lost by it.
+    List(String),
+    // Shows the named lists there are to switch to.
+    Lists,
+}
+
+impl Command {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut words = source.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let arg = words.next();
+        let command = match (name, arg) {
+            ("done", None) => Command::AllDone,
+            ("undone", None) => Command::AllTodo,
+            ("purge", None) => Command::PurgeDone,
+            ("tag", Some(tag)) => {
+                let tag = tag.trim_start_matches('#');
+                if tag.is_empty() {
+                    return Err("Tag name can't be empty".to_string());
+                }
+                Command::Tag(tag.to_string())
+            }
+            ("tag", None) => return Err("Usage: tag <name>".to_string()),
+            // Template names may have spaces in them.
+            ("template", Some(arg)) => {
+                let rest: Vec<&str> = words.by_ref().collect();
+                Command::Template([&[arg][..], &rest].concat().join(" "))
+            }
+            ("template", None) => return Err("Usage: template <name>".to_string()),
+            // Paths may have spaces in them too.
+            ("attach", Some(arg)) => {
+                let rest: Vec<&str> = words.by_ref().collect();
+                Command::Attach([&[arg][..], &rest].concat().join(" "))
+            }
+            ("attach", None) => return Err("Usage: attach <path or URL>".to_string()),
+            ("sort", Some(arg)) => {
+                let rest: Vec<&str> = words.by_ref().collect();
+                Command::Sort(Order::parse(&[&[arg][..], &rest].concat().join(" "))?)
+            }
+            ("sort", None) => return Err("Usage: sort <keys>, like sort priority, due".to_string()),
+            ("list", Some(name)) => {
+                if name != boards::HERE {
+                    boards::check(name)?;
+                }
+                Command::List(name.to_string())
+            }
+            ("list", None) => Command::Lists,
+            ("filter", Some("save")) => {
+                let name = words.next();
+                let rest: Vec<&str> = words.by_ref().collect();
+                match name {
+                    Some(name) if !rest.is_empty() => {
+                        Command::SaveFilter(name.to_string(), rest.join(" "))
+                    }
+                    _ => return Err("Usage: filter save <name> <expression>".to_string()),
+                }
+            }
+            ("filter", arg) => {
+                let rest: Vec<&str> = arg.into_iter().chain(words.by_ref()).collect();
+                Command::Filter(rest.join(" "))
+            }
+            ("", _) => return Err("No command".to_string()),
+            _ => return Err(format!("Unknown command `{}`", source.trim())),
+        };
+        if words.next().is_some() {
+            return Err(format!("Too many arguments for `{}`", name));
+        }
+        Ok(command)
+    }
+
+    pub fn describe(
+        &self,
+        todos: &[Item],
+        dones: &[Item],
+        somedays: &[Item],
+        panel: &Status,
+    ) -> String {
+        match self {
+            Command::AllDone => format!("Mark all {} TODO items as DONE?", todos.len()),
+            Command::AllTodo => format!("Move all {} DONE items back to TODO?", dones.len()),
+            Command::PurgeDone => format!("Delete all {} DONE items?", dones.len()),
+            Command::Tag(tag) => {
+                let count = match panel {
+                    Status::Todo => todos.len(),
+                    Status::Done => dones.len(),
+                    Status::Someday => somedays.len(),
+                };
+                format!("Tag all {} visible items with #{}?", count, tag)
+            }
+            Command::Template(name) => format!("Add the items of template {}?", name),
+            Command::Filter(filter) => format!("Show only {}?", filter),
+            Command::SaveFilter(name, filter) => format!("Save {} as filter {}?", filter, name),
+            Command::Attach(path) => format!("Attach {}?", path),
+            Command::Sort(order) => format!("Sort by {}?", order.name()),
+            Command::List(name) => format!("Switch to list {}?", name),
+            Command::Lists => "Show the lists?".to_string(),
+        }
+    }
+
+    pub fn run(
+        self,
+        todos: &mut Vec<Item>,
+        todo_curr: &mut usize,
+        dones: &mut Vec<Item>,
+        done_curr: &mut usize,
+        somedays: &mut Vec<Item>,
+        panel: &Status,
+    ) -> String {
+        match self {
+            Command::AllDone => {
+                let count = todos.len();
+                dones.append(todos);
+                *todo_curr = 0;
+                format!("{} items DONE!", count)
+            }
+            Command::AllTodo => {
+                let count = dones.len();
+                todos.append(dones);
+                *done_curr = 0;
+                format!("{} items back to TODO", count)
+            }
+            Command::PurgeDone => {
+                let count = dones.len();
+                dones.clear();
+                *done_curr = 0;
+                format!("{} items into The Abyss!", count)
+            }
+            Command::Tag(tag) => {
+                let items = match panel {
+                    Status::Todo => todos,
+                    Status::Done => dones,
+                    Status::Someday => somedays,
+                };
+                for item in items.iter_mut() {
+                    tags::set(&mut item.title, &tag, true);
+                }
+                format!("Tagged {} items with #{}", items.len(), tag)
+            }
+            // The templates are in the front-end's config, which is where it's carried out.
+            Command::Template(name) => format!("No template {}", name),
+            Command::Filter(_) | Command::SaveFilter(..) => "Nothing filtered".to_string(),
+            Command::Attach(_) => "Nothing attached".to_string(),
+            // Sorting by when items were added or done takes the activity log the front-end has.
+            Command::Sort(_) => "Nothing sorted".to_string(),
+            // Which file the lists are in is the front-end's business too.
+            Command::List(_) | Command::Lists => "No other lists".to_string(),
+        }
+    }
+}
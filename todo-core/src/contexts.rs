@@ -0,0 +1,21 @@
+use crate::item::Item;
+
+// Contexts are `@words` anywhere in an item's title, where or with what it can be done: `@phone`,
+// `@errands`. They work like tags but an item is meant to be looked at from one context at a time.
+pub fn of(item: &str) -> impl Iterator<Item = &str> {
+    item.split_whitespace()
+        .filter_map(|word| word.strip_prefix('@'))
+        .filter(|context| !context.is_empty())
+}
+
+pub fn has(item: &str, context: &str) -> bool {
+    of(item).any(|other| other == context)
+}
+
+// Every context used in `items`, sorted and without repeats.
+pub fn all<'a>(items: impl IntoIterator<Item = &'a Item>) -> Vec<&'a str> {
+    let mut contexts: Vec<&str> = items.into_iter().flat_map(|item| of(item)).collect();
+    contexts.sort_unstable();
+    contexts.dedup();
+    contexts
+}
@@ -0,0 +1,59 @@
+use crate::activity;
+use crate::date::DateTime;
+use crate::fields;
+use crate::status::Status;
+use crate::storage::Lists;
+use crate::tags;
+
+pub const HEADER: &str = "id,title,status,created,completed,due,tags";
+
+// A field the way RFC 4180 has it, in quotes when it has a comma, a quote or a line break in it.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Every item as a row for spreadsheets, TODO, DONE and SOMEDAY one after the other, numbered from
+// 1 in that order. The title is the item as it is, fields and all. When it was created and
+// completed comes from its `created:` and `completed:` fields, or from the activity log when it
+// has none, in local time `offset` seconds ahead of UTC. Tags are their names without the `#`,
+// separated by spaces, and anything unknown is left empty.
+pub fn export(lists: &Lists, activity: &activity::Log, offset: i64) -> String {
+    let mut out = format!("{}\n", HEADER);
+    let date = |item: &str, key: &str, logged: Option<i64>| {
+        fields::get(item, key)
+            .and_then(DateTime::parse)
+            .or_else(|| logged.map(|time| DateTime::from_unix(time, offset)))
+            .map(DateTime::to_item)
+            .unwrap_or_default()
+    };
+    let items = [
+        (Status::Todo, &lists.todos),
+        (Status::Done, &lists.dones),
+        (Status::Someday, &lists.somedays),
+    ]
+    .into_iter()
+    .flat_map(|(status, list)| list.iter().map(move |item| (status, item)));
+    for (index, (status, item)) in items.enumerate() {
+        let closed = (status == Status::Done)
+            .then(|| activity.closed(item))
+            .flatten();
+        let tags: Vec<&str> = tags::of(item).collect();
+        let row = [
+            (index + 1).to_string(),
+            item.to_string(),
+            status.name().to_string(),
+            date(item, "created", activity.opened(item)),
+            date(item, "completed", closed),
+            item.due.map(DateTime::to_item).unwrap_or_default(),
+            tags.join(" "),
+        ];
+        let row: Vec<String> = row.iter().map(|field| quote(field)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,400 @@
+use std::fmt;
+
+// A moment as it's written in items, `2026-10-20` or `2026-10-20T14:30`, to the minute and without
+// a time zone. There's no time zone database in here, converting from and to unix time takes the
+// local offset from UTC, which the front-end knows how to find out.
+//
+// Fields are in order of significance, so the derived ordering is the chronological one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+const DAY: i64 = 24 * 60 * 60;
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar, after Howard Hinnant's
+// `days_from_civil`.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = year as i64 - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year as i32, month, day)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn number<T: std::str::FromStr>(text: &str, digits: usize) -> Option<T> {
+    if text.len() != digits || !text.bytes().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+impl DateTime {
+    pub fn new(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> Option<Self> {
+        let valid = (1..=12).contains(&month)
+            && (1..=days_in_month(year, month)).contains(&day)
+            && hour < 24
+            && minute < 60;
+        valid.then_some(Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+        })
+    }
+
+    // `YYYY-MM-DD` is midnight at the start of that day.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (date, time) = match text.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (text, None),
+        };
+        let mut parts = date.splitn(3, '-');
+        let year = number(parts.next()?, 4)?;
+        let month = number(parts.next()?, 2)?;
+        let day = number(parts.next()?, 2)?;
+        let (hour, minute) = match time {
+            Some(time) => {
+                let (hour, minute) = time.split_once(':')?;
+                (number(hour, 2)?, number(minute, 2)?)
+            }
+            None => (0, 0),
+        };
+        Self::new(year, month, day, hour, minute)
+    }
+
+    // `offset` is how many seconds local time is ahead of UTC.
+    pub fn from_unix(secs: i64, offset: i64) -> Self {
+        let secs = secs + offset;
+        let (year, month, day) = civil_from_days(secs.div_euclid(DAY));
+        let time = secs.rem_euclid(DAY);
+        Self {
+            year,
+            month,
+            day,
+            hour: (time / 3600) as u32,
+            minute: (time % 3600 / 60) as u32,
+        }
+    }
+
+    pub fn to_unix(self, offset: i64) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * DAY
+            + self.hour as i64 * 3600
+            + self.minute as i64 * 60
+            - offset
+    }
+
+    // 0 is Monday.
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        (days_from_civil(self.year, self.month, self.day) + 3).rem_euclid(7) as u32
+    }
+
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_unix(self.to_unix(0) + days * DAY, 0)
+    }
+
+    // The way it's written in items, the time is left out at midnight.
+    pub fn to_item(self) -> String {
+        let date = format!("{:04}-{:02}-{:02}", self.year, self.month, self.day);
+        if self.hour == 0 && self.minute == 0 {
+            date
+        } else {
+            format!("{}T{:02}:{:02}", date, self.hour, self.minute)
+        }
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute
+        )
+    }
+}
+
+const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+// Full names or at least the first three letters of them.
+fn name_index(names: &[&str], word: &str) -> Option<usize> {
+    names
+        .iter()
+        .position(|name| word.len() >= 3 && name.starts_with(word))
+}
+
+// `17:00`, `9:30`, `9am` or `5:30pm`.
+fn parse_time(word: &str) -> Option<(u32, u32)> {
+    let (word, afternoon) = match word.strip_suffix("am") {
+        Some(word) => (word, Some(false)),
+        None => match word.strip_suffix("pm") {
+            Some(word) => (word, Some(true)),
+            None => (word, None),
+        },
+    };
+    let (hour, minute) = match word.split_once(':') {
+        Some((hour, minute)) => (hour, number(minute, 2)?),
+        None if afternoon.is_some() => (word, 0),
+        None => return None,
+    };
+    if hour.is_empty() || hour.len() > 2 || !hour.bytes().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+    let hour: u32 = hour.parse().ok()?;
+    let hour = match afternoon {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(false) => hour % 12,
+        Some(true) => hour % 12 + 12,
+        None => hour,
+    };
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+fn add_months(date: DateTime, months: i64) -> DateTime {
+    let index = date.year as i64 * 12 + date.month as i64 - 1 + months;
+    let year = index.div_euclid(12) as i32;
+    let month = index.rem_euclid(12) as u32 + 1;
+    DateTime {
+        year,
+        month,
+        day: date.day.min(days_in_month(year, month)),
+        ..date
+    }
+}
+
+// `in 3 days`, `in an hour`, `in 2 months`.
+fn parse_in(count: &str, unit: &str, now: DateTime) -> Option<DateTime> {
+    let count: i64 = match count {
+        "a" | "an" => 1,
+        count => count.parse().ok().filter(|count| *count >= 0)?,
+    };
+    let unit = unit.strip_suffix('s').unwrap_or(unit);
+    let seconds = match unit {
+        "m" | "min" | "minute" => 60,
+        "h" | "hour" => 3600,
+        "d" | "day" => DAY,
+        "w" | "week" => 7 * DAY,
+        "month" => return Some(add_months(now, count)),
+        _ => return None,
+    };
+    Some(DateTime::from_unix(now.to_unix(0) + count * seconds, 0))
+}
+
+// `sep 12`, `12 september` or `sep 12 2027`. Without a year it's the next one to come.
+fn parse_month_day(words: &[&str], now: DateTime) -> Option<DateTime> {
+    let day = |word: &str| -> Option<u32> {
+        let word = word.trim_end_matches(',');
+        let word = ["st", "nd", "rd", "th"]
+            .iter()
+            .find_map(|suffix| word.strip_suffix(suffix))
+            .unwrap_or(word);
+        word.parse().ok()
+    };
+    let (month, day, year) = match words {
+        [first, second, rest @ ..] if rest.len() <= 1 => {
+            let (month, day) = match name_index(&MONTHS, first) {
+                Some(month) => (month, day(second)?),
+                None => (name_index(&MONTHS, second)?, day(first)?),
+            };
+            let year = match rest {
+                [year] => Some(number(year, 4)?),
+                _ => None,
+            };
+            (month as u32 + 1, day, year)
+        }
+        _ => return None,
+    };
+    match year {
+        Some(year) => DateTime::new(year, month, day, 0, 0),
+        None => {
+            let this_year = DateTime::new(now.year, month, day, 0, 0)?;
+            let today = DateTime {
+                hour: 0,
+                minute: 0,
+                ..now
+            };
+            if this_year >= today {
+                Some(this_year)
+            } else {
+                DateTime::new(now.year + 1, month, day, 0, 0)
+            }
+        }
+    }
+}
+
+// The day part of what parse_when() understands, at midnight unless it's a span from now.
+fn parse_day(words: &[&str], now: DateTime) -> Option<DateTime> {
+    let midnight = DateTime {
+        hour: 0,
+        minute: 0,
+        ..now
+    };
+    // Days until the next `weekday`, a week for today's weekday unless `today` counts.
+    let ahead = |weekday: usize, today: bool| {
+        let ahead = (weekday as i64 - now.weekday() as i64).rem_euclid(7);
+        if ahead == 0 && !today {
+            7
+        } else {
+            ahead
+        }
+    };
+    let first = DateTime { day: 1, ..midnight };
+    match words {
+        [word] if DateTime::parse(word).is_some() => DateTime::parse(word),
+        ["today"] => Some(midnight),
+        ["tomorrow"] => Some(midnight.add_days(1)),
+        ["day", "after", "tomorrow"] => Some(midnight.add_days(2)),
+        // There's no point deferring until this morning, a bare weekday that's today is next
+        // week's. `this fri` on a Friday is today.
+        [word] | ["next", word] if name_index(&WEEKDAYS, word).is_some() => {
+            Some(midnight.add_days(ahead(name_index(&WEEKDAYS, word)?, false)))
+        }
+        ["this", word] if name_index(&WEEKDAYS, word).is_some() => {
+            Some(midnight.add_days(ahead(name_index(&WEEKDAYS, word)?, true)))
+        }
+        ["weekend"] | ["this", "weekend"] => {
+            // Sunday is still the weekend.
+            let today = now.weekday() >= 5;
+            Some(midnight.add_days(if today { 0 } else { ahead(5, false) }))
+        }
+        ["next", "week"] => Some(midnight.add_days(ahead(0, false))),
+        ["next", "month"] => Some(add_months(first, 1)),
+        ["next", "year"] => DateTime::new(now.year + 1, 1, 1, 0, 0),
+        ["end", "of", "week"] => Some(midnight.add_days(ahead(6, true))),
+        ["end", "of", "month"] => Some(add_months(first, 1).add_days(-1)),
+        ["in", count, unit] => parse_in(count, unit, now),
+        [word] => parse_span(word).map(|span| DateTime::from_unix(now.to_unix(0) + span, 0)),
+        words => parse_month_day(words, now),
+    }
+}
+
+// A moment as the user types it: a date or date and time the way items have them, `today`,
+// `tomorrow`, `day after tomorrow`, the next weekday (`monday`, `mon`, `next fri` or `this fri`),
+// `weekend`, `next week` (Monday), `next month` and `next year` (the first day of them), `end of
+// week` and `end of month`, a date without the year (`sep 12`), or a span from now like `2h`, `3d`
+// or `in 3 days`. Days start at midnight unless a time of day
+// follows, as in `tomorrow 9am` or `fri at 17:00`. A time of day alone is the next one to come.
+pub fn parse_when(text: &str, now: DateTime) -> Option<DateTime> {
+    let text = text.trim();
+    if let Some(date) = DateTime::parse(text) {
+        return Some(date);
+    }
+    let text = text.to_lowercase();
+    let words: Vec<&str> = text
+        .split_whitespace()
+        .filter(|word| *word != "at")
+        .collect();
+    let (last, rest) = words.split_last()?;
+    let Some((hour, minute)) = parse_time(last) else {
+        return parse_day(&words, now);
+    };
+    if rest.is_empty() {
+        let today = DateTime {
+            hour,
+            minute,
+            ..now
+        };
+        return Some(if today > now {
+            today
+        } else {
+            today.add_days(1)
+        });
+    }
+    Some(DateTime {
+        hour,
+        minute,
+        ..parse_day(rest, now)?
+    })
+}
+
+// A span of time like `30m`, `2h`, `1d` or `1w`, in seconds. A bare `0` is allowed too.
+pub fn parse_span(text: &str) -> Option<i64> {
+    if text == "0" {
+        return Some(0);
+    }
+    let unit = match text.chars().last()? {
+        'm' => 60,
+        'h' => 3600,
+        'd' => DAY,
+        'w' => 7 * DAY,
+        _ => return None,
+    };
+    let count: i64 = text[..text.len() - 1].parse().ok()?;
+    (count >= 0).then_some(count * unit)
+}
+
+// The other way round, rounded down to the two largest units: `1d 2h`, `45m`.
+pub fn format_span(secs: i64) -> String {
+    let units = [("d", DAY), ("h", 3600), ("m", 60)];
+    let parts: Vec<String> = units
+        .iter()
+        .scan(secs.max(0), |rest, (name, size)| {
+            let count = *rest / size;
+            *rest %= size;
+            Some((count, name))
+        })
+        .skip_while(|(count, _)| *count == 0)
+        .take(2)
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, name)| format!("{}{}", count, name))
+        .collect();
+    if parts.is_empty() {
+        "0m".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
@@ -0,0 +1,17 @@
+use crate::date::DateTime;
+use crate::fields;
+
+// Deferred items have `defer:2026-10-20` or `defer:2026-10-20T09:00` in their title and stay out
+// of the way until then, like tickets in a tickler file.
+pub fn until(item: &str) -> Option<DateTime> {
+    fields::get(item, "defer").and_then(DateTime::parse)
+}
+
+pub fn is_deferred(item: &str, now: DateTime) -> bool {
+    until(item).is_some_and(|until| until > now)
+}
+
+// Replaces whatever the item was deferred until, None brings it back right away.
+pub fn set(item: &mut String, until: Option<DateTime>) {
+    fields::set(item, "defer", until.map(DateTime::to_item).as_deref());
+}
@@ -0,0 +1,69 @@
+use crate::date::{format_span, DateTime};
+use crate::item::Item;
+
+const DAY: i64 = 24 * 60 * 60;
+
+// How long until the item is due, the way the lists show it: `today`, `tomorrow`, `3d`, or `2h` and
+// `45m` for the last day of items due at a time. Late ones are negative, `-2d`, and come with true.
+pub fn countdown(item: &Item, now: DateTime) -> Option<(String, bool)> {
+    let due = item.due?;
+    let today = DateTime {
+        hour: 0,
+        minute: 0,
+        ..now
+    };
+    if due.hour == 0 && due.minute == 0 {
+        let days = (due.to_unix(0) - today.to_unix(0)).div_euclid(DAY);
+        return Some(match days {
+            0 => ("today".to_string(), false),
+            1 => ("tomorrow".to_string(), false),
+            days if days < 0 => (format!("{}d", days), true),
+            days => (format!("{}d", days), false),
+        });
+    }
+    let left = due.to_unix(0) - now.to_unix(0);
+    // Just the largest unit, `2h` rather than `2h 30m`.
+    let span = format_span(left.abs());
+    let span = span.split(' ').next().unwrap_or(&span);
+    Some(if left < 0 {
+        (format!("-{}", span), true)
+    } else {
+        (span.to_string(), false)
+    })
+}
+
+// Whether the item is due within a day and isn't late yet, which the lists make stand out.
+pub fn is_soon(item: &Item, now: DateTime) -> bool {
+    item.due_by().is_some_and(|due| {
+        let left = due.to_unix(0) - now.to_unix(0);
+        0 < left && left <= DAY
+    })
+}
+
+pub struct Reminder<'a> {
+    pub item: &'a Item,
+    // Unix time.
+    pub due: i64,
+}
+
+// The items to be reminded of because a reminder `lead` seconds ahead of their due time fell into
+// the window after `since` up to and including `now`. An item shows up once per window even when
+// several of its reminders fell into it, like after waking up from sleep.
+pub fn reminders<'a>(
+    items: &'a [Item],
+    leads: &[i64],
+    since: i64,
+    now: i64,
+    offset: i64,
+) -> Vec<Reminder<'a>> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let due = item.due_by()?.to_unix(offset);
+            leads
+                .iter()
+                .any(|lead| (since + 1..=now).contains(&(due - lead)))
+                .then_some(Reminder { item, due })
+        })
+        .collect()
+}
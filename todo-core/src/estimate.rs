@@ -0,0 +1,17 @@
+use crate::date::parse_span;
+use crate::item::Item;
+
+// Estimates are `~30m` or `~2h` anywhere in an item's title, how long it's expected to take, in
+// seconds.
+pub fn parse(item: &str) -> Option<i64> {
+    // The status bar adds up every item on every frame, most of them have no estimate.
+    if !item.contains('~') {
+        return None;
+    }
+    item.split_whitespace()
+        .find_map(|word| word.strip_prefix('~').and_then(parse_span))
+}
+
+pub fn total<'a>(items: impl IntoIterator<Item = &'a Item>) -> i64 {
+    items.into_iter().filter_map(|item| parse(item)).sum()
+}
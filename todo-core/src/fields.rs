@@ -0,0 +1,72 @@
+use crate::date::{self, DateTime};
+
+// Fields are `key:value` words anywhere in an item's title, like `due:2026-10-20`. They stay part of
+// the title, so the file format doesn't change and any editor can add them by hand.
+pub fn get<'a>(item: &'a str, key: &str) -> Option<&'a str> {
+    words(item).find_map(|word| {
+        word.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix(':'))
+    })
+}
+
+// Replaces every `key:` field with `key:value` at the end, or takes them out when `value` is None.
+pub fn set(item: &mut String, key: &str, value: Option<&str>) {
+    let field = format!("{}:", key);
+    let words: Vec<&str> = words(item)
+        .filter(|word| !word.starts_with(&field))
+        .collect();
+    let mut title = words.join(" ");
+    if let Some(value) = value {
+        if !title.is_empty() {
+            title.push(' ');
+        }
+        title.push_str(&field);
+        title.push_str(value);
+    }
+    *item = title;
+}
+
+// Words are whatever whitespace separates, the same for finding a field as for changing it. An item
+// that gets a field changed comes out with single spaces between its words.
+fn words(item: &str) -> impl Iterator<Item = &str> {
+    item.split_whitespace()
+}
+
+// Fields holding a date or a date and time.
+const DATES: [&str; 3] = ["due", "defer", "sched"];
+
+// Date fields may be typed the way date::parse_when() reads them, and this writes them the way
+// items have them. Words can't have spaces, so `due:next-fri` stands for `next fri`. Fields it
+// can't make sense of are left alone and the first one comes back as the error.
+pub fn resolve_dates(item: &mut String, now: DateTime) -> Result<(), String> {
+    let mut unknown = None;
+    let mut resolved = false;
+    let words: Vec<String> = words(item)
+        .map(|word| {
+            let Some((key, value)) = word.split_once(':') else {
+                return word.to_string();
+            };
+            if !DATES.contains(&key) || value.is_empty() || DateTime::parse(value).is_some() {
+                return word.to_string();
+            }
+            match date::parse_when(&value.replace(['-', '_'], " "), now) {
+                Some(date) => {
+                    resolved = true;
+                    format!("{}:{}", key, date.to_item())
+                }
+                None => {
+                    unknown.get_or_insert_with(|| value.to_string());
+                    word.to_string()
+                }
+            }
+        })
+        .collect();
+    // Left as typed, spaces and all, when there was nothing to resolve.
+    if resolved {
+        *item = words.join(" ");
+    }
+    match unknown {
+        Some(value) => Err(value),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,41 @@
+use std::cmp;
+
+// Fuzzy matching for the Ctrl+P finder: `query` matches an item when its characters are all in the
+// item in the same order, ignoring case, with anything in between. `rlsnts` finds `release notes`.
+// Spaces in the query don't have to be anywhere.
+//
+// How good the match is goes by where the characters land. Ones right after the previous one and
+// ones starting a word count the most, every character skipped in between a little against it, so
+// `rn` puts `release notes` ahead of `return the drill`. None when it doesn't match at all.
+pub fn score(query: &str, item: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut chars = item.chars().flat_map(char::to_lowercase).enumerate();
+    // Where the last match was and the character before the next one to look at.
+    let mut last: Option<usize> = None;
+    let mut before = ' ';
+    for wanted in query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+    {
+        let (at, found_after) = loop {
+            let (at, c) = chars.next()?;
+            let after = before;
+            before = c;
+            if c == wanted {
+                break (at, after);
+            }
+        };
+        score += 1;
+        match last {
+            Some(last) if at == last + 1 => score += 4,
+            Some(last) => score -= cmp::min(at - last - 1, 3) as i32,
+            None => score -= cmp::min(at, 3) as i32,
+        }
+        if !found_after.is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(at);
+    }
+    Some(score)
+}
@@ -0,0 +1,40 @@
+use crate::tags;
+
+// Habits are TODO items tagged `#habit`. Enter checks one off for the day instead of moving it to
+// DONE, it's back to be done again tomorrow.
+pub const TAG: &str = "habit";
+
+const DAY: i64 = 24 * 60 * 60;
+
+pub fn is_habit(item: &str) -> bool {
+    item.contains("#habit") && tags::has(item, TAG)
+}
+
+// Which of the `days` days up to and including the one starting at `today` (unix time of a local
+// midnight) the habit was done on, oldest first. `checked` is when it was done, oldest first.
+pub fn days(checked: &[i64], today: i64, days: usize) -> Vec<bool> {
+    let first = today - (days as i64 - 1) * DAY;
+    let mut done = vec![false; days];
+    for &time in checked.iter().rev() {
+        if time < first {
+            break;
+        }
+        if let Some(day) = done.get_mut(((time - first) / DAY) as usize) {
+            *day = true;
+        }
+    }
+    done
+}
+
+// Days in a row it was done, up to today or yesterday like the streak of the whole list.
+pub fn streak(done: &[bool]) -> usize {
+    let Some((today, before)) = done.split_last() else {
+        return 0;
+    };
+    let before = before.iter().rev().take_while(|done| **done).count();
+    if *today {
+        before + 1
+    } else {
+        before
+    }
+}
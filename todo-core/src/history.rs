@@ -0,0 +1,54 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::paths::data_dir;
+
+// Titles of every item ever added, oldest first. Lots of tasks come back with the exact same
+// wording ("water plants", "weekly report") so this is what the edit field completes from.
+#[derive(Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    pub entries: Vec<String>,
+}
+
+impl History {
+    pub fn load() -> Self {
+        let path = data_dir().map(|dir| dir.join("history"));
+        let mut history = Self {
+            path,
+            entries: Vec::new(),
+        };
+        // History is a nicety, a missing or unreadable file just means no suggestions.
+        if let Some(source) = history
+            .path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+        {
+            for line in source.lines() {
+                history.remember(line);
+            }
+        }
+        history
+    }
+
+    fn remember(&mut self, entry: &str) {
+        self.entries.retain(|x| x != entry);
+        self.entries.push(entry.to_string());
+    }
+
+    pub fn add(&mut self, entry: &str) -> io::Result<()> {
+        if entry.is_empty() {
+            return Ok(());
+        }
+        self.remember(entry);
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
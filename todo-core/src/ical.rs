@@ -0,0 +1,101 @@
+use crate::date::DateTime;
+use crate::priority;
+use crate::status::Status;
+use crate::storage::Lists;
+use crate::tags;
+
+// iCalendar text escapes backslashes, commas, semicolons and line breaks.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Lines longer than 75 bytes go on over the next ones, each starting with a space, without cutting
+// a character in half.
+fn fold(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+// FNV-1a, which unlike std's hasher gives the same UID for the same item in every build.
+fn fnv(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// `20261020` or `20261020T143000`.
+fn ical_date(date: DateTime) -> String {
+    let day = format!("{:04}{:02}{:02}", date.year, date.month, date.day);
+    match (date.hour, date.minute) {
+        (0, 0) => day,
+        (hour, minute) => format!("{}T{:02}{:02}00", day, hour, minute),
+    }
+}
+
+// The items with a due date as a calendar of VTODOs, for calendar apps to subscribe to. The due
+// date is in local time, as it is in the item. The UID goes by the item without its due date, so
+// moving the date or getting the item done changes the one the calendar has rather than adding
+// another. `now` is unix time, for DTSTAMP.
+pub fn export(lists: &Lists, now: i64) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//cli-todo//cli-todo//EN".to_string(),
+    ];
+    let utc = DateTime::from_unix(now, 0);
+    let stamp = format!(
+        "{:04}{:02}{:02}T{:02}{:02}00Z",
+        utc.year, utc.month, utc.day, utc.hour, utc.minute
+    );
+    for (status, list) in [
+        (Status::Todo, &lists.todos),
+        (Status::Done, &lists.dones),
+        (Status::Someday, &lists.somedays),
+    ] {
+        for item in list {
+            let Some(due) = item.due else {
+                continue;
+            };
+            let summary = &item.title;
+            lines.push("BEGIN:VTODO".to_string());
+            lines.push(format!("UID:{:016x}@cli-todo", fnv(summary)));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("SUMMARY:{}", escape(summary)));
+            lines.push(match (due.hour, due.minute) {
+                (0, 0) => format!("DUE;VALUE=DATE:{}", ical_date(due)),
+                _ => format!("DUE:{}", ical_date(due)),
+            });
+            lines.push(match status {
+                Status::Done => "STATUS:COMPLETED".to_string(),
+                Status::Todo | Status::Someday => "STATUS:NEEDS-ACTION".to_string(),
+            });
+            // 1 is the highest, 9 the lowest.
+            if let Some(level) = item.priority {
+                let position = priority::LEVELS.iter().position(|&x| x == level);
+                lines.push(format!("PRIORITY:{}", 1 + 4 * position.unwrap_or(0)));
+            }
+            let tags: Vec<String> = tags::of(item).map(escape).collect();
+            if !tags.is_empty() {
+                lines.push(format!("CATEGORIES:{}", tags.join(",")));
+            }
+            lines.push("END:VTODO".to_string());
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+    let mut out = String::new();
+    for line in lines {
+        fold(&line, &mut out);
+    }
+    out
+}
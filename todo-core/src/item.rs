@@ -0,0 +1,142 @@
+use std::fmt;
+use std::ops::Deref;
+
+use crate::date::DateTime;
+use crate::priority;
+
+// An item of the lists. In the file the due date is a `due:2026-10-20` or `due:2026-10-20T14:30`
+// word anywhere in the line and the priority a `pri:A`, read out of it once when the lists are
+// loaded and written back at the end when they're saved, so nothing in between has to go looking
+// for them.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Item {
+    // The rest of the line, tags and the other fields included. Items deref to it, which is what
+    // searching, tags and the like go by.
+    pub title: String,
+    // Midnight for items due some day without a time, which are due by the end of it.
+    pub due: Option<DateTime>,
+    // One of priority::LEVELS.
+    pub priority: Option<char>,
+}
+
+impl Item {
+    // Reads a line the way it's kept in the file. Every `due:` that is a date and `pri:` that is a
+    // level comes out of the title along with the space before it, the first one of each is what
+    // the item gets. The rest of the line is kept the way it was typed, so saving it again writes
+    // what was read.
+    pub fn parse(line: &str) -> Self {
+        let mut due = None;
+        let mut level = None;
+        let mut title = String::new();
+        // How much of the line is in the title already.
+        let mut copied = 0;
+        let mut start = 0;
+        let space_at = |at: usize| line[at..].chars().next().map_or(0, char::len_utf8);
+        for word in line.split(char::is_whitespace) {
+            let end = start + word.len();
+            let date = word.strip_prefix("due:").and_then(DateTime::parse);
+            let priority = priority::parse(word);
+            if date.is_some() || priority.is_some() {
+                due = due.or(date);
+                level = level.or(priority);
+                // At the start of the line it's the space after it instead.
+                let (from, to) = match line[..start].chars().next_back() {
+                    Some(space) => (start - space.len_utf8(), end),
+                    None => (start, end + space_at(end)),
+                };
+                if from > copied {
+                    title.push_str(&line[copied..from]);
+                }
+                copied = copied.max(to);
+            }
+            start = end + space_at(end);
+        }
+        title.push_str(&line[copied..]);
+        Self {
+            title,
+            due,
+            priority: level,
+        }
+    }
+
+    fn fields(&self) -> impl Iterator<Item = String> {
+        let priority = self.priority.map(|level| format!("pri:{}", level));
+        let due = self.due.map(|due| format!("due:{}", due.to_item()));
+        priority.into_iter().chain(due)
+    }
+
+    // When it has to be done by.
+    pub fn due_by(&self) -> Option<DateTime> {
+        let due = self.due?;
+        Some(if due.hour == 0 && due.minute == 0 {
+            due.add_days(1)
+        } else {
+            due
+        })
+    }
+}
+
+impl From<&str> for Item {
+    fn from(line: &str) -> Self {
+        Self::parse(line)
+    }
+}
+
+impl From<String> for Item {
+    fn from(line: String) -> Self {
+        Self::parse(&line)
+    }
+}
+
+impl Deref for Item {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.title
+    }
+}
+
+// The line it's saved as, the title and then the fields that were read out of it.
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.title)?;
+        let mut first = self.title.is_empty();
+        for field in self.fields() {
+            if !first {
+                f.write_str(" ")?;
+            }
+            f.write_str(&field)?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+// Whether it's saved as `line`.
+impl PartialEq<str> for Item {
+    fn eq(&self, line: &str) -> bool {
+        let Some(mut rest) = line.strip_prefix(self.title.as_str()) else {
+            return false;
+        };
+        let mut first = self.title.is_empty();
+        for field in self.fields() {
+            let after = if first {
+                Some(rest)
+            } else {
+                rest.strip_prefix(' ')
+            };
+            match after.and_then(|after| after.strip_prefix(field.as_str())) {
+                Some(after) => rest = after,
+                None => return false,
+            }
+            first = false;
+        }
+        rest.is_empty()
+    }
+}
+
+impl PartialEq<&str> for Item {
+    fn eq(&self, line: &&str) -> bool {
+        self == *line
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::item::Item;
+use crate::json::Value;
+use crate::status::Status;
+use crate::storage::{self, Format, Lists, Storage};
+
+// The lists as JSON, for programs that would rather not parse a format of their own:
+//
+//     {"version":1,"items":[
+//     {"id":1,"title":"water plants","status":"todo","created":1760500000,"updated":1760500000}
+//     ]}
+//
+// One item to a line, so diffs of it stay readable. The lists have no ids or times of their own,
+// saving goes by the file as it was instead: an item keeps the id and the creation time of the one
+// with the same title, and `updated` is when it last went to another list. Renaming an item makes
+// it a new one, the same as merging sees it. Without the file, the lists come from the `lines`
+// file next to it if there is one, which is how they move over to JSON.
+pub struct Json;
+
+// What save() writes. Files from a later cli-todo are refused rather than half understood.
+pub const VERSION: u64 = 1;
+
+struct Entry {
+    id: u64,
+    title: String,
+    status: Status,
+    created: i64,
+    updated: i64,
+}
+
+fn invalid(file_path: &str, message: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::InvalidData,
+        format!("{}: ERROR: {}", file_path, message),
+    )
+}
+
+fn parse_entry(item: &Value) -> Option<Entry> {
+    let number = |key| match item.get(key)? {
+        Value::Number(number) if number.fract() == 0.0 => Some(*number),
+        _ => None,
+    };
+    let status = item.get("status")?.as_str()?;
+    Some(Entry {
+        id: number("id")? as u64,
+        title: item.get("title")?.as_str()?.to_string(),
+        status: [Status::Todo, Status::Done, Status::Someday]
+            .into_iter()
+            .find(|other| other.name() == status)?,
+        created: number("created")? as i64,
+        updated: number("updated")? as i64,
+    })
+}
+
+fn read(file_path: &str) -> io::Result<Vec<Entry>> {
+    let source = fs::read_to_string(file_path)?;
+    let value = Value::parse(&source).map_err(|error| invalid(file_path, &error))?;
+    match value.get("version") {
+        Some(Value::Number(version)) if *version == VERSION as f64 => {}
+        Some(Value::Number(version)) if *version > VERSION as f64 => {
+            return Err(invalid(
+                file_path,
+                &format!(
+                    "version {} is from a later cli-todo, this one reads up to {}",
+                    version, VERSION
+                ),
+            ))
+        }
+        _ => return Err(invalid(file_path, "no version this cli-todo knows")),
+    }
+    let Some(Value::Array(items)) = value.get("items") else {
+        return Err(invalid(file_path, "no items"));
+    };
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            parse_entry(item)
+                .ok_or_else(|| invalid(file_path, &format!("ill-formed item {}", index + 1)))
+        })
+        .collect()
+}
+
+impl Storage for Json {
+    fn load(&self, file_path: &str, progress: &mut dyn FnMut(u64, u64)) -> io::Result<Lists> {
+        let entries = match read(file_path) {
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                let lines = Path::new(file_path).with_file_name(Format::Lines.file_name());
+                return match storage::load(&lines.to_string_lossy(), progress) {
+                    Err(other) if other.kind() == ErrorKind::NotFound => Err(error),
+                    result => result,
+                };
+            }
+            result => result?,
+        };
+        let mut lists = Lists::default();
+        for entry in entries {
+            match entry.status {
+                Status::Todo => lists.todos.push(Item::parse(&entry.title)),
+                Status::Done => lists.dones.push(Item::parse(&entry.title)),
+                Status::Someday => lists.somedays.push(Item::parse(&entry.title)),
+            }
+        }
+        Ok(lists)
+    }
+
+    fn save(&self, lists: &Lists, file_path: &str) -> io::Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs() as i64);
+        // A file that can't be read any more only costs the ids and times, not the lists.
+        let before = read(file_path).unwrap_or_default();
+        let mut next = before.iter().map(|entry| entry.id).max().unwrap_or(0) + 1;
+        let mut known: HashMap<&str, VecDeque<&Entry>> = HashMap::new();
+        for entry in &before {
+            known.entry(&entry.title).or_default().push_back(entry);
+        }
+        let mut file = File::create(file_path)?;
+        writeln!(file, "{{\"version\":{},\"items\":[", VERSION)?;
+        let items = [
+            (Status::Todo, &lists.todos),
+            (Status::Done, &lists.dones),
+            (Status::Someday, &lists.somedays),
+        ]
+        .into_iter()
+        .flat_map(|(status, list)| list.iter().map(move |item| (status, item)));
+        for (index, (status, item)) in items.enumerate() {
+            let title = item.to_string();
+            let entry = known.get_mut(title.as_str()).and_then(VecDeque::pop_front);
+            let (id, created, updated) = match entry {
+                Some(entry) if entry.status == status => (entry.id, entry.created, entry.updated),
+                Some(entry) => (entry.id, entry.created, now),
+                None => {
+                    next += 1;
+                    (next - 1, now, now)
+                }
+            };
+            let item = Value::object([
+                ("id", Value::Number(id as f64)),
+                ("title", title.as_str().into()),
+                ("status", status.name().into()),
+                ("created", Value::Number(created as f64)),
+                ("updated", Value::Number(updated as f64)),
+            ]);
+            let comma = if index > 0 { "," } else { "" };
+            writeln!(file, "{}{}", comma, item)?;
+        }
+        writeln!(file, "]}}")
+    }
+}
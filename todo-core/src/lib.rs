@@ -0,0 +1,46 @@
+// Everything about TODO lists that doesn't care how they are shown: the item model, reading and
+// writing the file, operations on the lists and the `:` commands. The terminal UI lives in the
+// cli-todo crate on top of this, other front-ends are welcome to do the same.
+pub mod activity;
+pub mod attach;
+pub mod boards;
+pub mod checklist;
+pub mod command;
+pub mod contexts;
+pub mod csv;
+pub mod date;
+pub mod defer;
+pub mod due;
+pub mod estimate;
+pub mod fields;
+pub mod fuzzy;
+pub mod habit;
+pub mod history;
+pub mod ical;
+pub mod item;
+pub mod json;
+pub mod jsonfile;
+pub mod list;
+pub mod markdown;
+pub mod matrix;
+pub mod merge;
+pub mod ops;
+pub mod org;
+pub mod paths;
+pub mod pick;
+pub mod pin;
+pub mod points;
+pub mod pomodoro;
+pub mod priority;
+pub mod projects;
+pub mod query;
+pub mod regex;
+pub mod schedule;
+pub mod search;
+pub mod sort;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod status;
+pub mod storage;
+pub mod tags;
+pub mod todotxt;
@@ -0,0 +1,126 @@
+use std::cmp;
+
+use crate::item::Item;
+
+pub fn drag_up(list: &mut [Item], list_curr: &mut usize) {
+    if *list_curr > 0 {
+        list.swap(*list_curr, *list_curr - 1);
+        *list_curr -= 1;
+    }
+}
+
+pub fn drag_down(list: &mut [Item], list_curr: &mut usize) {
+    if *list_curr + 1 < list.len() {
+        list.swap(*list_curr, *list_curr + 1);
+        *list_curr += 1;
+    }
+}
+
+pub fn up(list_curr: &mut usize) {
+    if *list_curr > 0 {
+        *list_curr -= 1;
+    }
+}
+
+pub fn down(list: &[Item], list_curr: &mut usize) {
+    if *list_curr + 1 < list.len() {
+        *list_curr += 1;
+    }
+}
+
+pub fn page_up(list_curr: &mut usize, page: usize) {
+    *list_curr = list_curr.saturating_sub(page);
+}
+
+pub fn page_down(list: &[Item], list_curr: &mut usize, page: usize) {
+    if !list.is_empty() {
+        *list_curr = cmp::min(*list_curr + page, list.len() - 1);
+    }
+}
+
+pub fn first(list_curr: &mut usize) {
+    if *list_curr > 0 {
+        *list_curr = 0;
+    }
+}
+
+pub fn last(list: &[Item], list_curr: &mut usize) {
+    if !list.is_empty() {
+        *list_curr = list.len() - 1;
+    }
+}
+
+pub fn transfer(list_dst: &mut Vec<Item>, list_src: &mut Vec<Item>, list_src_curr: &mut usize) {
+    if *list_src_curr < list_src.len() {
+        list_dst.push(list_src.remove(*list_src_curr));
+        if *list_src_curr >= list_src.len() && !list_src.is_empty() {
+            *list_src_curr = list_src.len() - 1;
+        }
+    }
+}
+
+pub fn delete(list: &mut Vec<Item>, list_curr: &mut usize) -> Option<Item> {
+    if *list_curr < list.len() {
+        let item = list.remove(*list_curr);
+        if *list_curr >= list.len() && !list.is_empty() {
+            *list_curr = list.len() - 1;
+        }
+        Some(item)
+    } else {
+        None
+    }
+}
+
+pub fn paste(list: &mut Vec<Item>, list_curr: &mut usize, item: Item) {
+    if list.is_empty() {
+        *list_curr = 0;
+    } else {
+        *list_curr += 1;
+    }
+    list.insert(*list_curr, item);
+}
+
+pub fn duplicate(list: &mut Vec<Item>, list_curr: &mut usize) {
+    if let Some(item) = list.get(*list_curr).cloned() {
+        paste(list, list_curr, item);
+    }
+}
+
+// Joins the current item with the next one. The `; ` separator is exactly what split() breaks
+// items apart at, so the two undo each other. The joined item is due when the first one is, or
+// else when the second one is, and the first part keeps that when it's split again.
+pub fn join(list: &mut Vec<Item>, list_curr: &mut usize) {
+    if *list_curr + 1 < list.len() {
+        let next = list.remove(*list_curr + 1);
+        let item = &mut list[*list_curr];
+        item.title = format!("{}; {}", item.title, next.title);
+        item.due = item.due.or(next.due);
+    }
+}
+
+pub fn split(list: &mut Vec<Item>, list_curr: &mut usize) {
+    if let Some(item) = list.get(*list_curr) {
+        let mut parts: Vec<Item> = item
+            .title
+            .split(';')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(Item::parse)
+            .collect();
+        if parts.len() > 1 {
+            parts[0].due = parts[0].due.or(item.due);
+            list.splice(*list_curr..=*list_curr, parts);
+        }
+    }
+}
+
+// Puts the items in `order`, a permutation of their indices, with the cursor staying on the item
+// it was on.
+pub fn reorder(list: &mut Vec<Item>, list_curr: &mut usize, order: Vec<usize>) {
+    *list_curr = order
+        .iter()
+        .position(|&index| index == *list_curr)
+        .unwrap_or(0);
+    let mut items: Vec<Option<Item>> = list.drain(..).map(Some).collect();
+    list.extend(order.into_iter().filter_map(|index| items[index].take()));
+}
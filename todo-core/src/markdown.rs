@@ -0,0 +1,58 @@
+use crate::item::Item;
+use crate::storage::Lists;
+
+// Markdown checklists, the way the lists look on screen: `- [ ] title` for TODO and `- [x] title`
+// for DONE, under a heading per list. SOMEDAY only comes when it has anything in it.
+pub fn export(lists: &Lists) -> String {
+    let mut out = String::new();
+    for (name, mark, list) in [
+        ("TODO", "[ ]", &lists.todos),
+        ("DONE", "[x]", &lists.dones),
+        ("SOMEDAY", "[ ]", &lists.somedays),
+    ] {
+        if name == "SOMEDAY" && list.is_empty() {
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("## {}\n\n", name));
+        for item in list {
+            out.push_str(&format!("- {} {}\n", mark, item));
+        }
+    }
+    out
+}
+
+// The checklist items of any Markdown, in the order they come. `[x]` and `[X]` are DONE, the open
+// ones are TODO unless they're under a SOMEDAY heading. Indented items count the same as the
+// others, everything that isn't a checklist item is skipped.
+pub fn import(source: &str) -> Lists {
+    let mut lists = Lists::default();
+    let mut someday = false;
+    for line in source.lines() {
+        let line = line.trim_start();
+        if let Some(heading) = line.strip_prefix('#') {
+            someday = heading.trim_start_matches('#').trim() == "SOMEDAY";
+            continue;
+        }
+        let Some(rest) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| line.strip_prefix(bullet))
+        else {
+            continue;
+        };
+        if let Some(item) = rest.strip_prefix("[ ] ") {
+            match someday {
+                true => lists.somedays.push(Item::parse(item)),
+                false => lists.todos.push(Item::parse(item)),
+            }
+        } else if let Some(item) = rest
+            .strip_prefix("[x] ")
+            .or_else(|| rest.strip_prefix("[X] "))
+        {
+            lists.dones.push(Item::parse(item));
+        }
+    }
+    lists
+}
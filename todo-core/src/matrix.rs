@@ -0,0 +1,81 @@
+use crate::date::DateTime;
+use crate::item::Item;
+use crate::tags;
+
+// The Eisenhower matrix: what to do first, plan, hand off or drop, by whether items are urgent and
+// whether they're important.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quadrant {
+    DoFirst,
+    Schedule,
+    Delegate,
+    Eliminate,
+}
+
+pub const QUADRANTS: [Quadrant; 4] = [
+    Quadrant::DoFirst,
+    Quadrant::Schedule,
+    Quadrant::Delegate,
+    Quadrant::Eliminate,
+];
+
+// Due within this many days counts as urgent without being tagged so.
+const URGENT_DAYS: i64 = 2;
+
+impl Quadrant {
+    pub fn new(urgent: bool, important: bool) -> Self {
+        match (urgent, important) {
+            (true, true) => Quadrant::DoFirst,
+            (false, true) => Quadrant::Schedule,
+            (true, false) => Quadrant::Delegate,
+            (false, false) => Quadrant::Eliminate,
+        }
+    }
+
+    pub fn urgent(self) -> bool {
+        matches!(self, Quadrant::DoFirst | Quadrant::Delegate)
+    }
+
+    pub fn important(self) -> bool {
+        matches!(self, Quadrant::DoFirst | Quadrant::Schedule)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Quadrant::DoFirst => "Do first",
+            Quadrant::Schedule => "Schedule",
+            Quadrant::Delegate => "Delegate",
+            Quadrant::Eliminate => "Eliminate",
+        }
+    }
+}
+
+// Tagged `#urgent`, or due soon.
+pub fn is_urgent(item: &Item, now: DateTime) -> bool {
+    tags::has(item, "urgent") || is_due_soon(item, now)
+}
+
+fn is_due_soon(item: &Item, now: DateTime) -> bool {
+    item.due_by()
+        .is_some_and(|due| due <= now.add_days(URGENT_DAYS))
+}
+
+pub fn is_important(item: &str) -> bool {
+    tags::has(item, "important")
+}
+
+pub fn quadrant(item: &Item, now: DateTime) -> Quadrant {
+    Quadrant::new(is_urgent(item, now), is_important(item))
+}
+
+// Moves the item into `quadrant` by tagging it. An item due soon is urgent whatever its tags say,
+// so it can't be moved out of the urgent half, and that's what the error is about.
+pub fn move_to(item: &mut Item, quadrant: Quadrant, now: DateTime) -> Result<(), String> {
+    let due_soon = is_due_soon(item, now);
+    if !quadrant.urgent() && due_soon {
+        return Err(format!("{} is due soon, that makes it urgent", item.title));
+    }
+    tags::set(&mut item.title, "urgent", quadrant.urgent() && !due_soon);
+    tags::set(&mut item.title, "important", quadrant.important());
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+
+use crate::item::Item;
+use crate::status::Status;
+use crate::storage::Lists;
+
+// Putting back together two copies of the lists that went their own ways, like the conflict files
+// Syncthing or Dropbox leave next to the real one. Next to each list is `<file>.state` saying when
+// each item last changed and what to, deleted items included, so a merge can tell an item one side
+// deleted from one the other side never had. Items are known by their title, renaming one is
+// deleting the old title and adding the new one. Whichever side changed an item last wins it.
+pub fn state_path(file_path: &str) -> String {
+    format!("{}.state", file_path)
+}
+
+// Copies of `file_path` that sync tools left behind when it changed in two places at once, like
+// Syncthing's `TODO.sync-conflict-20261015-093012-ABCDEFG` or Dropbox's `TODO (Sam's conflicted
+// copy 2026-10-15)`, oldest first as far as the names go. Copies of the state file aren't.
+pub fn conflicts(file_path: &str) -> io::Result<Vec<String>> {
+    let path = Path::new(file_path);
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let syncthing = format!("{}.sync-conflict-", name);
+    let dropbox = format!("{} (", name);
+    let mut conflicts = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let other = entry?.file_name().to_string_lossy().into_owned();
+        let conflict = other.starts_with(&syncthing)
+            || (other.starts_with(&dropbox) && other.contains("conflicted copy"));
+        if conflict && !other.ends_with(".state") {
+            conflicts.push(path.with_file_name(other).to_string_lossy().into_owned());
+        }
+    }
+    conflicts.sort();
+    Ok(conflicts)
+}
+
+// When each title last changed as unix time, and which list it went to, None once it's deleted.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct State {
+    items: HashMap<String, (i64, Option<Status>)>,
+}
+
+fn parse_status(name: &str) -> Option<Option<Status>> {
+    match name {
+        "deleted" => Some(None),
+        _ => [Status::Todo, Status::Done, Status::Someday]
+            .into_iter()
+            .find(|status| status.name() == name)
+            .map(Some),
+    }
+}
+
+// The list each title is in, the first one when it's in several. Items go by the whole line
+// they're saved as, one with another due date counts as another item.
+fn statuses(lists: &Lists) -> HashMap<String, Status> {
+    let mut statuses = HashMap::new();
+    for (status, list) in [
+        (Status::Todo, &lists.todos),
+        (Status::Done, &lists.dones),
+        (Status::Someday, &lists.somedays),
+    ] {
+        for item in list {
+            statuses.entry(item.to_string()).or_insert(status);
+        }
+    }
+    statuses
+}
+
+impl State {
+    // `<unix time> <todo|done|someday|deleted> <title>` per line. A missing file is a list that
+    // was never saved with one, everything in it is as new as the next change.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error),
+        };
+        let mut items = HashMap::new();
+        for (index, line) in source.lines().enumerate() {
+            let mut parts = line.splitn(3, ' ');
+            let entry = (|| {
+                let time = parts.next()?.parse().ok()?;
+                let status = parse_status(parts.next()?)?;
+                Some((parts.next()?.to_string(), (time, status)))
+            })();
+            let Some((title, entry)) = entry else {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}:{}: ERROR: ill-formed state line", path, index + 1),
+                ));
+            };
+            items.insert(title, entry);
+        }
+        Ok(Self { items })
+    }
+
+    // Sorted by title, so the file doesn't change when the state doesn't.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut items: Vec<_> = self.items.iter().collect();
+        items.sort_by(|a, b| a.0.cmp(b.0));
+        let mut file = File::create(path)?;
+        for (title, (time, status)) in items {
+            let name = status.map_or("deleted", Status::name);
+            writeln!(file, "{} {} {}", time, name, title)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, title: &str) -> Option<(i64, Option<Status>)> {
+        self.items.get(title).copied()
+    }
+
+    // Records a decision about `title` made at `time`, like picking one side of a dispute.
+    pub fn set(&mut self, title: &str, time: i64, status: Option<Status>) {
+        self.items.insert(title.to_string(), (time, status));
+    }
+
+    // Stamps everything that changed since the state was last brought up to date with `now`.
+    // Returns whether anything did.
+    pub fn update(&mut self, lists: &Lists, now: i64) -> bool {
+        let statuses = statuses(lists);
+        let mut changed = false;
+        for (title, status) in &statuses {
+            if self.get(title).map(|(_, old)| old) != Some(Some(*status)) {
+                self.items.insert(title.to_string(), (now, Some(*status)));
+                changed = true;
+            }
+        }
+        for (title, entry) in self.items.iter_mut() {
+            if entry.1.is_some() && !statuses.contains_key(title) {
+                *entry = (now, None);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+// Brings the state of `file_path` up to date with what was just saved there.
+pub fn record(file_path: &str, lists: &Lists, now: i64) -> io::Result<()> {
+    let path = state_path(file_path);
+    let mut state = State::load(&path)?;
+    if state.update(lists, now) {
+        state.save(&path)?;
+    }
+    Ok(())
+}
+
+// Everything both sides did, with the later change winning for items both changed. Changes
+// neither state knows about yet count as made `now`. On a tie an item stays rather than goes, and
+// otherwise it's ours. Items keep our order, the ones only they have go in after whatever was
+// before them on their side.
+pub fn merge(
+    ours: &Lists,
+    our_state: &State,
+    theirs: &Lists,
+    their_state: &State,
+    now: i64,
+) -> (Lists, State) {
+    let mut our_state = our_state.clone();
+    our_state.update(ours, now);
+    let mut their_state = their_state.clone();
+    their_state.update(theirs, now);
+
+    let mut state = our_state.clone();
+    for (title, theirs) in their_state.items {
+        let wins = match state.get(&title) {
+            None => true,
+            Some(ours) => theirs.0 > ours.0 || (theirs.0 == ours.0 && ours.1.is_none()),
+        };
+        if wins {
+            state.items.insert(title, theirs);
+        }
+    }
+
+    let mut merged = Lists::default();
+    for (status, ours, theirs, list) in [
+        (Status::Todo, &ours.todos, &theirs.todos, &mut merged.todos),
+        (Status::Done, &ours.dones, &theirs.dones, &mut merged.dones),
+        (
+            Status::Someday,
+            &ours.somedays,
+            &theirs.somedays,
+            &mut merged.somedays,
+        ),
+    ] {
+        let lines = |list: &[Item]| -> Vec<String> {
+            list.iter()
+                .map(ToString::to_string)
+                .filter(|line| state.get(line).and_then(|(_, status)| status) == Some(status))
+                .collect()
+        };
+        let (ours, theirs) = (lines(ours), lines(theirs));
+        let kept: HashSet<&str> = ours.iter().map(String::as_str).collect();
+        // Theirs only, by the item of ours they came after, None for the top.
+        let mut after: HashMap<Option<&str>, Vec<&str>> = HashMap::new();
+        let mut added = HashSet::new();
+        let mut previous = None;
+        for line in &theirs {
+            if kept.contains(line.as_str()) {
+                previous = Some(line.as_str());
+            } else if added.insert(line.as_str()) {
+                after.entry(previous).or_default().push(line);
+            }
+        }
+        list.extend(after.remove(&None).into_iter().flatten().map(Item::parse));
+        for line in &ours {
+            list.push(Item::parse(line));
+            list.extend(
+                after
+                    .remove(&Some(line.as_str()))
+                    .into_iter()
+                    .flatten()
+                    .map(Item::parse),
+            );
+        }
+    }
+    (merged, state)
+}
+
+// Items the sides disagree about, where merge() had to pick one: in different lists, or in a list
+// on one side and deleted on the other. Items only one side ever had aren't, nobody disagrees
+// about those. In our order, then theirs.
+pub fn disputed(
+    ours: &Lists,
+    our_state: &State,
+    theirs: &Lists,
+    their_state: &State,
+) -> Vec<String> {
+    let (our_statuses, their_statuses) = (statuses(ours), statuses(theirs));
+    let deleted = |state: &State, title: &str| matches!(state.get(title), Some((_, None)));
+    let mut disputed = Vec::new();
+    for (title, status) in &our_statuses {
+        let disagree = match their_statuses.get(title) {
+            Some(other) => other != status,
+            None => deleted(their_state, title),
+        };
+        if disagree {
+            disputed.push(title.to_string());
+        }
+    }
+    for title in their_statuses.keys() {
+        if !our_statuses.contains_key(title) && deleted(our_state, title) {
+            disputed.push(title.to_string());
+        }
+    }
+    let mut order = HashMap::new();
+    for item in [ours, theirs]
+        .into_iter()
+        .flat_map(|lists| [&lists.todos, &lists.dones, &lists.somedays])
+        .flatten()
+    {
+        let next = order.len();
+        order.entry(item.to_string()).or_insert(next);
+    }
+    disputed.sort_by_key(|title| order.get(title).copied());
+    disputed
+}
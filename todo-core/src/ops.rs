@@ -0,0 +1,516 @@
+use crate::item::Item;
+use crate::status::Status;
+use crate::storage::Lists;
+
+// Changes to the lists that can be taken back and done again. Keys say what they did as they do it,
+// copying the lists on every key to compare adds up on long ones. Only commands and scripts, which
+// could do anything, are worked out from the difference between the lists before and after.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    Insert {
+        status: Status,
+        at: usize,
+        item: Item,
+    },
+    Delete {
+        status: Status,
+        at: usize,
+        item: Item,
+    },
+    Rename {
+        status: Status,
+        at: usize,
+        from: Item,
+        to: Item,
+    },
+    // One item moved up or down its list.
+    Drag {
+        status: Status,
+        from: usize,
+        to: usize,
+        item: Item,
+    },
+    // One item moved to another list, like Enter moves TODO items to DONE.
+    Transfer {
+        from: (Status, usize),
+        to: (Status, usize),
+        item: Item,
+    },
+    // Anything bigger, like :undone or sorting, as the stretch of each list that changed.
+    Splice(Vec<Change>),
+    // Several of the above one after the other, like deferring an item renames it and moves it to
+    // the end, taken back as one.
+    Batch(Vec<Op>),
+}
+
+// `removed` at `at` in the list of `status` was replaced with `inserted`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub status: Status,
+    pub at: usize,
+    pub removed: Vec<Item>,
+    pub inserted: Vec<Item>,
+}
+
+const STATUSES: [Status; 3] = [Status::Todo, Status::Done, Status::Someday];
+
+fn list(lists: &Lists, status: Status) -> &Vec<Item> {
+    match status {
+        Status::Todo => &lists.todos,
+        Status::Done => &lists.dones,
+        Status::Someday => &lists.somedays,
+    }
+}
+
+fn list_mut(lists: &mut Lists, status: Status) -> &mut Vec<Item> {
+    match status {
+        Status::Todo => &mut lists.todos,
+        Status::Done => &mut lists.dones,
+        Status::Someday => &mut lists.somedays,
+    }
+}
+
+// The stretch of `after` that differs from `before`, between what they start and end with.
+fn change(status: Status, before: &[Item], after: &[Item]) -> Option<Change> {
+    let prefix = before
+        .iter()
+        .zip(after)
+        .take_while(|(before, after)| before == after)
+        .count();
+    let most = before.len().min(after.len()) - prefix;
+    let suffix = before
+        .iter()
+        .rev()
+        .zip(after.iter().rev())
+        .take(most)
+        .take_while(|(before, after)| before == after)
+        .count();
+    let removed = &before[prefix..before.len() - suffix];
+    let inserted = &after[prefix..after.len() - suffix];
+    if removed.is_empty() && inserted.is_empty() {
+        return None;
+    }
+    Some(Change {
+        status,
+        at: prefix,
+        removed: removed.to_vec(),
+        inserted: inserted.to_vec(),
+    })
+}
+
+// One change to one list as the op it most likely was.
+fn single(change: &Change) -> Option<Op> {
+    let Change {
+        status,
+        at,
+        removed,
+        inserted,
+    } = change;
+    let (status, at) = (*status, *at);
+    let op = match (&removed[..], &inserted[..]) {
+        ([], [item]) => Op::Insert {
+            status,
+            at,
+            item: item.clone(),
+        },
+        ([item], []) => Op::Delete {
+            status,
+            at,
+            item: item.clone(),
+        },
+        ([from], [to]) => Op::Rename {
+            status,
+            at,
+            from: from.clone(),
+            to: to.clone(),
+        },
+        (removed, inserted) if removed.len() == inserted.len() => {
+            let last = removed.len() - 1;
+            if removed[0] == inserted[last] && removed[1..] == inserted[..last] {
+                Op::Drag {
+                    status,
+                    from: at,
+                    to: at + last,
+                    item: removed[0].clone(),
+                }
+            } else if removed[last] == inserted[0] && removed[..last] == inserted[1..] {
+                Op::Drag {
+                    status,
+                    from: at + last,
+                    to: at,
+                    item: removed[last].clone(),
+                }
+            } else {
+                return None;
+            }
+        }
+        _ => return None,
+    };
+    Some(op)
+}
+
+// What happened between `before` and `after`, None when nothing did.
+pub fn diff(before: &Lists, after: &Lists) -> Option<Op> {
+    let changes: Vec<Change> = STATUSES
+        .into_iter()
+        .filter_map(|status| change(status, list(before, status), list(after, status)))
+        .collect();
+    let op = match &changes[..] {
+        [] => return None,
+        [change] => single(change),
+        [a, b] => {
+            let (from, to) = if a.inserted.is_empty() {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            match (
+                &from.removed[..],
+                &from.inserted[..],
+                &to.removed[..],
+                &to.inserted[..],
+            ) {
+                ([removed], [], [], [inserted]) if removed == inserted => Some(Op::Transfer {
+                    from: (from.status, from.at),
+                    to: (to.status, to.at),
+                    item: removed.clone(),
+                }),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    Some(op.unwrap_or(Op::Splice(changes)))
+}
+
+// `removed` at `at` in the list of `status` replaced with `inserted`, as the op it most likely was.
+pub fn splice(status: Status, at: usize, removed: &[Item], inserted: &[Item]) -> Option<Op> {
+    let mut change = change(status, removed, inserted)?;
+    change.at += at;
+    Some(single(&change).unwrap_or(Op::Splice(vec![change])))
+}
+
+// `list` of `status` put in `order`, a permutation of its indices like sorting makes. Only the
+// stretch that moves is copied.
+pub fn reorder(status: Status, list: &[Item], order: &[usize]) -> Option<Op> {
+    let moved = |(index, &other): (usize, &usize)| index != other;
+    let first = order.iter().enumerate().position(moved)?;
+    let last = order.iter().enumerate().rposition(moved)?;
+    let inserted: Vec<Item> = order[first..=last]
+        .iter()
+        .map(|&index| list[index].clone())
+        .collect();
+    splice(status, first, &list[first..=last], &inserted)
+}
+
+// `ops` done one after the other as one op, None when they come to nothing. An item added and
+// typed in is one Insert, like it would be undone.
+pub fn batch(ops: Vec<Op>) -> Option<Op> {
+    let mut done: Vec<Op> = Vec::new();
+    for op in ops {
+        let folded = match (done.last(), &op) {
+            (
+                Some(Op::Insert { status, at, item }),
+                Op::Rename {
+                    status: other,
+                    at: there,
+                    from,
+                    to,
+                },
+            ) if (status, at, item) == (other, there, from) => Some(Some(Op::Insert {
+                status: *status,
+                at: *at,
+                item: to.clone(),
+            })),
+            (
+                Some(Op::Insert { status, at, item }),
+                Op::Delete {
+                    status: other,
+                    at: there,
+                    item: deleted,
+                },
+            ) if (status, at, item) == (other, there, deleted) => Some(None),
+            (
+                Some(Op::Rename {
+                    status,
+                    at,
+                    from,
+                    to,
+                }),
+                Op::Rename {
+                    status: other,
+                    at: there,
+                    from: renamed,
+                    to: next,
+                },
+            ) if (status, at, to) == (other, there, renamed) => {
+                Some((from != next).then(|| Op::Rename {
+                    status: *status,
+                    at: *at,
+                    from: from.clone(),
+                    to: next.clone(),
+                }))
+            }
+            _ => None,
+        };
+        match folded {
+            Some(folded) => {
+                done.pop();
+                done.extend(folded);
+            }
+            None => done.push(op),
+        }
+    }
+    match done.len() {
+        0 => None,
+        1 => done.pop(),
+        _ => Some(Op::Batch(done)),
+    }
+}
+
+impl Op {
+    // The op that takes this one back.
+    pub fn inverse(&self) -> Op {
+        match self.clone() {
+            Op::Insert { status, at, item } => Op::Delete { status, at, item },
+            Op::Delete { status, at, item } => Op::Insert { status, at, item },
+            Op::Rename {
+                status,
+                at,
+                from,
+                to,
+            } => Op::Rename {
+                status,
+                at,
+                from: to,
+                to: from,
+            },
+            Op::Drag {
+                status,
+                from,
+                to,
+                item,
+            } => Op::Drag {
+                status,
+                from: to,
+                to: from,
+                item,
+            },
+            Op::Transfer { from, to, item } => Op::Transfer {
+                from: to,
+                to: from,
+                item,
+            },
+            Op::Splice(changes) => Op::Splice(
+                changes
+                    .into_iter()
+                    .map(|change| Change {
+                        removed: change.inserted,
+                        inserted: change.removed,
+                        ..change
+                    })
+                    .collect(),
+            ),
+            Op::Batch(ops) => Op::Batch(ops.iter().rev().map(Op::inverse).collect()),
+        }
+    }
+
+    // Does it again to `lists`. Returns false, leaving them alone, when they aren't the way they
+    // were when it was done, like after the file changed underneath.
+    pub fn apply(&self, lists: &mut Lists) -> bool {
+        match self {
+            Op::Insert { status, at, item } => {
+                let list = list_mut(lists, *status);
+                if *at > list.len() {
+                    return false;
+                }
+                list.insert(*at, item.clone());
+            }
+            Op::Delete { status, at, item } => {
+                let list = list_mut(lists, *status);
+                if list.get(*at) != Some(item) {
+                    return false;
+                }
+                list.remove(*at);
+            }
+            Op::Rename {
+                status,
+                at,
+                from,
+                to,
+            } => match list_mut(lists, *status).get_mut(*at) {
+                Some(item) if item == from => *item = to.clone(),
+                _ => return false,
+            },
+            Op::Drag {
+                status,
+                from,
+                to,
+                item,
+            } => {
+                let list = list_mut(lists, *status);
+                if list.get(*from) != Some(item) || *to >= list.len() {
+                    return false;
+                }
+                let item = list.remove(*from);
+                list.insert(*to, item);
+            }
+            Op::Transfer { from, to, item } => {
+                if list(lists, from.0).get(from.1) != Some(item) || to.1 > list(lists, to.0).len() {
+                    return false;
+                }
+                let item = list_mut(lists, from.0).remove(from.1);
+                list_mut(lists, to.0).insert(to.1, item);
+            }
+            Op::Splice(changes) => {
+                let fits = changes.iter().all(|change| {
+                    list(lists, change.status)
+                        .get(change.at..change.at + change.removed.len())
+                        .is_some_and(|items| items == change.removed)
+                });
+                if !fits {
+                    return false;
+                }
+                for change in changes {
+                    let end = change.at + change.removed.len();
+                    list_mut(lists, change.status)
+                        .splice(change.at..end, change.inserted.iter().cloned());
+                }
+            }
+            Op::Batch(ops) => {
+                for (done, op) in ops.iter().enumerate() {
+                    if !op.apply(lists) {
+                        // What was done of it is taken back, all or nothing like the others.
+                        for op in ops[..done].iter().rev() {
+                            op.inverse().apply(lists);
+                        }
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    // Where the item it's about is once it's done, for putting the cursor on it.
+    pub fn place(&self) -> (Status, usize) {
+        match self {
+            Op::Insert { status, at, .. }
+            | Op::Delete { status, at, .. }
+            | Op::Rename { status, at, .. } => (*status, *at),
+            Op::Drag { status, to, .. } => (*status, *to),
+            Op::Transfer { to, .. } => *to,
+            Op::Splice(changes) => changes
+                .first()
+                .map_or((Status::Todo, 0), |change| (change.status, change.at)),
+            Op::Batch(ops) => ops.last().map_or((Status::Todo, 0), Op::place),
+        }
+    }
+
+    // The items it gave a new title, as the old title and the new one. Items that only got another
+    // due date keep theirs.
+    pub fn renames(&self) -> Vec<(&str, &str)> {
+        let renames: Vec<(&str, &str)> = match self {
+            Op::Rename { from, to, .. } => vec![(&from.title, &to.title)],
+            // A stretch as long as the one it replaced is taken item by item, like :tag makes. Items
+            // still there after, only somewhere else like sorting leaves them, weren't renamed.
+            Op::Splice(changes) => changes
+                .iter()
+                .filter(|change| change.removed.len() == change.inserted.len())
+                .flat_map(|change| {
+                    change
+                        .removed
+                        .iter()
+                        .zip(&change.inserted)
+                        .filter(|(from, to)| {
+                            !change.inserted.contains(from) && !change.removed.contains(to)
+                        })
+                })
+                .map(|(from, to)| (from.title.as_str(), to.title.as_str()))
+                .collect(),
+            Op::Batch(ops) => ops.iter().flat_map(Op::renames).collect(),
+            _ => Vec::new(),
+        };
+        renames
+            .into_iter()
+            .filter(|(from, to)| from != to)
+            .collect()
+    }
+
+    // Calls item `from` `to` wherever it comes up, for when it was renamed behind the history's
+    // back.
+    pub fn rebase(&mut self, from: &Item, to: &Item) {
+        let mut items: Vec<&mut Item> = match self {
+            Op::Insert { item, .. }
+            | Op::Delete { item, .. }
+            | Op::Drag { item, .. }
+            | Op::Transfer { item, .. } => vec![item],
+            Op::Rename { from, to, .. } => vec![from, to],
+            Op::Splice(changes) => changes
+                .iter_mut()
+                .flat_map(|change| change.removed.iter_mut().chain(&mut change.inserted))
+                .collect(),
+            Op::Batch(ops) => {
+                for op in ops {
+                    op.rebase(from, to);
+                }
+                Vec::new()
+            }
+        };
+        for item in items.iter_mut().filter(|item| **item == from) {
+            **item = to.clone();
+        }
+    }
+}
+
+// How many changes back `u` can go.
+const LIMIT: usize = 100;
+
+// The changes made so far and the ones taken back since, to be done again.
+#[derive(Debug, Default)]
+pub struct Undo {
+    done: Vec<Op>,
+    undone: Vec<Op>,
+}
+
+impl Undo {
+    // A new change, which makes the ones taken back impossible to do again.
+    pub fn record(&mut self, op: Op) {
+        if self.done.len() == LIMIT {
+            self.done.remove(0);
+        }
+        self.done.push(op);
+        self.undone.clear();
+    }
+
+    // Takes the last change back, None when there's nothing to. When the lists don't look like
+    // that change left them any more, all of it is forgotten, nothing older would fit either.
+    pub fn undo(&mut self, lists: &mut Lists) -> Option<Result<&Op, String>> {
+        let op = self.done.pop()?;
+        if !op.inverse().apply(lists) {
+            *self = Self::default();
+            return Some(Err("The lists changed since, nothing to undo".to_string()));
+        }
+        self.undone.push(op);
+        Some(Ok(self.undone.last()?))
+    }
+
+    pub fn redo(&mut self, lists: &mut Lists) -> Option<Result<&Op, String>> {
+        let op = self.undone.pop()?;
+        if !op.apply(lists) {
+            *self = Self::default();
+            return Some(Err("The lists changed since, nothing to redo".to_string()));
+        }
+        self.done.push(op);
+        Some(Ok(self.done.last()?))
+    }
+
+    // Item `from` became `to` without a change of its own, like deferred items coming back when
+    // their time comes. Taking back the defer shouldn't fail for it, so the history is told the
+    // item was always called that.
+    pub fn rebase(&mut self, from: &Item, to: &Item) {
+        for op in self.done.iter_mut().chain(&mut self.undone) {
+            op.rebase(from, to);
+        }
+    }
+}
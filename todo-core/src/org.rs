@@ -0,0 +1,189 @@
+use crate::date::DateTime;
+use crate::item::Item;
+use crate::storage::Lists;
+
+// Emacs org-mode, a heading per item with its list as the TODO keyword:
+//
+//     #+TODO: TODO SOMEDAY | DONE
+//
+//     * TODO call mom due:2026-10-20
+//       DEADLINE: <2026-10-20 Tue>
+//     * DONE file taxes
+//
+// The `#+TODO:` line is what makes org see SOMEDAY as a keyword. Items with a due date get it as
+// a DEADLINE as well so they show up in the agenda.
+pub fn export(lists: &Lists) -> String {
+    let mut out = "#+TODO: TODO SOMEDAY | DONE\n\n".to_string();
+    for (keyword, list) in [
+        ("TODO", &lists.todos),
+        ("DONE", &lists.dones),
+        ("SOMEDAY", &lists.somedays),
+    ] {
+        for item in list {
+            out.push_str(&format!("* {} {}\n", keyword, item));
+            if let Some(due) = item.due {
+                out.push_str(&format!("  DEADLINE: <{}>\n", timestamp(due)));
+            }
+        }
+    }
+    out
+}
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+// `2026-10-20 Tue`, with ` 14:30` when it has a time.
+fn timestamp(date: DateTime) -> String {
+    let day = format!(
+        "{:04}-{:02}-{:02} {}",
+        date.year,
+        date.month,
+        date.day,
+        WEEKDAYS[date.weekday() as usize]
+    );
+    match (date.hour, date.minute) {
+        (0, 0) => day,
+        (hour, minute) => format!("{} {:02}:{:02}", day, hour, minute),
+    }
+}
+
+// The date out of the first `<2026-10-20 Tue 14:30>` in `text`, the way items write it.
+fn parse_timestamp(text: &str) -> Option<String> {
+    let inside = text.split_once('<')?.1.split_once('>')?.0;
+    let mut words = inside.split(' ');
+    let mut date = DateTime::parse(words.next()?)?;
+    if let Some((hour, minute)) = words.find_map(|word| word.split_once(':')) {
+        date = DateTime::new(
+            date.year,
+            date.month,
+            date.day,
+            hour.parse().ok()?,
+            minute.parse().ok()?,
+        )?;
+    }
+    Some(date.to_item())
+}
+
+// Org tags, `:work:urgent:` at the end of a heading, as `#work #urgent`.
+fn convert_tags(title: &str) -> String {
+    let (rest, last) = title.rsplit_once(' ').unwrap_or(("", title));
+    let names = last
+        .strip_prefix(':')
+        .and_then(|last| last.strip_suffix(':'))
+        .map(|names| names.split(':').collect::<Vec<_>>());
+    match names {
+        Some(names) if names.iter().all(|name| !name.is_empty()) => {
+            let tags: Vec<String> = names.iter().map(|name| format!("#{}", name)).collect();
+            format!("{} {}", rest.trim_end(), tags.join(" "))
+                .trim_start()
+                .to_string()
+        }
+        _ => title.to_string(),
+    }
+}
+
+// The keywords in `#+TODO:` lines, open ones before `|` and done ones after it, or the last one
+// being done when there's no `|`. Without any, org's own TODO and DONE.
+fn keywords(source: &str) -> Vec<(String, bool)> {
+    let mut keywords = Vec::new();
+    for line in source.lines() {
+        let Some(rest) = line.trim().strip_prefix("#+TODO:") else {
+            continue;
+        };
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        let bar = words.iter().position(|word| *word == "|");
+        for (index, word) in words.iter().enumerate() {
+            let done = match bar {
+                Some(bar) => index > bar,
+                None => index + 1 == words.len(),
+            };
+            if *word != "|" {
+                // `WAIT(w@/!)` is just WAIT, the rest is for org's own shortcuts.
+                let name = word.split('(').next().unwrap_or(word);
+                keywords.push((name.to_string(), done));
+            }
+        }
+    }
+    if keywords.is_empty() {
+        keywords = vec![("TODO".to_string(), false), ("DONE".to_string(), true)];
+    }
+    keywords
+}
+
+// The headings of any org file that have a TODO keyword. Open ones are TODO, or SOMEDAY when
+// that's their keyword, done ones DONE. Everything else is skipped. Org has more to it than items
+// with a title do, and what doesn't make it over comes back as warnings with their line number:
+// headings under other headings become items of their own next to them, and the text under a
+// heading is left out, except for a DEADLINE, which is the item's due date.
+pub fn import(source: &str) -> (Lists, Vec<(usize, String)>) {
+    let keywords = keywords(source);
+    let mut lists = Lists::default();
+    let mut warnings = Vec::new();
+    // The list and index of the item the lines are under, and whether its text was warned about.
+    let mut current: Option<(bool, bool, usize, bool)> = None;
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let stars = line.chars().take_while(|c| *c == '*').count();
+        let heading = (stars > 0)
+            .then(|| line[stars..].strip_prefix(' '))
+            .flatten();
+        if let Some(heading) = heading {
+            current = None;
+            let heading = heading.trim();
+            let (word, title) = heading.split_once(' ').unwrap_or((heading, ""));
+            let Some((keyword, done)) = keywords.iter().find(|(keyword, _)| keyword == word) else {
+                continue;
+            };
+            let title = convert_tags(title.trim());
+            if title.is_empty() {
+                continue;
+            }
+            if stars > 1 {
+                warnings.push((
+                    number,
+                    format!(
+                        "`{}` is under another heading, imported as an item of its own",
+                        title
+                    ),
+                ));
+            }
+            let list = match (done, keyword.as_str()) {
+                (true, _) => &mut lists.dones,
+                (false, "SOMEDAY") => &mut lists.somedays,
+                (false, _) => &mut lists.todos,
+            };
+            list.push(Item::parse(&title));
+            current = Some((*done, keyword == "SOMEDAY", list.len() - 1, false));
+            continue;
+        }
+        let text = line.trim();
+        let Some((done, someday, at, warned)) = current.as_mut() else {
+            continue;
+        };
+        if text.is_empty() || text.starts_with("#+") {
+            continue;
+        }
+        let item = match (*done, *someday) {
+            (true, _) => &mut lists.dones[*at],
+            (false, true) => &mut lists.somedays[*at],
+            (false, false) => &mut lists.todos[*at],
+        };
+        let deadline = text
+            .split_once("DEADLINE:")
+            .and_then(|(_, rest)| parse_timestamp(rest));
+        if let Some(deadline) = &deadline {
+            if item.due.is_none() {
+                item.due = DateTime::parse(deadline);
+            }
+        }
+        // Nothing is lost with a line that's just the DEADLINE, the way export() writes it.
+        let lossless = deadline.is_some()
+            && text.starts_with("DEADLINE:")
+            && !text.contains("SCHEDULED:")
+            && !text.contains("CLOSED:");
+        if !lossless && !*warned {
+            *warned = true;
+            warnings.push((number, format!("the text under `{}` is left out", item)));
+        }
+    }
+    (lists, warnings)
+}
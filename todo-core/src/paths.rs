@@ -0,0 +1,34 @@
+use std::env;
+use std::path::PathBuf;
+
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("cli-todo"));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("cli-todo"))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config"))
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("cli-todo"));
+    }
+    env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".local")
+            .join("share")
+            .join("cli-todo")
+    })
+}
+
+// Where a running cli-todo listens for todo-ctl. The runtime dir is private to the user and
+// cleared on logout, which is just right for a socket.
+pub fn socket_path() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("cli-todo.sock"));
+    }
+    data_dir().map(|dir| dir.join("socket"))
+}
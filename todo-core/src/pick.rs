@@ -0,0 +1,17 @@
+// Picks an index with a chance in proportion to its weight, `roll` being any random number. None
+// when all the weights are zero.
+pub fn weighted(weights: &[u64], roll: u64) -> Option<usize> {
+    let total: u64 = weights.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut left = roll % total;
+    weights.iter().position(|&weight| {
+        if left < weight {
+            true
+        } else {
+            left -= weight;
+            false
+        }
+    })
+}
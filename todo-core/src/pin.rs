@@ -0,0 +1,34 @@
+use crate::item::Item;
+use crate::list;
+use crate::tags;
+
+// Pinned items carry `#pinned` and stay at the top of TODO, whatever gets inserted or dragged
+// above them.
+pub const TAG: &str = "pinned";
+
+pub fn is_pinned(item: &str) -> bool {
+    // Most items have no tags at all, the quick check keeps long lists cheap.
+    item.contains("#pinned") && tags::has(item, TAG)
+}
+
+// Moves the pinned items up above the others, keeping the order within both, with the cursor
+// staying on the item it was on. Returns whether anything moved.
+pub fn float(list: &mut Vec<Item>, curr: &mut usize) -> bool {
+    let Some(order) = order(list) else {
+        return false;
+    };
+    list::reorder(list, curr, order);
+    true
+}
+
+// The order float() puts the list in as a permutation of its indices, None when the pinned items
+// are on top already.
+pub fn order(list: &[Item]) -> Option<Vec<usize>> {
+    let first = list.iter().position(|item| !is_pinned(item))?;
+    if !list[first..].iter().any(|item| is_pinned(item)) {
+        return None;
+    }
+    let mut order: Vec<usize> = (0..list.len()).collect();
+    order.sort_by_key(|&index| !is_pinned(&list[index]));
+    Some(order)
+}
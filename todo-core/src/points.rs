@@ -0,0 +1,55 @@
+use crate::activity::{Entry, Event};
+use crate::estimate;
+use crate::tags;
+
+// What getting things done is worth, all of it made up and only there for the fun of it. Points
+// are worked out from the titles in the activity log, so changing them changes the past too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Points {
+    // For every item done or habit checked off.
+    pub done: i64,
+    // On top of that per hour of its estimate.
+    pub hour: i64,
+    // And for `#important` and `#urgent` ones.
+    pub important: i64,
+    pub urgent: i64,
+}
+
+impl Default for Points {
+    fn default() -> Self {
+        Self {
+            done: 10,
+            hour: 10,
+            important: 10,
+            urgent: 5,
+        }
+    }
+}
+
+impl Points {
+    pub fn score(&self, item: &str) -> i64 {
+        let hours = estimate::parse(item).map_or(0, |secs| secs * self.hour / 3600);
+        let tagged = |tag, points| if tags::has(item, tag) { points } else { 0 };
+        self.done + hours + tagged("important", self.important) + tagged("urgent", self.urgent)
+    }
+
+    pub fn total(&self, entries: &[Entry]) -> i64 {
+        entries
+            .iter()
+            .filter(|entry| matches!(entry.event, Event::Done | Event::Checked))
+            .map(|entry| self.score(&entry.title))
+            .sum()
+    }
+}
+
+// The level `total` points reach, how many points into it they are and how many it takes to get
+// to the next one. Every level takes 100 more than the one before.
+pub fn level(total: i64) -> (i64, i64, i64) {
+    let mut level = 1;
+    let mut rest = total.max(0);
+    while rest >= level * 100 {
+        rest -= level * 100;
+        level += 1;
+    }
+    (level, rest, level * 100)
+}
@@ -0,0 +1,114 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::paths::data_dir;
+
+pub const WORK: Duration = Duration::from_secs(25 * 60);
+pub const BREAK: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    Break,
+}
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Work => "work",
+            Phase::Break => "break",
+        }
+    }
+
+    fn duration(self) -> Duration {
+        match self {
+            Phase::Work => WORK,
+            Phase::Break => BREAK,
+        }
+    }
+}
+
+// A 25 minutes of work, 5 minutes of break cycle on one item, going round until it's stopped.
+// Whoever runs it says how much time went by, so it works with any clock and tests don't have to
+// wait 25 minutes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pomodoro {
+    pub item: String,
+    pub phase: Phase,
+    pub remaining: Duration,
+}
+
+impl Pomodoro {
+    pub fn start(item: &str) -> Self {
+        Self {
+            item: item.to_string(),
+            phase: Phase::Work,
+            remaining: WORK,
+        }
+    }
+
+    // Returns the phase that just ended, if one did.
+    pub fn advance(&mut self, elapsed: Duration) -> Option<Phase> {
+        if elapsed < self.remaining {
+            self.remaining -= elapsed;
+            return None;
+        }
+        let ended = self.phase;
+        self.phase = match ended {
+            Phase::Work => Phase::Break,
+            Phase::Break => Phase::Work,
+        };
+        self.remaining = self.phase.duration();
+        Some(ended)
+    }
+}
+
+// Every finished pomodoro as `<unix time> <title>`, oldest first, so it's easy to see where the
+// time went.
+#[derive(Default)]
+pub struct Log {
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+}
+
+impl Log {
+    pub fn load() -> Self {
+        let path = data_dir().map(|dir| dir.join("pomodoros"));
+        // Like the history, a missing or unreadable log only means the counts start from zero.
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|source| {
+                source
+                    .lines()
+                    .filter_map(|line| line.split_once(' '))
+                    .map(|(_, title)| title.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    // How many pomodoros were finished on `title` so far.
+    pub fn count(&self, title: &str) -> usize {
+        self.entries.iter().filter(|entry| *entry == title).count()
+    }
+
+    pub fn add(&mut self, title: &str) -> io::Result<()> {
+        self.entries.push(title.to_string());
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{} {}", time, title)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::item::Item;
+
+// Priorities are `pri:A`, `pri:B` or `pri:C` in an item's line, A the most important, the same as
+// todo.txt has them. Items without one come after all of those that have one.
+pub const LEVELS: [char; 3] = ['A', 'B', 'C'];
+
+// The level of a `pri:` word, in either case.
+pub fn parse(word: &str) -> Option<char> {
+    let mut chars = word.strip_prefix("pri:")?.chars();
+    let level = chars.next()?.to_ascii_uppercase();
+    (chars.next().is_none() && LEVELS.contains(&level)).then_some(level)
+}
+
+// One level up, C for items without one, A stays A. Returns the new level.
+pub fn raise(item: &mut Item) -> Option<char> {
+    let level = match item.priority {
+        None => 'C',
+        Some(level) => LEVELS[LEVELS.iter().position(|&x| x == level)?.saturating_sub(1)],
+    };
+    item.priority = Some(level);
+    Some(level)
+}
+
+// One level down, C goes to none at all. Returns the new level.
+pub fn lower(item: &mut Item) -> Option<char> {
+    let level = item.priority.and_then(|level| {
+        let index = LEVELS.iter().position(|&x| x == level)?;
+        LEVELS.get(index + 1).copied()
+    });
+    item.priority = level;
+    level
+}
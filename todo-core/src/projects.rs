@@ -0,0 +1,21 @@
+use crate::item::Item;
+
+// Projects are `+words` anywhere in an item's title, the way todo.txt has them: `+garden`,
+// `+release-2`. A `+` followed by anything but a letter isn't one, `+1` stays a vote.
+pub fn of(item: &str) -> impl Iterator<Item = &str> {
+    item.split_whitespace()
+        .filter_map(|word| word.strip_prefix('+'))
+        .filter(|project| project.starts_with(char::is_alphabetic))
+}
+
+pub fn has(item: &str, project: &str) -> bool {
+    of(item).any(|other| other == project)
+}
+
+// Every project in `items`, sorted and without repeats.
+pub fn all<'a>(items: impl IntoIterator<Item = &'a Item>) -> Vec<&'a str> {
+    let mut projects: Vec<&str> = items.into_iter().flat_map(|item| of(item)).collect();
+    projects.sort_unstable();
+    projects.dedup();
+    projects
+}
@@ -0,0 +1,245 @@
+use crate::contexts;
+use crate::date::{self, parse_span, DateTime};
+use crate::fields;
+use crate::item::Item;
+use crate::projects;
+use crate::status::Status;
+use crate::tags;
+
+// Filter expressions like `tag:#work AND status:todo AND due<=+7d`. Terms next to each other have
+// to match all, AND says the same out loud, OR is between groups of those and NOT in front of a
+// term turns it around. There are no parentheses, `a AND b OR c` is `(a AND b) OR c`.
+//
+//     tag:work, #work       tagged #work
+//     @phone, context:phone with the context @phone
+//     +garden, project:garden in the project +garden
+//     status:todo           in TODO (or done, someday)
+//     due<=+7d              due within a week, with <, <=, >, >=, = and due, defer or sched
+//     anything else         in the title, ignoring case
+//
+// Dates are compared against `+3d`, `-1w` or `+2h` from now, `today`, `tomorrow`, or anything
+// date::parse_when() reads with `-` for spaces. Whole days compare by day, so `due<=+0d` is
+// anything due by the end of today.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    // Any of these, each of them with all of its terms matching.
+    any: Vec<Vec<(bool, Term)>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Tag(String),
+    Context(String),
+    Project(String),
+    Status(Status),
+    Date(&'static str, Op, Bound),
+    // Lowercase already.
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    // Seconds from now, or days from the start of today when `day` is set. They go along with the
+    // clock, a saved filter for next week stays about next week.
+    Relative { secs: i64, day: bool },
+    At { at: DateTime, day: bool },
+}
+
+const OPS: [(&str, Op); 5] = [
+    ("<=", Op::LessEqual),
+    (">=", Op::GreaterEqual),
+    ("<", Op::Less),
+    (">", Op::Greater),
+    ("=", Op::Equal),
+];
+
+const DATES: [&str; 3] = ["due", "defer", "sched"];
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn parse_bound(text: &str, now: DateTime) -> Result<Bound, String> {
+    let relative = |secs: i64| Bound::Relative {
+        secs,
+        day: secs % DAY == 0,
+    };
+    match text {
+        "today" => return Ok(relative(0)),
+        "tomorrow" => return Ok(relative(DAY)),
+        _ => {}
+    }
+    if let Some(span) = text.strip_prefix('+').and_then(parse_span) {
+        return Ok(relative(span));
+    }
+    if let Some(span) = text.strip_prefix('-').and_then(parse_span) {
+        return Ok(relative(-span));
+    }
+    let at = match DateTime::parse(text) {
+        Some(at) => Some(at),
+        None => date::parse_when(&text.replace(['-', '_'], " "), now),
+    };
+    match at {
+        Some(at) => Ok(Bound::At {
+            at,
+            day: at.hour == 0 && at.minute == 0,
+        }),
+        None => Err(format!("Can't tell when `{}` is", text)),
+    }
+}
+
+fn parse_term(word: &str, now: DateTime) -> Result<Term, String> {
+    for key in DATES {
+        let Some(rest) = word.strip_prefix(key) else {
+            continue;
+        };
+        if let Some((op, value)) = OPS
+            .iter()
+            .find_map(|(text, op)| rest.strip_prefix(text).map(|value| (*op, value)))
+        {
+            return Ok(Term::Date(key, op, parse_bound(value, now)?));
+        }
+    }
+    if let Some(value) = word.strip_prefix("status:") {
+        return match value {
+            "todo" => Ok(Term::Status(Status::Todo)),
+            "done" => Ok(Term::Status(Status::Done)),
+            "someday" => Ok(Term::Status(Status::Someday)),
+            _ => Err(format!(
+                "Unknown status `{}`, expected todo, done or someday",
+                value
+            )),
+        };
+    }
+    let tag = word.strip_prefix("tag:").or_else(|| word.strip_prefix('#'));
+    if let Some(tag) = tag.map(|tag| tag.trim_start_matches('#')) {
+        if !tag.is_empty() {
+            return Ok(Term::Tag(tag.to_string()));
+        }
+    }
+    let context = word
+        .strip_prefix("context:")
+        .or_else(|| word.strip_prefix('@'));
+    if let Some(context) = context.map(|context| context.trim_start_matches('@')) {
+        if !context.is_empty() {
+            return Ok(Term::Context(context.to_string()));
+        }
+    }
+    let project = word
+        .strip_prefix("project:")
+        .or_else(|| word.strip_prefix('+'));
+    if let Some(project) = project.map(|project| project.trim_start_matches('+')) {
+        if project.starts_with(char::is_alphabetic) {
+            return Ok(Term::Project(project.to_string()));
+        }
+    }
+    Ok(Term::Text(word.to_lowercase()))
+}
+
+impl Query {
+    // `now` is only for reading dates like `next-fri` that aren't relative to it anyway.
+    pub fn parse(source: &str, now: DateTime) -> Result<Self, String> {
+        let mut any = vec![Vec::new()];
+        let mut negated = false;
+        // Whether the last word was AND, OR or NOT, which need a term after them.
+        let mut dangling = None;
+        for word in source.split_whitespace() {
+            match word {
+                "AND" | "and" | "OR" | "or" if dangling.is_some() => {
+                    return Err(format!(
+                        "Nothing between {} and {}",
+                        dangling.unwrap_or(""),
+                        word
+                    ));
+                }
+                "AND" | "and" => dangling = Some(word),
+                "OR" | "or" => {
+                    if any.last().is_some_and(Vec::is_empty) {
+                        return Err(format!("Nothing before {}", word));
+                    }
+                    any.push(Vec::new());
+                    dangling = Some(word);
+                }
+                "NOT" | "not" => {
+                    negated = !negated;
+                    dangling = Some(word);
+                }
+                word => {
+                    let term = parse_term(word, now)?;
+                    if let Some(all) = any.last_mut() {
+                        all.push((negated, term));
+                    }
+                    negated = false;
+                    dangling = None;
+                }
+            }
+        }
+        if let Some(word) = dangling {
+            return Err(format!("Nothing after {}", word));
+        }
+        if any.iter().all(Vec::is_empty) {
+            return Err("Empty filter".to_string());
+        }
+        Ok(Self { any })
+    }
+
+    pub fn matches(&self, item: &Item, status: Status, now: DateTime) -> bool {
+        self.any.iter().any(|all| {
+            all.iter()
+                .all(|(negated, term)| term_matches(term, item, status, now) != *negated)
+        })
+    }
+}
+
+fn term_matches(term: &Term, item: &Item, status: Status, now: DateTime) -> bool {
+    match term {
+        Term::Tag(tag) => tags::has(item, tag),
+        Term::Context(context) => contexts::has(item, context),
+        Term::Project(project) => projects::has(item, project),
+        Term::Status(other) => status == *other,
+        Term::Text(text) => item.to_lowercase().contains(text),
+        Term::Date(key, op, bound) => {
+            let value = match *key {
+                "due" => item.due,
+                key => fields::get(item, key).and_then(DateTime::parse),
+            };
+            let Some(value) = value else {
+                return false;
+            };
+            let (at, day) = match *bound {
+                Bound::Relative { secs, day: true } => (
+                    DateTime {
+                        hour: 0,
+                        minute: 0,
+                        ..now
+                    }
+                    .add_days(secs / DAY),
+                    true,
+                ),
+                Bound::Relative { secs, day: false } => {
+                    (DateTime::from_unix(now.to_unix(0) + secs, 0), false)
+                }
+                Bound::At { at, day } => (at, day),
+            };
+            // The start of the day after, for comparing whole days.
+            let next = if day { at.add_days(1) } else { at };
+            match op {
+                Op::Less => value < at,
+                Op::LessEqual if day => value < next,
+                Op::LessEqual => value <= at,
+                Op::Greater if day => value >= next,
+                Op::Greater => value > at,
+                Op::GreaterEqual => value >= at,
+                Op::Equal if day => at <= value && value < next,
+                Op::Equal => value == at,
+            }
+        }
+    }
+}
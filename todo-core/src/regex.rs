@@ -0,0 +1,398 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::mem;
+use std::str::Chars;
+
+// Just enough regular expressions for searching titles: literals, `.`, classes like `[a-z]` and
+// `[^0-9]`, `\d`, `\w` and `\s` (and `\D`, `\W`, `\S` for the opposite), `^` and `$`, `*`, `+` and
+// `?`, groups and `|`. Patterns compile to a small program that's run on every way of matching at
+// once, Thompson's construction and Pike's VM, so matching takes time in proportion to the title
+// times the pattern whatever the pattern. A backtracker is shorter but `(a*)*b` on a line of `a`s
+// takes it longer than anyone waits, and the search runs on Enter with no way to stop it.
+#[derive(Clone, PartialEq)]
+pub struct Regex {
+    source: String,
+    program: Vec<Inst>,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Char(char),
+    Any,
+    Class { negated: bool, items: Vec<Item> },
+    Start,
+    End,
+    Group(Vec<Vec<Node>>),
+    Repeat(Box<Node>, usize, Option<usize>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Range(char, char),
+    Digit,
+    Word,
+    Space,
+}
+
+// `c` in lowercase, for comparing without caring about case.
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+impl Item {
+    fn matches(&self, c: char, ignore_case: bool) -> bool {
+        self.matches_exactly(c)
+            || (ignore_case
+                && (self.matches_exactly(fold(c))
+                    || self.matches_exactly(c.to_uppercase().next().unwrap_or(c))))
+    }
+
+    fn matches_exactly(&self, c: char) -> bool {
+        match *self {
+            Item::Range(from, to) => from <= c && c <= to,
+            Item::Digit => c.is_ascii_digit(),
+            Item::Word => c.is_alphanumeric() || c == '_',
+            Item::Space => c.is_whitespace(),
+        }
+    }
+}
+
+// Groups in groups deeper than this are not something anyone types, and recursing without a limit
+// lets a pattern blow the stack.
+const MAX_DEPTH: usize = 32;
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.chars.next_if_eq(&'|').is_some() {
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        while let Some(&c) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            self.chars.next();
+            let node = match c {
+                '*' | '+' | '?' => {
+                    let Some(last) = nodes.pop() else {
+                        return Err(format!("Nothing to repeat before `{}`", c));
+                    };
+                    let (min, max) = match c {
+                        '*' => (0, None),
+                        '+' => (1, None),
+                        _ => (0, Some(1)),
+                    };
+                    Node::Repeat(Box::new(last), min, max)
+                }
+                '(' => {
+                    if self.depth == MAX_DEPTH {
+                        return Err("Too many groups in groups".to_string());
+                    }
+                    self.depth += 1;
+                    let alternatives = self.alternatives()?;
+                    self.depth -= 1;
+                    if self.chars.next() != Some(')') {
+                        return Err("Missing `)`".to_string());
+                    }
+                    Node::Group(alternatives)
+                }
+                '[' => self.class()?,
+                '.' => Node::Any,
+                '^' => Node::Start,
+                '$' => Node::End,
+                '\\' => self.escape()?,
+                c => Node::Char(c),
+            };
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let class = |negated, item| Node::Class {
+            negated,
+            items: vec![item],
+        };
+        Ok(match self.chars.next() {
+            Some('d') => class(false, Item::Digit),
+            Some('D') => class(true, Item::Digit),
+            Some('w') => class(false, Item::Word),
+            Some('W') => class(true, Item::Word),
+            Some('s') => class(false, Item::Space),
+            Some('S') => class(true, Item::Space),
+            Some('t') => Node::Char('\t'),
+            Some(c) => Node::Char(c),
+            None => return Err("Nothing after `\\`".to_string()),
+        })
+    }
+
+    // After the `[`. A `]` right at the start is a `]` to match, like everywhere else.
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.chars.next_if_eq(&'^').is_some();
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let c = match self.chars.next() {
+                None => return Err("Missing `]`".to_string()),
+                Some(']') if !first => break,
+                Some('\\') => {
+                    let item = match self.chars.next() {
+                        Some('d') => Item::Digit,
+                        Some('w') => Item::Word,
+                        Some('s') => Item::Space,
+                        Some('t') => Item::Range('\t', '\t'),
+                        Some(c) => Item::Range(c, c),
+                        None => return Err("Nothing after `\\`".to_string()),
+                    };
+                    items.push(item);
+                    first = false;
+                    continue;
+                }
+                Some(c) => c,
+            };
+            first = false;
+            let range = self.chars.peek() == Some(&'-') && {
+                let mut ahead = self.chars.clone();
+                ahead.next();
+                ahead.peek().is_some_and(|&next| next != ']')
+            };
+            if range {
+                self.chars.next();
+                let to = self.chars.next().unwrap_or(c);
+                if to < c {
+                    return Err(format!("Range `{}-{}` is backwards", c, to));
+                }
+                items.push(Item::Range(c, to));
+            } else {
+                items.push(Item::Range(c, c));
+            }
+        }
+        Ok(Node::Class { negated, items })
+    }
+}
+
+impl Regex {
+    pub fn new(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: source.chars().peekable(),
+            depth: 0,
+        };
+        let alternatives = parser.alternatives()?;
+        if parser.chars.next().is_some() {
+            return Err("Unmatched `)`".to_string());
+        }
+        let mut program = Vec::new();
+        compile_alternatives(&alternatives, &mut program);
+        program.push(Inst::Match);
+        Ok(Self {
+            source: source.to_string(),
+            program,
+            ignore_case: false,
+        })
+    }
+
+    // Makes `a` match `A` and the other way round, in classes too.
+    pub fn ignore_case(self) -> Self {
+        Self {
+            ignore_case: true,
+            ..self
+        }
+    }
+
+    // Steps through `text` a character at a time with every thread of the program that's still
+    // going, starting a new one at every character since the match can start anywhere.
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let mut threads = Threads::new(self.program.len());
+        let mut current = Vec::new();
+        let mut next = Vec::new();
+        for at in 0..=text.len() {
+            if threads.add(&self.program, &mut current, 0, at, text.len()) {
+                return true;
+            }
+            let Some(&c) = text.get(at) else {
+                break;
+            };
+            for &pc in &current {
+                let step = match &self.program[pc] {
+                    Inst::Char(other) => {
+                        c == *other || (self.ignore_case && fold(c) == fold(*other))
+                    }
+                    Inst::Any => true,
+                    Inst::Class { negated, items } => {
+                        items.iter().any(|item| item.matches(c, self.ignore_case)) != *negated
+                    }
+                    _ => false,
+                };
+                if step && threads.add(&self.program, &mut next, pc + 1, at + 1, text.len()) {
+                    return true;
+                }
+            }
+            current.clear();
+            mem::swap(&mut current, &mut next);
+        }
+        false
+    }
+}
+
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl fmt::Debug for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Regex({:?})", self.source)
+    }
+}
+
+// What the pattern compiles to. Split goes on at both places, which is how `|`, `*`, `+` and `?`
+// come out. Start and End only let a thread on at the start and end of the text.
+#[derive(Debug, Clone, PartialEq)]
+enum Inst {
+    Char(char),
+    Any,
+    Class { negated: bool, items: Vec<Item> },
+    Start,
+    End,
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+fn compile_alternatives(alternatives: &[Vec<Node>], program: &mut Vec<Inst>) {
+    let mut jumps = Vec::new();
+    for (index, nodes) in alternatives.iter().enumerate() {
+        if index + 1 == alternatives.len() {
+            compile_sequence(nodes, program);
+            break;
+        }
+        let split = program.len();
+        program.push(Inst::Split(split + 1, 0));
+        compile_sequence(nodes, program);
+        jumps.push(program.len());
+        program.push(Inst::Jump(0));
+        program[split] = Inst::Split(split + 1, program.len());
+    }
+    let end = program.len();
+    for jump in jumps {
+        program[jump] = Inst::Jump(end);
+    }
+}
+
+fn compile_sequence(nodes: &[Node], program: &mut Vec<Inst>) {
+    for node in nodes {
+        compile_node(node, program);
+    }
+}
+
+fn compile_node(node: &Node, program: &mut Vec<Inst>) {
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class { negated, items } => program.push(Inst::Class {
+            negated: *negated,
+            items: items.clone(),
+        }),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::Group(alternatives) => compile_alternatives(alternatives, program),
+        Node::Repeat(node, min, max) => {
+            for _ in 0..*min {
+                compile_node(node, program);
+            }
+            match max {
+                // Once more and back round for as long as it goes.
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile_node(node, program);
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(program.len() + 1, 0));
+                        compile_node(node, program);
+                    }
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, program.len());
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The threads of the program at one place in the text, one per instruction at most.
+struct Threads {
+    // The place in the text each instruction last got a thread at.
+    seen: Vec<Option<usize>>,
+    stack: Vec<usize>,
+}
+
+impl Threads {
+    fn new(size: usize) -> Self {
+        Self {
+            seen: vec![None; size],
+            stack: Vec::new(),
+        }
+    }
+
+    // Adds a thread at `pc` to `list`, for the character at `at`, following splits, jumps and
+    // anchors right away so `list` only has the instructions that read a character. Returns
+    // whether one of them got to Match.
+    fn add(
+        &mut self,
+        program: &[Inst],
+        list: &mut Vec<usize>,
+        pc: usize,
+        at: usize,
+        len: usize,
+    ) -> bool {
+        self.stack.push(pc);
+        while let Some(pc) = self.stack.pop() {
+            if self.seen[pc] == Some(at) {
+                continue;
+            }
+            self.seen[pc] = Some(at);
+            match program[pc] {
+                Inst::Match => {
+                    self.stack.clear();
+                    return true;
+                }
+                Inst::Jump(to) => self.stack.push(to),
+                Inst::Split(first, second) => {
+                    self.stack.push(second);
+                    self.stack.push(first);
+                }
+                Inst::Start => {
+                    if at == 0 {
+                        self.stack.push(pc + 1);
+                    }
+                }
+                Inst::End => {
+                    if at == len {
+                        self.stack.push(pc + 1);
+                    }
+                }
+                Inst::Char(_) | Inst::Any | Inst::Class { .. } => list.push(pc),
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,25 @@
+use crate::date::DateTime;
+use crate::fields;
+use crate::item::Item;
+
+// Items picked for a day have `sched:2026-10-15`, which is when to work on them as opposed to when
+// they're due.
+pub fn scheduled(item: &str) -> Option<DateTime> {
+    fields::get(item, "sched").and_then(DateTime::parse)
+}
+
+pub fn set(item: &mut String, day: Option<DateTime>) {
+    fields::set(item, "sched", day.map(DateTime::to_item).as_deref());
+}
+
+// What's on for today: due by the end of it or scheduled for it, late ones included.
+pub fn is_today(item: &Item, now: DateTime) -> bool {
+    let tomorrow = DateTime {
+        hour: 0,
+        minute: 0,
+        ..now
+    }
+    .add_days(1);
+    item.due_by().is_some_and(|due| due <= tomorrow)
+        || scheduled(item).is_some_and(|day| day < tomorrow)
+}
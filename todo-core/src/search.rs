@@ -0,0 +1,76 @@
+use crate::regex::Regex;
+
+// Whether searches care about case. Smart is what vim users know from `ignorecase` with
+// `smartcase`: only patterns with capitals in them do.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Case {
+    #[default]
+    Smart,
+    Ignore,
+    Sensitive,
+}
+
+impl Case {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "smart" => Some(Case::Smart),
+            "ignore" => Some(Case::Ignore),
+            "sensitive" => Some(Case::Sensitive),
+            _ => None,
+        }
+    }
+
+    // Whether `pattern` is looked for as it is. Escapes like `\W` aren't capitals.
+    fn sensitive(self, pattern: &str) -> bool {
+        match self {
+            Case::Ignore => false,
+            Case::Sensitive => true,
+            Case::Smart => {
+                let mut escaped = false;
+                pattern.chars().any(|c| {
+                    let capital = !escaped && c.is_uppercase();
+                    escaped = !escaped && c == '\\';
+                    capital
+                })
+            }
+        }
+    }
+}
+
+// What `/` and `todo-ctl list --search` look for in titles. Plain text unless it starts with `\v`,
+// which makes the rest a regular expression the way it does in vim.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Search {
+    Text(String),
+    // Lowercase already.
+    IgnoreCase(String),
+    Regex(Regex),
+}
+
+impl Search {
+    pub fn parse(pattern: &str, case: Case) -> Result<Self, String> {
+        let sensitive = case.sensitive(pattern);
+        match pattern.strip_prefix("\\v") {
+            Some(source) => Regex::new(source)
+                .map(|regex| {
+                    Search::Regex(if sensitive {
+                        regex
+                    } else {
+                        regex.ignore_case()
+                    })
+                })
+                .map_err(|error| format!("{} in the pattern `{}`", error, source)),
+            None if pattern.is_empty() => Err("Nothing to search for".to_string()),
+            None if sensitive => Ok(Search::Text(pattern.to_string())),
+            None => Ok(Search::IgnoreCase(pattern.to_lowercase())),
+        }
+    }
+
+    pub fn matches(&self, item: &str) -> bool {
+        match self {
+            Search::Text(text) => item.contains(text.as_str()),
+            Search::IgnoreCase(text) => item.to_lowercase().contains(text.as_str()),
+            Search::Regex(regex) => regex.is_match(item),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::cmp::Ordering;
+
+use crate::activity;
+use crate::date::DateTime;
+use crate::defer;
+use crate::estimate;
+use crate::item::Item;
+use crate::list;
+use crate::matrix::{self, QUADRANTS};
+use crate::schedule;
+
+// What a list can be kept sorted by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Due,
+    Sched,
+    Defer,
+    Estimate,
+    // Eisenhower quadrant, do first before schedule before delegate before eliminate.
+    Quadrant,
+    // A before B before C.
+    Priority,
+    Title,
+    // When it was added and when it was done, from the activity log.
+    Added,
+    Completed,
+}
+
+pub const KEYS: [(&str, Key); 9] = [
+    ("due", Key::Due),
+    ("sched", Key::Sched),
+    ("defer", Key::Defer),
+    ("estimate", Key::Estimate),
+    ("quadrant", Key::Quadrant),
+    ("priority", Key::Priority),
+    ("title", Key::Title),
+    ("added", Key::Added),
+    ("completed", Key::Completed),
+];
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    Number(i64),
+    Text(String),
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+// Keys to sort by one after the other, `due, quadrant` sorts by quadrant what's due at the same
+// time. Each may be `desc` for the other way round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Order {
+    keys: Vec<(Key, bool)>,
+}
+
+impl Order {
+    // Reads `due, estimate desc`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let keys = text
+            .split(',')
+            .map(|part| {
+                let mut words = part.split_whitespace();
+                let name = words.next().unwrap_or("");
+                let key = KEYS
+                    .iter()
+                    .find(|(other, _)| *other == name)
+                    .map(|(_, key)| *key)
+                    .ok_or_else(|| {
+                        let names: Vec<&str> = KEYS.iter().map(|(name, _)| *name).collect();
+                        format!(
+                            "unknown sort key `{}`, expected one of {}",
+                            name,
+                            names.join(", ")
+                        )
+                    })?;
+                let descending = match (words.next(), words.next()) {
+                    (None, _) | (Some("asc"), None) => false,
+                    (Some("desc"), None) => true,
+                    _ => return Err(format!("expected `{} asc` or `{} desc`", name, name)),
+                };
+                Ok((key, descending))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { keys })
+    }
+
+    pub fn name(&self) -> String {
+        let keys: Vec<String> = self
+            .keys
+            .iter()
+            .map(|&(key, descending)| {
+                let name = KEYS
+                    .iter()
+                    .find(|(_, other)| *other == key)
+                    .map_or("", |(name, _)| name);
+                if descending {
+                    format!("{} desc", name)
+                } else {
+                    name.to_string()
+                }
+            })
+            .collect();
+        keys.join(", ")
+    }
+
+    // Sorts the list, keeping items that compare equal in the order they were in and the cursor
+    // on the same item. Items without a value for a key come after those with one, whichever way
+    // it goes. Returns whether anything moved.
+    pub fn apply(
+        &self,
+        list: &mut Vec<Item>,
+        curr: &mut usize,
+        activity: &activity::Log,
+        now: DateTime,
+    ) -> bool {
+        let Some(order) = self.order(list, activity, now) else {
+            return false;
+        };
+        list::reorder(list, curr, order);
+        true
+    }
+
+    // The order apply() puts the list in as a permutation of its indices, None when it's in order
+    // already.
+    pub fn order(
+        &self,
+        list: &[Item],
+        activity: &activity::Log,
+        now: DateTime,
+    ) -> Option<Vec<usize>> {
+        let values: Vec<Vec<Option<Value>>> = list
+            .iter()
+            .map(|item| {
+                self.keys
+                    .iter()
+                    .map(|&(key, _)| value(key, item, activity, now))
+                    .collect()
+            })
+            .collect();
+        let compare = |a: usize, b: usize| {
+            for (index, &(_, descending)) in self.keys.iter().enumerate() {
+                let ordering = match (&values[a][index], &values[b][index]) {
+                    (Some(a), Some(b)) if descending => b.partial_cmp(a),
+                    (Some(a), Some(b)) => a.partial_cmp(b),
+                    (Some(_), None) => Some(Ordering::Less),
+                    (None, Some(_)) => Some(Ordering::Greater),
+                    (None, None) => None,
+                };
+                match ordering {
+                    Some(Ordering::Equal) | None => continue,
+                    Some(ordering) => return ordering,
+                }
+            }
+            Ordering::Equal
+        };
+        if (1..list.len()).all(|index| compare(index - 1, index) != Ordering::Greater) {
+            return None;
+        }
+        let mut order: Vec<usize> = (0..list.len()).collect();
+        order.sort_by(|&a, &b| compare(a, b));
+        Some(order)
+    }
+}
+
+fn value(key: Key, item: &Item, activity: &activity::Log, now: DateTime) -> Option<Value> {
+    let date = |date: Option<DateTime>| date.map(|date| Value::Number(date.to_unix(0)));
+    match key {
+        Key::Due => date(item.due_by()),
+        Key::Sched => date(schedule::scheduled(item)),
+        Key::Defer => date(defer::until(item)),
+        Key::Estimate => estimate::parse(item).map(Value::Number),
+        Key::Quadrant => {
+            let quadrant = matrix::quadrant(item, now);
+            QUADRANTS
+                .iter()
+                .position(|&other| other == quadrant)
+                .map(|position| Value::Number(position as i64))
+        }
+        Key::Priority => item.priority.map(|level| Value::Number(level as i64)),
+        Key::Title => Some(Value::Text(item.to_lowercase())),
+        Key::Added => activity.opened(item).map(Value::Number),
+        Key::Completed => activity.closed(item).map(Value::Number),
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::date::DateTime;
+use crate::item::Item;
+use crate::status::Status;
+use crate::storage::{self, Format, Lists, Storage};
+
+// The lists in an SQLite database, for lists so long that rewriting all of them on every save adds
+// up. Each item is a row, and save() only touches the rows of the items that were added, removed or
+// moved since, in one transaction. The due date is a column of its own, so
+//
+//     SELECT title FROM items WHERE status = 'todo' AND due < date('now');
+//
+// is all it takes to find what's overdue with `sqlite3 todo.db`. Like the JSON file, the first time
+// there's no database the lists come from the `lines` file next to it.
+pub struct Sqlite;
+
+// In `PRAGMA user_version`. Databases from a later cli-todo are refused rather than half understood.
+pub const VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS items (
+        id INTEGER PRIMARY KEY,
+        status TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        due TEXT
+    );
+    CREATE INDEX IF NOT EXISTS items_due ON items (status, due);
+";
+
+const STATUSES: [Status; 3] = [Status::Todo, Status::Done, Status::Someday];
+
+fn error(file_path: &str, error: rusqlite::Error) -> io::Error {
+    io::Error::other(format!("{}: ERROR: {}", file_path, error))
+}
+
+fn open(file_path: &str, flags: OpenFlags) -> io::Result<Connection> {
+    let connection =
+        Connection::open_with_flags(file_path, flags).map_err(|e| error(file_path, e))?;
+    let version: i64 = connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| error(file_path, e))?;
+    if version > VERSION {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{}: ERROR: version {} is from a later cli-todo, this one reads up to {}",
+                file_path, version, VERSION
+            ),
+        ));
+    }
+    Ok(connection)
+}
+
+impl Storage for Sqlite {
+    fn load(&self, file_path: &str, progress: &mut dyn FnMut(u64, u64)) -> io::Result<Lists> {
+        if !Path::new(file_path).exists() {
+            let lines = Path::new(file_path).with_file_name(Format::Lines.file_name());
+            return match storage::load(&lines.to_string_lossy(), progress) {
+                Err(other) if other.kind() == ErrorKind::NotFound => {
+                    Err(io::Error::new(ErrorKind::NotFound, file_path))
+                }
+                result => result,
+            };
+        }
+        let connection = open(file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut lists = Lists::default();
+        let mut query = connection
+            .prepare("SELECT status, title FROM items ORDER BY position")
+            .map_err(|e| error(file_path, e))?;
+        let rows = query
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| error(file_path, e))?;
+        for row in rows {
+            let (status, title): (String, String) = row.map_err(|e| error(file_path, e))?;
+            let title = Item::parse(&title);
+            match STATUSES.into_iter().find(|other| other.name() == status) {
+                Some(Status::Todo) => lists.todos.push(title),
+                Some(Status::Done) => lists.dones.push(title),
+                Some(Status::Someday) => lists.somedays.push(title),
+                None => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{}: ERROR: unknown status `{}`", file_path, status),
+                    ))
+                }
+            }
+        }
+        Ok(lists)
+    }
+
+    // Items are matched to the rows that have their title, in order, the same way the JSON file
+    // keeps ids. Adding one at the end of a list is a single INSERT, one at the top still moves
+    // every item under it down a position.
+    fn save(&self, lists: &Lists, file_path: &str) -> io::Result<()> {
+        let mut connection = open(file_path, OpenFlags::default())?;
+        let fail = |e| error(file_path, e);
+        let transaction = connection.transaction().map_err(fail)?;
+        transaction.execute_batch(SCHEMA).map_err(fail)?;
+        transaction
+            .pragma_update(None, "user_version", VERSION)
+            .map_err(fail)?;
+        let mut rows: HashMap<String, VecDeque<(i64, String, i64)>> = HashMap::new();
+        {
+            let mut query = transaction
+                .prepare("SELECT id, status, position, title FROM items ORDER BY position")
+                .map_err(fail)?;
+            let found = query
+                .query_map([], |row| {
+                    Ok((row.get(3)?, (row.get(0)?, row.get(1)?, row.get(2)?)))
+                })
+                .map_err(fail)?;
+            for row in found {
+                let (title, row): (String, _) = row.map_err(fail)?;
+                rows.entry(title).or_default().push_back(row);
+            }
+        }
+        {
+            let mut insert = transaction
+                .prepare("INSERT INTO items (status, position, title, due) VALUES (?, ?, ?, ?)")
+                .map_err(fail)?;
+            let mut update = transaction
+                .prepare("UPDATE items SET status = ?, position = ? WHERE id = ?")
+                .map_err(fail)?;
+            let mut delete = transaction
+                .prepare("DELETE FROM items WHERE id = ?")
+                .map_err(fail)?;
+            for (status, list) in
+                STATUSES
+                    .into_iter()
+                    .zip([&lists.todos, &lists.dones, &lists.somedays])
+            {
+                for (position, item) in list.iter().enumerate() {
+                    let (status, position) = (status.name(), position as i64);
+                    // The title column keeps the whole line, due date and all, and the due column
+                    // the date the way it's written, which sorts the same as the dates do.
+                    let title = item.to_string();
+                    match rows.get_mut(&title).and_then(VecDeque::pop_front) {
+                        Some((_, before, at)) if before == status && at == position => {}
+                        Some((id, _, _)) => {
+                            update
+                                .execute(params![status, position, id])
+                                .map_err(fail)?;
+                        }
+                        None => {
+                            insert
+                                .execute(params![
+                                    status,
+                                    position,
+                                    title,
+                                    item.due.map(DateTime::to_item)
+                                ])
+                                .map_err(fail)?;
+                        }
+                    }
+                }
+            }
+            for (id, _, _) in rows.into_values().flatten() {
+                delete.execute(params![id]).map_err(fail)?;
+            }
+        }
+        transaction.commit().map_err(fail)
+    }
+}
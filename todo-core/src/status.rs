@@ -0,0 +1,25 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Todo,
+    Done,
+    // Parked for some day, out of the way of what's actually being worked on.
+    Someday,
+}
+
+impl Status {
+    pub fn toggle(&self) -> Self {
+        match self {
+            Status::Todo => Status::Done,
+            Status::Done | Status::Someday => Status::Todo,
+        }
+    }
+
+    // As hooks and scripts see it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::Done => "done",
+            Status::Someday => "someday",
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, BufRead, ErrorKind, Write};
+use std::path::Path;
+
+use crate::item::Item;
+use crate::jsonfile::Json;
+#[cfg(feature = "sqlite")]
+use crate::sqlite::Sqlite;
+use crate::status::Status;
+use crate::todotxt::TodoTxt;
+
+pub fn parse_item(line: &str) -> Option<(Status, &str)> {
+    let todo_item = line
+        .strip_prefix("TODO: ")
+        .map(|title| (Status::Todo, title));
+    let done_item = line
+        .strip_prefix("DONE: ")
+        .map(|title| (Status::Done, title));
+    let someday_item = line
+        .strip_prefix("SOMEDAY: ")
+        .map(|title| (Status::Someday, title));
+    todo_item.or(done_item).or(someday_item)
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Lists {
+    pub todos: Vec<Item>,
+    pub dones: Vec<Item>,
+    pub somedays: Vec<Item>,
+}
+
+// Reads the lists from `file_path`. The file may be big, so `progress` is called every now and
+// then with the number of bytes read so far and the size of the whole file.
+pub fn load(file_path: &str, mut progress: impl FnMut(u64, u64)) -> io::Result<Lists> {
+    let file = File::open(file_path)?;
+    let total = file.metadata()?.len();
+    let mut lists = Lists::default();
+    let mut done = 0;
+    for (index, line) in io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        done += line.len() as u64 + 1;
+        match parse_item(&line) {
+            Some((Status::Todo, title)) => lists.todos.push(Item::parse(title)),
+            Some((Status::Done, title)) => lists.dones.push(Item::parse(title)),
+            Some((Status::Someday, title)) => lists.somedays.push(Item::parse(title)),
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}:{}: ERROR: ill-formed item line", file_path, index + 1),
+                ));
+            }
+        }
+        if index % 1024 == 0 {
+            progress(done, total);
+        }
+    }
+    Ok(lists)
+}
+
+pub fn save(todos: &[Item], dones: &[Item], somedays: &[Item], file_path: &str) -> io::Result<()> {
+    let mut file = File::create(file_path)?;
+    for todo in todos.iter() {
+        writeln!(file, "TODO: {}", todo)?;
+    }
+    for done in dones.iter() {
+        writeln!(file, "DONE: {}", done)?;
+    }
+    for someday in somedays.iter() {
+        writeln!(file, "SOMEDAY: {}", someday)?;
+    }
+    Ok(())
+}
+
+// How the lists are kept in a file. Everything that reads or writes the user's lists goes through
+// the one their Format picks, load() and save() below are Lines, the format cli-todo always had.
+pub trait Storage {
+    fn load(&self, file_path: &str, progress: &mut dyn FnMut(u64, u64)) -> io::Result<Lists>;
+    fn save(&self, lists: &Lists, file_path: &str) -> io::Result<()>;
+}
+
+// `TODO: `, `DONE: ` or `SOMEDAY: ` and the title, one item per line.
+pub struct Lines;
+
+impl Storage for Lines {
+    fn load(&self, file_path: &str, progress: &mut dyn FnMut(u64, u64)) -> io::Result<Lists> {
+        load(file_path, progress)
+    }
+
+    fn save(&self, lists: &Lists, file_path: &str) -> io::Result<()> {
+        save(&lists.todos, &lists.dones, &lists.somedays, file_path)
+    }
+}
+
+// Which Storage the lists are in, from `format` in [storage] or `--format`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Format {
+    #[default]
+    Lines,
+    TodoTxt,
+    Json,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+pub const FORMATS: &[(&str, Format)] = &[
+    ("lines", Format::Lines),
+    ("todotxt", Format::TodoTxt),
+    ("json", Format::Json),
+    #[cfg(feature = "sqlite")]
+    ("sqlite", Format::Sqlite),
+];
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        if cfg!(not(feature = "sqlite")) && name == "sqlite" {
+            return Err("this cli-todo was built without the `sqlite` feature".to_string());
+        }
+        FORMATS
+            .iter()
+            .find(|(other, _)| *other == name)
+            .map(|(_, format)| *format)
+            .ok_or_else(|| {
+                let names: Vec<_> = FORMATS.iter().map(|(name, _)| *name).collect();
+                format!(
+                    "unknown format `{}`, expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+
+    pub fn storage(self) -> &'static dyn Storage {
+        match self {
+            Format::Lines => &Lines,
+            Format::TodoTxt => &TodoTxt,
+            Format::Json => &Json,
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => &Sqlite,
+        }
+    }
+
+    // The file the lists are in, in the current directory.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Format::Lines => "TODO",
+            Format::TodoTxt => "todo.txt",
+            Format::Json => "todo.json",
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => "todo.db",
+        }
+    }
+}
+
+// DONE items that are old news go to `<file>.archive` as `DONE: ` lines, whatever format the lists
+// are in. Appending a line is all an archive needs, and `--format lines` opens it like any list.
+pub fn archive_path(file_path: &str) -> String {
+    format!("{}.archive", file_path)
+}
+
+pub fn archive(dones: &[Item], file_path: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(archive_path(file_path))?;
+    for done in dones {
+        writeln!(file, "DONE: {}", done)?;
+    }
+    Ok(())
+}
+
+// Held by whoever is about to write `file_path` back, so two programs never save over each other.
+// The UI holds it for the whole session, the server only while it handles a request. The lock
+// lives on a file next to the list rather than the list itself, since saving truncates that one
+// and Windows won't have anyone writing to a locked file. The OS lets go of it when the process
+// dies, so a crash never leaves the list locked.
+pub struct Lock {
+    _file: File,
+}
+
+pub fn lock_path(file_path: &str) -> String {
+    let path = Path::new(file_path);
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.lock", name))
+        .to_string_lossy()
+        .into_owned()
+}
+
+// Fails with ErrorKind::WouldBlock when someone else holds the lock.
+pub fn lock(file_path: &str) -> io::Result<Lock> {
+    let path = lock_path(file_path);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Lock { _file: file }),
+        Err(TryLockError::WouldBlock) => Err(io::Error::new(
+            ErrorKind::WouldBlock,
+            format!("{}: ERROR: {} is open in another cli-todo", path, file_path),
+        )),
+        Err(TryLockError::Error(error)) => Err(error),
+    }
+}
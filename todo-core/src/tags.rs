@@ -0,0 +1,44 @@
+use crate::item::Item;
+
+// Tags are `#words` anywhere in an item's title.
+pub fn of(item: &str) -> impl Iterator<Item = &str> {
+    item.split_whitespace()
+        .filter_map(|word| word.strip_prefix('#'))
+        .filter(|tag| !tag.is_empty())
+}
+
+// Every tag used in `items`, sorted and without repeats.
+pub fn all<'a>(items: impl IntoIterator<Item = &'a Item>) -> Vec<&'a str> {
+    let mut tags: Vec<&str> = items.into_iter().flat_map(|item| of(item)).collect();
+    tags.sort_unstable();
+    tags.dedup();
+    tags
+}
+
+pub fn has(item: &str, tag: &str) -> bool {
+    item.split_whitespace()
+        .any(|word| word.strip_prefix('#') == Some(tag))
+}
+
+pub fn add(items: &mut [String], tag: &str) {
+    for item in items.iter_mut().filter(|item| !has(item, tag)) {
+        item.push_str(" #");
+        item.push_str(tag);
+    }
+}
+
+// Adds the tag to a single item, or takes it out of it.
+pub fn set(item: &mut String, tag: &str, on: bool) {
+    if on {
+        add(std::slice::from_mut(item), tag);
+    } else if has(item, tag) {
+        let tag = format!("#{}", tag);
+        *item = item
+            .split(' ')
+            .filter(|word| *word != tag)
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_end()
+            .to_string();
+    }
+}
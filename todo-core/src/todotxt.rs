@@ -0,0 +1,163 @@
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+
+use crate::fields;
+use crate::item::Item;
+use crate::status::Status;
+use crate::storage::{Lists, Storage};
+
+// todo.txt (http://todotxt.org), the format a lot of other tools read, one task per line:
+//
+//     (A) 2026-10-01 call mom +family @phone due:2026-10-20
+//     x 2026-10-14 2026-10-01 file taxes pri:B
+//
+// The priority and the dates up front become `pri:`, `created:` and `completed:` fields at the end
+// of the item and go back to the front when it's saved, contexts, projects and the other fields
+// are just words in the title either way. todo.txt has no SOMEDAY, those items are open tasks with
+// `@someday` in them and go back there when the file is read.
+pub struct TodoTxt;
+
+const SOMEDAY: &str = "@someday";
+
+fn is_date(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(index, byte)| match index {
+            4 | 7 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
+
+// A `(A)` priority.
+fn is_priority(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() == 3 && bytes[0] == b'(' && bytes[1].is_ascii_uppercase() && bytes[2] == b')'
+}
+
+// The first word of `line` and what comes after it when `word` likes it.
+fn take(line: &str, word: impl Fn(&str) -> bool) -> Option<(&str, &str)> {
+    let (first, rest) = line.split_once(' ').unwrap_or((line, ""));
+    word(first).then_some((first, rest))
+}
+
+pub fn parse_line(line: &str) -> (Status, String) {
+    let mut rest = line;
+    let mut found = Vec::new();
+    let done = match line.strip_prefix("x ") {
+        Some(after) => {
+            rest = after;
+            true
+        }
+        None => false,
+    };
+    if done {
+        if let Some((completed, after)) = take(rest, is_date) {
+            found.push(("completed", completed));
+            rest = after;
+            if let Some((created, after)) = take(rest, is_date) {
+                found.push(("created", created));
+                rest = after;
+            }
+        }
+    } else {
+        if let Some((priority, after)) = take(rest, is_priority) {
+            found.push(("pri", &priority[1..2]));
+            rest = after;
+        }
+        if let Some((created, after)) = take(rest, is_date) {
+            found.push(("created", created));
+            rest = after;
+        }
+    }
+    let someday = !done && rest.split(' ').any(|word| word == SOMEDAY);
+    let mut item = if someday {
+        let words: Vec<&str> = rest.split(' ').filter(|word| *word != SOMEDAY).collect();
+        words.join(" ").trim_end().to_string()
+    } else {
+        rest.to_string()
+    };
+    // Created before completed, the way they'd be read out loud.
+    found.sort_by_key(|(key, _)| *key == "completed");
+    for (key, value) in found {
+        fields::set(&mut item, key, Some(value));
+    }
+    let status = match (done, someday) {
+        (true, _) => Status::Done,
+        (false, true) => Status::Someday,
+        (false, false) => Status::Todo,
+    };
+    (status, item)
+}
+
+// Takes the `key:` field out of `item` when `valid` likes its value.
+fn field(item: &mut String, key: &str, valid: impl Fn(&str) -> bool) -> Option<String> {
+    let value = fields::get(item, key)
+        .filter(|value| valid(value))?
+        .to_string();
+    fields::set(item, key, None);
+    Some(value)
+}
+
+pub fn format_line(status: Status, item: &str) -> String {
+    let mut item = item.to_string();
+    let mut front = Vec::new();
+    if status == Status::Done {
+        front.push("x".to_string());
+        // A creation date only goes up front after a completion date.
+        if let Some(completed) = field(&mut item, "completed", is_date) {
+            front.push(completed);
+            front.extend(field(&mut item, "created", is_date));
+        }
+    } else {
+        let priority = |value: &str| value.len() == 1 && value.as_bytes()[0].is_ascii_uppercase();
+        front.extend(field(&mut item, "pri", priority).map(|level| format!("({})", level)));
+        front.extend(field(&mut item, "created", is_date));
+    }
+    if status == Status::Someday {
+        item.push(' ');
+        item.push_str(SOMEDAY);
+    }
+    front.push(item);
+    front.join(" ")
+}
+
+impl Storage for TodoTxt {
+    // Every line is a task, there's nothing ill-formed about any of them. Blank ones are skipped.
+    fn load(&self, file_path: &str, progress: &mut dyn FnMut(u64, u64)) -> io::Result<Lists> {
+        let file = File::open(file_path)?;
+        let total = file.metadata()?.len();
+        let mut lists = Lists::default();
+        let mut done = 0;
+        for (index, line) in io::BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            done += line.len() as u64 + 1;
+            if !line.trim().is_empty() {
+                let (status, item) = parse_line(&line);
+                let item = Item::parse(&item);
+                match status {
+                    Status::Todo => lists.todos.push(item),
+                    Status::Done => lists.dones.push(item),
+                    Status::Someday => lists.somedays.push(item),
+                }
+            }
+            if index % 1024 == 0 {
+                progress(done, total);
+            }
+        }
+        Ok(lists)
+    }
+
+    fn save(&self, lists: &Lists, file_path: &str) -> io::Result<()> {
+        let mut file = File::create(file_path)?;
+        for (status, list) in [
+            (Status::Todo, &lists.todos),
+            (Status::Done, &lists.dones),
+            (Status::Someday, &lists.somedays),
+        ] {
+            for item in list {
+                writeln!(file, "{}", format_line(status, &item.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,765 @@
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use proptest::prelude::*;
+use todo_core::activity::{self, Entry, Event};
+use todo_core::checklist;
+use todo_core::date::{self, DateTime};
+use todo_core::due;
+use todo_core::fields;
+use todo_core::fuzzy;
+use todo_core::item::Item;
+use todo_core::json::Value;
+use todo_core::list;
+use todo_core::markdown;
+use todo_core::merge::{self, State};
+use todo_core::ops::{self, Undo};
+use todo_core::org;
+use todo_core::pick;
+use todo_core::pin;
+use todo_core::priority;
+use todo_core::regex::Regex;
+use todo_core::status::Status;
+use todo_core::storage::{self, Format, Lists};
+
+#[derive(Debug, Clone)]
+enum Op {
+    DragUp,
+    DragDown,
+    Up,
+    Down,
+    PageUp(usize),
+    PageDown(usize),
+    First,
+    Last,
+    Transfer,
+    TransferBack,
+    Delete,
+    Paste(Item),
+    Duplicate,
+    Join,
+    Split,
+}
+
+// Items the way todo.txt tasks come out: words, contexts and projects, then whichever of the fields
+// it has a place for up front. A lone `x` first would be read as the mark of a finished task.
+fn todotxt_item(done: bool) -> impl Strategy<Value = Item> {
+    let date = "20[0-9]{2}-[01][0-9]-[0-3][0-9]";
+    (
+        "[a-wyz][a-z]{0,5}( ([a-z]{1,6}|@[a-z]{1,4}|\\+[a-z]{1,4})){0,4}",
+        prop::option::of("[A-Z]"),
+        prop::option::of(date),
+        prop::option::of(date),
+    )
+        .prop_map(move |(mut item, priority, created, completed)| {
+            // Only DONE items have a completion date, and a creation date only goes with one.
+            if let Some(priority) = priority.filter(|_| !done) {
+                item.push_str(&format!(" pri:{}", priority));
+            }
+            if let Some(created) = created.filter(|_| !done || completed.is_some()) {
+                item.push_str(&format!(" created:{}", created));
+            }
+            if let Some(completed) = completed.filter(|_| done) {
+                item.push_str(&format!(" completed:{}", completed));
+            }
+            Item::parse(&item)
+        })
+}
+
+// Anything that can end up in a title. Titles are single lines, the file has no way of holding a
+// line break inside of one.
+fn title() -> impl Strategy<Value = String> {
+    "[^\r\n]{0,20}"
+}
+
+// Items with `title`, due some time now and then.
+fn due_now_and_then(title: impl Strategy<Value = String>) -> impl Strategy<Value = Item> {
+    (
+        title,
+        prop::option::of(0i64..100_000_000),
+        prop::option::of(prop::sample::select(priority::LEVELS.to_vec())),
+    )
+        .prop_map(|(title, minutes, level)| {
+            let mut item = Item::parse(&title);
+            item.due = item
+                .due
+                .or(minutes.map(|minutes| DateTime::from_unix(minutes * 60, 0)));
+            item.priority = item.priority.or(level);
+            item
+        })
+}
+
+fn item() -> impl Strategy<Value = Item> {
+    due_now_and_then(title())
+}
+
+// Org headings are trimmed and take `:tags:` at their end, so titles are words without colons.
+fn org_title() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9#@+]{1,8}( [a-zA-Z0-9#@+]{1,8}){0,3}"
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        Just(Op::DragUp),
+        Just(Op::DragDown),
+        Just(Op::Up),
+        Just(Op::Down),
+        (0..50usize).prop_map(Op::PageUp),
+        (0..50usize).prop_map(Op::PageDown),
+        Just(Op::First),
+        Just(Op::Last),
+        Just(Op::Transfer),
+        Just(Op::TransferBack),
+        Just(Op::Delete),
+        item().prop_map(Op::Paste),
+        Just(Op::Duplicate),
+        Just(Op::Join),
+        Just(Op::Split),
+    ]
+}
+
+fn in_bounds(list: &[Item], curr: usize) -> bool {
+    curr < list.len() || (list.is_empty() && curr == 0)
+}
+
+fn run_op(op: &Op, lists: &mut Lists, todo_curr: &mut usize, done_curr: &mut usize) {
+    let (list, curr) = (&mut lists.todos, todo_curr);
+    match op {
+        Op::DragUp => list::drag_up(list, curr),
+        Op::DragDown => list::drag_down(list, curr),
+        Op::Up => list::up(curr),
+        Op::Down => list::down(list, curr),
+        Op::PageUp(page) => list::page_up(curr, *page),
+        Op::PageDown(page) => list::page_down(list, curr, *page),
+        Op::First => list::first(curr),
+        Op::Last => list::last(list, curr),
+        Op::Transfer => list::transfer(&mut lists.dones, list, curr),
+        Op::TransferBack => list::transfer(list, &mut lists.dones, done_curr),
+        Op::Delete => {
+            list::delete(list, curr);
+        }
+        Op::Paste(item) => list::paste(list, curr, item.clone()),
+        Op::Duplicate => list::duplicate(list, curr),
+        Op::Join => list::join(list, curr),
+        Op::Split => list::split(list, curr),
+    }
+}
+
+fn temp_path() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let name = format!(
+        "todo-core-{}-{}",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    env::temp_dir().join(name).to_str().unwrap().to_string()
+}
+
+proptest! {
+    #[test]
+    fn cursor_stays_in_bounds(
+        mut todos in prop::collection::vec(item(), 0..10),
+        mut dones in prop::collection::vec(item(), 0..10),
+        ops in prop::collection::vec(op(), 0..50),
+    ) {
+        let mut todo_curr = 0;
+        let mut done_curr = 0;
+        for op in ops {
+            let total = todos.len() + dones.len();
+            let (list, curr) = (&mut todos, &mut todo_curr);
+            match &op {
+                Op::DragUp => list::drag_up(list, curr),
+                Op::DragDown => list::drag_down(list, curr),
+                Op::Up => list::up(curr),
+                Op::Down => list::down(list, curr),
+                Op::PageUp(page) => list::page_up(curr, *page),
+                Op::PageDown(page) => list::page_down(list, curr, *page),
+                Op::First => list::first(curr),
+                Op::Last => list::last(list, curr),
+                Op::Transfer => list::transfer(&mut dones, list, curr),
+                Op::TransferBack => list::transfer(list, &mut dones, &mut done_curr),
+                Op::Delete => {
+                    list::delete(list, curr);
+                }
+                Op::Paste(item) => list::paste(list, curr, item.clone()),
+                Op::Duplicate => list::duplicate(list, curr),
+                Op::Join => list::join(list, curr),
+                Op::Split => list::split(list, curr),
+            }
+            prop_assert!(in_bounds(&todos, todo_curr));
+            prop_assert!(in_bounds(&dones, done_curr));
+            if let Op::Transfer | Op::TransferBack = op {
+                // Items move between the lists, none get lost or made up on the way.
+                prop_assert_eq!(todos.len() + dones.len(), total);
+            }
+        }
+    }
+
+    #[test]
+    fn drag_up_and_down_undo_each_other(
+        mut items in prop::collection::vec(item(), 2..10),
+        curr in 0..10usize,
+    ) {
+        let mut curr = curr % (items.len() - 1) + 1;
+        let (before, before_curr) = (items.clone(), curr);
+        list::drag_up(&mut items, &mut curr);
+        prop_assert_eq!(curr, before_curr - 1);
+        list::drag_down(&mut items, &mut curr);
+        prop_assert_eq!(items, before);
+        prop_assert_eq!(curr, before_curr);
+    }
+
+    #[test]
+    fn split_undoes_join(
+        items in prop::collection::vec("[a-z]{1,8}( [a-z]{1,8})?", 2..10),
+        curr in 0..10usize,
+    ) {
+        let mut items: Vec<Item> = items.into_iter().map(Item::from).collect();
+        let mut curr = curr % (items.len() - 1);
+        let before = items.clone();
+        list::join(&mut items, &mut curr);
+        prop_assert_eq!(items.len(), before.len() - 1);
+        list::split(&mut items, &mut curr);
+        prop_assert_eq!(items, before);
+    }
+
+    #[test]
+    fn parse_item_reads_what_save_writes(title in title()) {
+        let todo = format!("TODO: {}", title);
+        let done = format!("DONE: {}", title);
+        prop_assert_eq!(storage::parse_item(&todo), Some((Status::Todo, title.as_str())));
+        prop_assert_eq!(storage::parse_item(&done), Some((Status::Done, title.as_str())));
+        let someday = format!("SOMEDAY: {}", title);
+        prop_assert_eq!(storage::parse_item(&someday), Some((Status::Someday, title.as_str())));
+    }
+
+    #[test]
+    fn items_read_back_what_they_wrote(item in item(), line in title()) {
+        prop_assert_eq!(Item::parse(&item.to_string()), item);
+        // Lines without a due date or priority are the title as they are, spaces and all.
+        let parsed = Item::parse(&line);
+        if parsed.due.is_none() && parsed.priority.is_none() {
+            prop_assert_eq!(parsed.title, line);
+        }
+    }
+
+    #[test]
+    fn load_reads_back_what_save_wrote(
+        todos in prop::collection::vec(item(), 0..20),
+        dones in prop::collection::vec(item(), 0..20),
+        somedays in prop::collection::vec(item(), 0..20),
+    ) {
+        let file_path = temp_path();
+        storage::save(&todos, &dones, &somedays, &file_path).unwrap();
+        let loaded = storage::load(&file_path, |_, _| {});
+        fs::remove_file(&file_path).unwrap();
+        prop_assert_eq!(loaded.unwrap(), storage::Lists { todos, dones, somedays });
+    }
+
+    #[test]
+    fn todotxt_reads_back_what_it_wrote(
+        todos in prop::collection::vec(todotxt_item(false), 0..10),
+        dones in prop::collection::vec(todotxt_item(true), 0..10),
+        somedays in prop::collection::vec(todotxt_item(false), 0..10),
+    ) {
+        let file_path = temp_path();
+        let lists = Lists { todos, dones, somedays };
+        let storage = Format::TodoTxt.storage();
+        storage.save(&lists, &file_path).unwrap();
+        let loaded = storage.load(&file_path, &mut |_, _| {});
+        fs::remove_file(&file_path).unwrap();
+        prop_assert_eq!(loaded.unwrap(), lists);
+    }
+
+    #[test]
+    fn json_file_reads_back_what_it_wrote(
+        todos in prop::collection::vec(item(), 0..10),
+        dones in prop::collection::vec(item(), 0..10),
+        somedays in prop::collection::vec(item(), 0..10),
+    ) {
+        let file_path = temp_path();
+        let lists = Lists { todos, dones, somedays };
+        let storage = Format::Json.storage();
+        storage.save(&lists, &file_path).unwrap();
+        let first = fs::read_to_string(&file_path).unwrap();
+        // Saving them again changes nothing, not even the ids or times.
+        storage.save(&lists, &file_path).unwrap();
+        let second = fs::read_to_string(&file_path).unwrap();
+        let loaded = storage.load(&file_path, &mut |_, _| {});
+        fs::remove_file(&file_path).unwrap();
+        prop_assert_eq!(first, second);
+        prop_assert_eq!(loaded.unwrap(), lists);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_reads_back_what_it_wrote(
+        first in prop::collection::vec(item(), 0..10),
+        todos in prop::collection::vec(item(), 0..10),
+        dones in prop::collection::vec(item(), 0..10),
+        somedays in prop::collection::vec(item(), 0..10),
+    ) {
+        let file_path = temp_path();
+        let storage = Format::Sqlite.storage();
+        // Over lists that were there before, so some rows are kept, some moved and some dropped.
+        let before = Lists { todos: first.clone(), dones: first, somedays: Vec::new() };
+        storage.save(&before, &file_path).unwrap();
+        let lists = Lists { todos, dones, somedays };
+        storage.save(&lists, &file_path).unwrap();
+        let loaded = storage.load(&file_path, &mut |_, _| {});
+        fs::remove_file(&file_path).unwrap();
+        prop_assert_eq!(loaded.unwrap(), lists);
+    }
+
+    #[test]
+    fn org_reads_back_what_it_wrote(
+        todos in prop::collection::vec(due_now_and_then(org_title()), 0..10),
+        dones in prop::collection::vec(due_now_and_then(org_title()), 0..10),
+        somedays in prop::collection::vec(due_now_and_then(org_title()), 0..10),
+    ) {
+        let lists = Lists { todos, dones, somedays };
+        prop_assert_eq!(org::import(&org::export(&lists)), (lists, Vec::new()));
+    }
+
+    #[test]
+    fn markdown_reads_back_what_it_wrote(
+        todos in prop::collection::vec(item(), 0..10),
+        dones in prop::collection::vec(item(), 0..10),
+        somedays in prop::collection::vec(item(), 0..10),
+    ) {
+        let lists = Lists { todos, dones, somedays };
+        prop_assert_eq!(markdown::import(&markdown::export(&lists)), lists);
+    }
+
+    #[test]
+    fn json_reads_back_what_it_wrote(
+        text in any::<String>(),
+        items in prop::collection::vec(any::<String>(), 0..5),
+        number in any::<i32>(),
+    ) {
+        let value = Value::object([
+            ("text", text.as_str().into()),
+            ("items", items[..].into()),
+            ("number", Value::Number(number as f64)),
+            ("nothing", Value::Null),
+            ("flag", Value::Bool(true)),
+        ]);
+        prop_assert_eq!(Value::parse(&value.to_string()), Ok(value));
+    }
+
+    #[test]
+    fn dates_read_back_what_they_wrote(
+        minutes in -100_000_000i64..100_000_000,
+        offset in -14 * 60i64..14 * 60,
+    ) {
+        let (secs, offset) = (minutes * 60, offset * 60);
+        let date = DateTime::from_unix(secs, offset);
+        prop_assert_eq!(date.to_unix(offset), secs);
+        prop_assert_eq!(DateTime::parse(&date.to_item()), Some(date));
+    }
+
+    #[test]
+    fn month_and_day_is_the_next_one_to_come(
+        minutes in 0i64..100_000_000,
+        month in 0usize..12,
+        day in 1u32..29,
+    ) {
+        let now = DateTime::from_unix(minutes * 60, 0);
+        let names = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+        let text = format!("{} {}", names[month], day);
+        let date = date::parse_when(&text, now).unwrap();
+        prop_assert_eq!((date.month, date.day), (month as u32 + 1, day));
+        let today = DateTime { hour: 0, minute: 0, ..now };
+        prop_assert!(date >= today && date < today.add_days(366));
+        let spaced = format!("{} {} at 9:30pm", day, names[month]);
+        prop_assert_eq!(date::parse_when(&spaced, now), Some(DateTime { hour: 21, minute: 30, ..date }));
+    }
+
+    #[test]
+    fn phrases_land_on_the_right_day(minutes in 0i64..100_000_000) {
+        let now = DateTime::from_unix(minutes * 60, 0);
+        let today = DateTime { hour: 0, minute: 0, ..now };
+        let when = |text: &str| date::parse_when(text, now).unwrap();
+        let days = |date: DateTime| (date.to_unix(0) - today.to_unix(0)) / (24 * 60 * 60);
+        prop_assert_eq!(when("day after tomorrow"), today.add_days(2));
+        let friday = when("this fri");
+        prop_assert_eq!(friday.weekday(), 4);
+        prop_assert!((0..7).contains(&days(friday)));
+        let friday = when("next fri");
+        prop_assert_eq!(friday.weekday(), 4);
+        prop_assert!((1..=7).contains(&days(friday)));
+        let monday = when("next week");
+        prop_assert_eq!(monday.weekday(), 0);
+        prop_assert!((1..=7).contains(&days(monday)));
+        let weekend = when("weekend");
+        prop_assert!(weekend.weekday() >= 5 && (0..7).contains(&days(weekend)));
+        let first = when("next month");
+        prop_assert_eq!(first.day, 1);
+        prop_assert!((1..=31).contains(&days(first)));
+        let last = when("end of month");
+        prop_assert_eq!(last.add_days(1), first);
+        prop_assert_eq!(when("end of week").weekday(), 6);
+        prop_assert_eq!(when("next year"), DateTime::new(now.year + 1, 1, 1, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn picks_only_what_can_be_picked(
+        weights in prop::collection::vec(0u64..4, 0..20),
+        roll in any::<u64>(),
+    ) {
+        match pick::weighted(&weights, roll) {
+            Some(index) => prop_assert!(weights[index] > 0),
+            None => prop_assert!(weights.iter().all(|&weight| weight == 0)),
+        }
+    }
+
+    #[test]
+    fn pinned_items_float_up_in_order(
+        pinned in prop::collection::vec(any::<bool>(), 1..20),
+        curr in any::<prop::sample::Index>(),
+    ) {
+        let mut list: Vec<Item> = pinned
+            .iter()
+            .enumerate()
+            .map(|(index, &pinned)| if pinned { format!("{} #pinned", index) } else { index.to_string() })
+            .map(Item::from)
+            .collect();
+        let mut curr = curr.index(list.len());
+        let before = list[curr].clone();
+        pin::float(&mut list, &mut curr);
+        prop_assert_eq!(&list[curr], &before);
+        let split = list.iter().take_while(|item| pin::is_pinned(item)).count();
+        prop_assert!(list[split..].iter().all(|item| !pin::is_pinned(item)));
+        for half in [&list[..split], &list[split..]] {
+            let numbers: Vec<usize> = half
+                .iter()
+                .map(|item| item.split(' ').next().unwrap().parse().unwrap())
+                .collect();
+            prop_assert!(numbers.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[test]
+    fn ticking_a_step_twice_changes_nothing(
+        steps in prop::collection::vec((any::<bool>(), "[a-z ]{0,8}"), 1..6),
+        index in any::<prop::sample::Index>(),
+    ) {
+        let mut item = "title".to_string();
+        for (done, text) in &steps {
+            item += if *done { "; [x] " } else { "; [ ] " };
+            item += text;
+        }
+        let before = item.clone();
+        let index = index.index(steps.len());
+        checklist::toggle(&mut item, index);
+        prop_assert_eq!(checklist::steps(&item)[index].0, !steps[index].0);
+        checklist::toggle(&mut item, index);
+        prop_assert_eq!(item, before);
+    }
+
+    #[test]
+    fn every_reminder_comes_exactly_once(
+        minutes in 0i64..100_000_000,
+        lead in 0i64..10_000,
+        early in 0i64..100_000,
+        checks in prop::collection::vec(1i64..100_000, 1..20),
+    ) {
+        let due = DateTime::from_unix(minutes * 60, 0);
+        let items = vec![Item::parse(&format!("water plants due:{}", due.to_item()))];
+        let mut since = items[0].due_by().unwrap().to_unix(0) - lead * 60 - early - 1;
+        let mut count = 0;
+        for check in checks.iter().chain(&[100_000]) {
+            let now = since + check;
+            count += due::reminders(&items, &[lead * 60], since, now, 0).len();
+            since = now;
+        }
+        prop_assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn streak_counts_days_in_a_row(
+        start in 0i64..100_000,
+        days in 1i64..30,
+        hours in prop::collection::vec(0i64..24, 1..4),
+        later in 0i64..2,
+        offset in -14i64..14,
+    ) {
+        const DAY: i64 = 24 * 60 * 60;
+        let offset = offset * 3600;
+        let mut log = activity::Log::default();
+        for day in start..start + days {
+            for hour in &hours {
+                log.entries.push(Entry {
+                    time: day * DAY + hour * 3600 - offset,
+                    event: Event::Done,
+                    title: "water plants".to_string(),
+                });
+            }
+        }
+        let now = (start + days - 1 + later) * DAY + 12 * 3600 - offset;
+        let streak = log.streak(now, offset);
+        prop_assert_eq!(streak.current, days as usize);
+        prop_assert_eq!(streak.best, days as usize);
+        prop_assert_eq!(streak.done_today, later == 0);
+        prop_assert_eq!(log.streak(now + 2 * DAY, offset).current, 0);
+    }
+
+    #[test]
+    fn stats_add_up(
+        events in prop::collection::vec((0usize..5, 0usize..3, 1i64..1000), 0..40),
+        bounds in prop::collection::vec(0i64..20_000, 1..6),
+        open_now in 0usize..20,
+    ) {
+        let kinds = [Event::Added, Event::Done, Event::Undone, Event::Deleted, Event::Checked];
+        let mut time = 0;
+        let entries: Vec<Entry> = events
+            .into_iter()
+            .map(|(kind, title, after)| {
+                time += after;
+                Entry { time, event: kinds[kind], title: format!("item {}", title) }
+            })
+            .collect();
+        let log = activity::Log::new(entries.clone());
+
+        let mut bounds = bounds;
+        bounds.sort_unstable();
+        let periods = log.periods(&bounds, open_now);
+        prop_assert_eq!(periods.len(), bounds.len() - 1);
+        for (period, bound) in periods.iter().zip(bounds.windows(2)) {
+            let within = |events: &[Event]| {
+                entries
+                    .iter()
+                    .filter(|entry| events.contains(&entry.event) && (bound[0]..bound[1]).contains(&entry.time))
+                    .count()
+            };
+            prop_assert_eq!(period.added, within(&[Event::Added]));
+            prop_assert_eq!(period.done, within(&[Event::Done, Event::Checked]));
+            prop_assert_eq!(period.open, log.open_at(bound[1], open_now));
+        }
+
+        // Every title closed goes with the earliest time it was opened and isn't closed yet.
+        let mut open: Vec<(&str, i64)> = Vec::new();
+        let (mut total, mut count) = (0, 0);
+        for entry in &entries {
+            match entry.event {
+                Event::Added | Event::Undone => open.push((&entry.title, entry.time)),
+                Event::Done | Event::Deleted => {
+                    if let Some(index) = open.iter().position(|(title, _)| *title == entry.title) {
+                        let (_, added) = open.remove(index);
+                        if entry.event == Event::Done {
+                            total += entry.time - added;
+                            count += 1;
+                        }
+                    }
+                }
+                Event::Checked => {}
+            }
+        }
+        prop_assert_eq!(log.completion_time(), (count > 0).then(|| (total / count as i64, count)));
+    }
+
+    #[test]
+    fn merging_keeps_the_edits_from_both_sides(
+        base in 2usize..10,
+        ours_new in 0usize..5,
+        theirs_new in 0usize..5,
+        removed in any::<prop::sample::Index>(),
+    ) {
+        let numbered = |name: &str, count: usize| -> Vec<Item> {
+            (0..count).map(|index| Item::from(format!("{} {}", name, index))).collect()
+        };
+        let base = storage::Lists { todos: numbered("base", base), ..Default::default() };
+        let mut state = State::default();
+        state.update(&base, 1);
+        // Round trip through the file, which is what a merge gets to see.
+        let path = temp_path();
+        state.save(&path).unwrap();
+        let state = State::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // We delete one and add some, they finish the one after it and add others.
+        let removed = removed.index(base.todos.len());
+        let finished = (removed + 1) % base.todos.len();
+        let mut ours = storage::Lists { todos: base.todos.clone(), ..Default::default() };
+        ours.todos.remove(removed);
+        ours.todos.extend(numbered("ours", ours_new));
+        let mut our_state = state.clone();
+        our_state.update(&ours, 2);
+        let mut theirs = storage::Lists { todos: base.todos.clone(), ..Default::default() };
+        theirs.dones.push(theirs.todos.remove(finished));
+        theirs.todos.extend(numbered("theirs", theirs_new));
+        let mut their_state = state.clone();
+        their_state.update(&theirs, 3);
+
+        let (merged, merged_state) = merge::merge(&ours, &our_state, &theirs, &their_state, 4);
+        prop_assert!(!merged.todos.contains(&base.todos[removed]));
+        prop_assert_eq!(&merged.dones, &vec![base.todos[finished].clone()]);
+        for item in numbered("ours", ours_new).iter().chain(&numbered("theirs", theirs_new)) {
+            prop_assert!(merged.todos.contains(item));
+        }
+        prop_assert_eq!(merged.todos.len(), base.todos.len() - 2 + ours_new + theirs_new);
+        // Either way round, and again, it comes out the same.
+        let (back, _) = merge::merge(&theirs, &their_state, &ours, &our_state, 4);
+        let sorted = |list: &[Item]| {
+            let mut list = list.to_vec();
+            list.sort_by_key(ToString::to_string);
+            list
+        };
+        prop_assert_eq!(sorted(&back.todos), sorted(&merged.todos));
+        let (again, _) = merge::merge(&merged, &merged_state, &merged, &merged_state, 5);
+        prop_assert_eq!(again, merged);
+    }
+
+    // Whatever whitespace is around a field, the field set is the one found, and clearing it
+    // leaves none behind.
+    #[test]
+    fn fields_set_what_they_get(
+        words in prop::collection::vec(("[a-c:]{1,6}", "[ \t]{1,3}"), 0..5),
+        value in "[0-9a-z-]{1,10}",
+    ) {
+        let mut item: String = words.iter().map(|(word, space)| format!("{}{}", word, space)).collect();
+        item.push_str("\tdue:old");
+        fields::set(&mut item, "due", Some(&value));
+        prop_assert_eq!(fields::get(&item, "due"), Some(value.as_str()));
+        prop_assert_eq!(item.matches("due:").count(), 1);
+        fields::set(&mut item, "due", None);
+        prop_assert_eq!(fields::get(&item, "due"), None);
+    }
+
+    // Any pattern either compiles or says what's wrong with it, and escaped text finds itself.
+    #[test]
+    fn regexes_find_literal_text(
+        pattern in "[a-c.*+?()|\\[\\]^$-]{0,8}",
+        needle in "[a-c.*+?()|^$-]{0,4}",
+        text in "[a-c.*+?()|^$-]{0,12}",
+    ) {
+        if let Ok(regex) = Regex::new(&pattern) {
+            regex.is_match(&text);
+        }
+        let escaped: String = needle.chars().flat_map(|c| ['\\', c]).collect();
+        let regex = Regex::new(&escaped).unwrap();
+        prop_assert_eq!(regex.is_match(&text), text.contains(&needle));
+        let whole = Regex::new(&format!("^{}$", escaped)).unwrap();
+        prop_assert_eq!(whole.is_match(&text), text == needle);
+    }
+
+    // Patterns that make a backtracker try every way of splitting up the `a`s still answer right
+    // away, and right.
+    #[test]
+    fn regexes_take_no_time_on_nested_repeats(count in 0usize..60, ends_in_b: bool) {
+        let text = format!("{}{}", "a".repeat(count), if ends_in_b { "b" } else { "" });
+        for (pattern, expected) in [
+            ("(a|a)*b", ends_in_b),
+            ("(a*)*b", ends_in_b),
+            ("(a+)+b", ends_in_b && count > 0),
+            ("^(a|aa)+$", !ends_in_b && count > 0),
+        ] {
+            let regex = Regex::new(pattern).unwrap();
+            let start = std::time::Instant::now();
+            prop_assert_eq!(regex.is_match(&text), expected);
+            prop_assert!(start.elapsed().as_millis() < 100, "{} took too long", pattern);
+        }
+    }
+
+    // Picking any characters of an item in order finds it, whatever their case, and one more the
+    // item doesn't have doesn't.
+    #[test]
+    fn fuzzy_finds_any_characters_in_order(
+        item in "[a-zA-Z ]{0,20}",
+        keep in prop::collection::vec(any::<bool>(), 20),
+    ) {
+        let query: String = item
+            .chars()
+            .zip(&keep)
+            .filter(|(_, keep)| **keep)
+            .map(|(c, _)| c.to_ascii_uppercase())
+            .collect();
+        prop_assert!(fuzzy::score(&query, &item).is_some());
+        let more = format!("{}#", query);
+        prop_assert!(fuzzy::score(&more, &item).is_none());
+    }
+
+    // A renamed item keeps its history, in the log read back from its file too, whatever the titles
+    // have in them.
+    #[test]
+    fn renamed_items_keep_their_history(titles in prop::collection::vec(title(), 2..6)) {
+        let path = temp_path();
+        let mut log = activity::Log::open(path.clone().into());
+        log.add(Event::Added, &titles[0]).unwrap();
+        for pair in titles.windows(2) {
+            log.rename(&pair[0], &pair[1]).unwrap();
+        }
+        let loaded = activity::Log::open(path.clone().into());
+        fs::remove_file(&path).unwrap();
+        let title = titles.last().unwrap();
+        prop_assert!(log.opened(title).is_some());
+        prop_assert_eq!(loaded.opened(title), log.opened(title));
+        prop_assert_eq!(&loaded.entries, &log.entries);
+    }
+
+    // Whatever the ops did, undoing every change gets back to where they started and redoing them
+    // all gets back to the end.
+    #[test]
+    fn undo_takes_every_change_back(
+        todos in prop::collection::vec(item(), 0..10),
+        dones in prop::collection::vec(item(), 0..10),
+        ops in prop::collection::vec(op(), 0..30),
+    ) {
+        let start = Lists { todos, dones, somedays: Vec::new() };
+        let mut lists = start.clone();
+        let mut undo = Undo::default();
+        let (mut todo_curr, mut done_curr) = (0, 0);
+        for op in ops {
+            let before = lists.clone();
+            run_op(&op, &mut lists, &mut todo_curr, &mut done_curr);
+            if let Some(op) = ops::diff(&before, &lists) {
+                undo.record(op);
+            }
+        }
+        let end = lists.clone();
+        while let Some(done) = undo.undo(&mut lists) {
+            prop_assert!(done.is_ok());
+        }
+        prop_assert_eq!(&lists, &start);
+        while let Some(done) = undo.redo(&mut lists) {
+            prop_assert!(done.is_ok());
+        }
+        prop_assert_eq!(&lists, &end);
+    }
+
+    // However many changes go in a batch, sorting after them included, it's taken back and done
+    // again as one.
+    #[test]
+    fn batches_undo_as_one(
+        todos in prop::collection::vec(item(), 0..10),
+        dones in prop::collection::vec(item(), 0..10),
+        ops in prop::collection::vec(op(), 0..10),
+    ) {
+        let start = Lists { todos, dones, somedays: Vec::new() };
+        let mut lists = start.clone();
+        let mut changes = Vec::new();
+        let (mut todo_curr, mut done_curr) = (0, 0);
+        for op in ops {
+            let before = lists.clone();
+            run_op(&op, &mut lists, &mut todo_curr, &mut done_curr);
+            changes.extend(ops::diff(&before, &lists));
+        }
+        let mut order: Vec<usize> = (0..lists.todos.len()).collect();
+        order.sort_by_key(|&index| lists.todos[index].to_string());
+        changes.extend(ops::reorder(Status::Todo, &lists.todos, &order));
+        list::reorder(&mut lists.todos, &mut todo_curr, order);
+
+        let end = lists.clone();
+        match ops::batch(changes) {
+            Some(batch) => {
+                prop_assert!(batch.inverse().apply(&mut lists));
+                prop_assert_eq!(&lists, &start);
+                prop_assert!(batch.apply(&mut lists));
+                prop_assert_eq!(&lists, &end);
+            }
+            None => prop_assert_eq!(&lists, &start),
+        }
+    }
+}
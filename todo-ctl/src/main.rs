@@ -0,0 +1,108 @@
+use std::env;
+use std::process;
+
+use todo_core::json::Value;
+use todo_core::search::{Case, Search};
+
+fn usage() -> ! {
+    eprintln!("Usage: todo-ctl add <title>");
+    eprintln!("       todo-ctl add --template <name>");
+    eprintln!("       todo-ctl done <title>");
+    eprintln!("       todo-ctl list [--search <pattern>]");
+    eprintln!("       todo-ctl search <pattern>");
+    process::exit(1);
+}
+
+#[cfg(unix)]
+fn call(method: &str, params: Value) -> Result<Value, String> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use todo_core::paths::socket_path;
+
+    let path = socket_path().ok_or("ERROR: could not figure out where the socket is")?;
+    let mut stream = UnixStream::connect(&path).map_err(|error| {
+        format!(
+            "ERROR: cli-todo doesn't seem to be running ({}: {})",
+            path.display(),
+            error
+        )
+    })?;
+    let request = Value::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", Value::Number(1.0)),
+        ("method", method.into()),
+        ("params", params),
+    ]);
+    writeln!(stream, "{}", request).map_err(|error| format!("ERROR: {}", error))?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(|error| format!("ERROR: {}", error))?;
+    if line.is_empty() {
+        return Err("ERROR: cli-todo closed the connection".to_string());
+    }
+    let response = Value::parse(&line).map_err(|error| format!("ERROR: {}", error))?;
+    if let Some(error) = response.get("error") {
+        let message = error.get("message").and_then(Value::as_str).unwrap_or("");
+        return Err(format!("ERROR: {}", message));
+    }
+    Ok(response.get("result").cloned().unwrap_or(Value::Null))
+}
+
+#[cfg(not(unix))]
+fn call(_method: &str, _params: Value) -> Result<Value, String> {
+    Err("ERROR: todo-ctl needs Unix domain sockets".to_string())
+}
+
+// Prints the items of both lists, only those matching `pattern` when there is one.
+fn list(pattern: Option<&str>) -> Result<Value, String> {
+    let search = pattern
+        .map(|pattern| Search::parse(pattern, Case::default()))
+        .transpose()
+        .map_err(|error| format!("ERROR: {}", error))?;
+    let result = call("list", Value::Null)?;
+    for (key, mark) in [("todos", ' '), ("dones", 'x')] {
+        if let Some(Value::Array(items)) = result.get(key) {
+            for item in items.iter().filter_map(Value::as_str) {
+                if search.as_ref().is_none_or(|search| search.matches(item)) {
+                    println!("- [{}] {}", mark, item);
+                }
+            }
+        }
+    }
+    Ok(Value::Null)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some((command, rest)) = args.split_first() else {
+        usage();
+    };
+    let title = rest.join(" ");
+    let result = match (command.as_str(), rest.is_empty()) {
+        ("add", false) if rest[0] == "--template" => {
+            let name = rest[1..].join(" ");
+            if name.is_empty() {
+                usage();
+            }
+            call("template", Value::object([("name", name.as_str().into())])).map(|count| {
+                println!("Added {} items", count);
+                Value::Null
+            })
+        }
+        ("add", false) => call("add", Value::object([("title", title.as_str().into())])),
+        ("done", false) => call(
+            "complete",
+            Value::object([("title", title.as_str().into())]),
+        ),
+        ("list", true) => list(None),
+        ("list", false) if rest[0] == "--search" && rest.len() == 2 => list(Some(&rest[1])),
+        ("search", false) => list(Some(&title)),
+        _ => usage(),
+    };
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}